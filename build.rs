@@ -0,0 +1,55 @@
+//! Build script: embeds the git commit for `railway_exporter_build_info`.
+//!
+//! Uses the `GIT_SHA` environment variable if set (Docker builds without
+//! `.git`), otherwise asks git. Falls back to `unknown`.
+//!
+//! With the `embedded-dashboard` feature it also checks that the dashboard's
+//! static export exists, so a fresh checkout fails with a pointer to the
+//! build command instead of a derive error.
+
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    if Path::new(".git/HEAD").exists() {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+        println!("cargo:rerun-if-changed=.git/refs/heads");
+    }
+
+    let sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .or_else(git_sha)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_SHA={}", sha);
+
+    if std::env::var_os("CARGO_FEATURE_EMBEDDED_DASHBOARD").is_some() {
+        check_dashboard_export();
+    }
+}
+
+fn check_dashboard_export() {
+    let index = Path::new("dashboard/out/index.html");
+    println!("cargo:rerun-if-changed={}", index.display());
+    if !index.exists() {
+        eprintln!(
+            "error: the embedded-dashboard feature needs the dashboard's static export \
+             in dashboard/out, build it first:\n\n    \
+             (cd dashboard && npm ci && npm run build:static)\n"
+        );
+        std::process::exit(1);
+    }
+}
+
+fn git_sha() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let sha = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!sha.is_empty()).then_some(sha)
+}
@@ -0,0 +1,21 @@
+#!/bin/sh
+# Builds the CSR-only static export into out/ (served by the exporter from
+# static_dir, or compiled in with `cargo build --features embedded-dashboard`).
+#
+# Swaps in the export config and page for the build and restores the SSR
+# versions afterwards.
+set -eu
+cd "$(dirname "$0")/.."
+
+backup=$(mktemp -d)
+cp next.config.ts src/app/page.tsx "$backup/"
+restore() {
+  cp "$backup/next.config.ts" next.config.ts
+  cp "$backup/page.tsx" src/app/page.tsx
+  rm -rf "$backup"
+}
+trap restore EXIT
+
+cp static-export/next.config.ts next.config.ts
+cp static-export/page.tsx src/app/page.tsx
+npx next build
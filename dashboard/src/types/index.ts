@@ -128,6 +128,16 @@ export interface ApiStatusResponse {
       min_size: number
       level: number
     }
+    brotli: {
+      enabled: boolean
+      min_size: number
+      level: number
+    }
+    zstd: {
+      enabled: boolean
+      min_size: number
+      level: number
+    }
     icon_cache: {
       enabled: boolean
       mode: 'base64' | 'link'
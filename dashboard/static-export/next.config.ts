@@ -0,0 +1,10 @@
+import type { NextConfig } from "next";
+
+// Static export served by the exporter (static_dir or embedded-dashboard)
+const nextConfig: NextConfig = {
+  output: 'export',
+  trailingSlash: true,
+  images: { unoptimized: true },
+};
+
+export default nextConfig;
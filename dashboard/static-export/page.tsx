@@ -0,0 +1,29 @@
+'use client'
+
+import { Dashboard } from '@/components/Dashboard/Dashboard'
+import { LANGUAGE_CODES, type Language } from '@/i18n/keys'
+import type { InitialData } from '@/lib/api.server'
+import { useSearchParams } from 'next/navigation'
+import { Suspense } from 'react'
+
+// API host defaults to same origin in static export
+const API_HOST = typeof window !== 'undefined' ? window.location.host : ''
+
+// Empty initial data - Dashboard will fetch client-side
+const emptyInitialData: InitialData = { metrics: null, serverStatus: null, error: null }
+
+function DashboardWithParams() {
+  const searchParams = useSearchParams()
+  const lang = searchParams.get('lang')
+  const initialLang = (LANGUAGE_CODES.includes(lang as Language) ? lang : 'en') as Language
+
+  return <Dashboard apiHost={API_HOST} initialData={emptyInitialData} initialLang={initialLang} />
+}
+
+export default function Home() {
+  return (
+    <Suspense fallback={<div>Loading...</div>}>
+      <DashboardWithParams />
+    </Suspense>
+  )
+}
@@ -0,0 +1,291 @@
+//! HTTP access log.
+//!
+//! With an `access_log` section every request answered by the server is
+//! written as one line, either as JSON or in Common Log Format:
+//!
+//! - `json` — `time`, `client_ip`, `user`, `method`, `path`, `protocol`,
+//!   `status`, `bytes`, `duration_ms`, `encoding`, `referer`, `user_agent`
+//! - `common` — `client - user [time] "METHOD path HTTP/1.1" status bytes`
+//! - `combined` — `common` plus the quoted referer and user agent
+//!
+//! Query strings are left out, since `/ws` may carry a token in them. `bytes`
+//! is the body size as sent (after compression); `user` is the name from `auth`.
+//!
+//! Behind a reverse proxy the connection comes from the proxy, so the client
+//! address is taken from `X-Forwarded-For` when the peer is one of the
+//! top-level `trusted_proxies` (see `auth::TrustedProxies`). Unix socket
+//! connections have no peer address and never match.
+//!
+//! `sample_rate` logs that fraction of successful requests, spread evenly;
+//! errors (status 400 and above) are always logged. Paths in `exclude_paths`
+//! (exact or `/prefix/*`) are never logged.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! access_log:
+//!   format: json              # json | common | combined
+//!   output: stdout            # stdout | stderr | file path (appended)
+//!   sample_rate: 1.0
+//!   exclude_paths: [/health]
+//! ```
+
+use crate::auth::path_matches;
+use crate::config::ConfigError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+/// Line format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogFormat {
+    /// One JSON object per line.
+    #[default]
+    Json,
+    /// Common Log Format.
+    Common,
+    /// Common Log Format with referer and user agent.
+    Combined,
+}
+
+/// Access log section as written in YAML.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AccessLogConfig {
+    /// Write access log lines.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Line format.
+    #[serde(default)]
+    pub format: AccessLogFormat,
+    /// `stdout`, `stderr` or a file path.
+    #[serde(default = "default_output")]
+    pub output: String,
+    /// Fraction of successful requests to log (0.0-1.0).
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+    /// Paths never logged (exact or `/prefix/*`).
+    #[serde(default)]
+    pub exclude_paths: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_output() -> String {
+    "stdout".to_string()
+}
+
+fn default_sample_rate() -> f64 {
+    1.0
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            format: AccessLogFormat::default(),
+            output: default_output(),
+            sample_rate: default_sample_rate(),
+            exclude_paths: Vec::new(),
+        }
+    }
+}
+
+/// Where lines are written.
+#[derive(Debug, Clone)]
+enum Output {
+    Stdout,
+    Stderr,
+    File(Arc<Mutex<File>>),
+}
+
+impl Output {
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        match self {
+            Output::Stdout => io::stdout().lock().write_all(line.as_bytes()),
+            Output::Stderr => io::stderr().lock().write_all(line.as_bytes()),
+            Output::File(file) => file
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .write_all(line.as_bytes()),
+        }
+    }
+}
+
+/// One answered request.
+#[derive(Debug, Clone)]
+pub struct AccessLogEntry<'a> {
+    pub time: DateTime<Utc>,
+    pub client_ip: Option<IpAddr>,
+    /// Authenticated user or token name.
+    pub user: Option<&'a str>,
+    pub method: &'a str,
+    pub path: &'a str,
+    pub protocol: &'a str,
+    pub status: u16,
+    /// Body size as sent.
+    pub bytes: u64,
+    pub duration: Duration,
+    /// `Content-Encoding` of the body, if compressed.
+    pub encoding: Option<&'a str>,
+    pub referer: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+}
+
+/// Compiled access log.
+#[derive(Debug, Clone)]
+pub struct AccessLog {
+    format: AccessLogFormat,
+    output: Output,
+    sample_rate: f64,
+    /// Successful requests seen so far (for sampling).
+    sampled: Arc<AtomicU64>,
+    exclude_paths: Vec<String>,
+}
+
+impl AccessLog {
+    /// Validates the section and opens the output.
+    pub fn new(config: AccessLogConfig) -> Result<Self, ConfigError> {
+        let err = |msg: String| ConfigError::InvalidValue(format!("access_log: {}", msg));
+
+        if !(0.0..=1.0).contains(&config.sample_rate) {
+            return Err(err(format!(
+                "sample_rate must be between 0.0 and 1.0, got {}",
+                config.sample_rate
+            )));
+        }
+        if let Some(path) = config.exclude_paths.iter().find(|p| !p.starts_with('/')) {
+            return Err(err(format!(
+                "exclude_paths: '{}' must start with '/'",
+                path
+            )));
+        }
+        let output = match config.output.trim() {
+            "" => return Err(err("output must not be empty".to_string())),
+            "stdout" => Output::Stdout,
+            "stderr" => Output::Stderr,
+            path => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| err(format!("cannot open '{}': {}", path, e)))?;
+                Output::File(Arc::new(Mutex::new(file)))
+            }
+        };
+
+        Ok(Self {
+            format: config.format,
+            output,
+            sample_rate: config.sample_rate,
+            sampled: Arc::new(AtomicU64::new(0)),
+            exclude_paths: config.exclude_paths,
+        })
+    }
+
+    /// Returns true if a request to `path` answered with `status` is logged.
+    ///
+    /// Successful requests advance the sampling counter.
+    pub fn should_log(&self, path: &str, status: u16) -> bool {
+        if self.exclude_paths.iter().any(|p| path_matches(p, path)) {
+            return false;
+        }
+        if status >= 400 || self.sample_rate >= 1.0 {
+            return true;
+        }
+        // Log request n when the running total of sample_rate crosses an integer
+        let n = self.sampled.fetch_add(1, Ordering::Relaxed);
+        (((n + 1) as f64) * self.sample_rate).floor() > ((n as f64) * self.sample_rate).floor()
+    }
+
+    /// Renders one line (with trailing newline).
+    pub fn format(&self, entry: &AccessLogEntry) -> String {
+        match self.format {
+            AccessLogFormat::Json => format_json(entry),
+            AccessLogFormat::Common => format_common(entry, false),
+            AccessLogFormat::Combined => format_common(entry, true),
+        }
+    }
+
+    /// Writes the entry unless it is excluded or sampled out.
+    pub fn log(&self, entry: &AccessLogEntry) {
+        if !self.should_log(entry.path, entry.status) {
+            return;
+        }
+        if let Err(e) = self.output.write_line(&self.format(entry)) {
+            warn!("Failed to write access log: {}", e);
+        }
+    }
+}
+
+fn format_json(entry: &AccessLogEntry) -> String {
+    let duration_ms = (entry.duration.as_secs_f64() * 1_000_000.0).round() / 1000.0;
+    let line = serde_json::json!({
+        "time": entry.time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "client_ip": entry.client_ip.map(|ip| ip.to_canonical().to_string()),
+        "user": entry.user,
+        "method": entry.method,
+        "path": entry.path,
+        "protocol": entry.protocol,
+        "status": entry.status,
+        "bytes": entry.bytes,
+        "duration_ms": duration_ms,
+        "encoding": entry.encoding,
+        "referer": entry.referer,
+        "user_agent": entry.user_agent,
+    });
+    format!("{}\n", line)
+}
+
+fn format_common(entry: &AccessLogEntry, combined: bool) -> String {
+    let client = entry
+        .client_ip
+        .map_or_else(|| "-".to_string(), |ip| ip.to_canonical().to_string());
+    let mut line = format!(
+        "{} - {} [{}] \"{} {} {}\" {} {}",
+        client,
+        entry.user.map_or_else(|| "-".to_string(), escape),
+        entry.time.format("%d/%b/%Y:%H:%M:%S %z"),
+        escape(entry.method),
+        escape(entry.path),
+        entry.protocol,
+        entry.status,
+        entry.bytes,
+    );
+    if combined {
+        let _ = write!(
+            line,
+            " \"{}\" \"{}\"",
+            entry.referer.map_or_else(|| "-".to_string(), escape),
+            entry.user_agent.map_or_else(|| "-".to_string(), escape),
+        );
+    }
+    line.push('\n');
+    line
+}
+
+/// Escapes quotes, backslashes and control characters so fields cannot
+/// break the line apart.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\x{:02x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
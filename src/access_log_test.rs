@@ -0,0 +1,197 @@
+//! Tests for the access log.
+
+use crate::access_log::{AccessLog, AccessLogConfig, AccessLogEntry, AccessLogFormat};
+use crate::auth::{Auth, AuthConfig, TrustedProxies};
+use crate::config::{Plan, YamlConfig};
+use crate::test_support::start_test_server;
+use crate::Config;
+use chrono::{TimeZone, Utc};
+use std::time::Duration;
+
+fn access_log(yaml: &str) -> AccessLog {
+    AccessLog::new(serde_yaml::from_str::<AccessLogConfig>(yaml).unwrap()).unwrap()
+}
+
+fn entry() -> AccessLogEntry<'static> {
+    AccessLogEntry {
+        time: Utc.with_ymd_and_hms(2026, 3, 5, 14, 7, 9).unwrap(),
+        client_ip: Some("203.0.113.7".parse().unwrap()),
+        user: Some("grafana"),
+        method: "GET",
+        path: "/metrics",
+        protocol: "HTTP/1.1",
+        status: 200,
+        bytes: 5120,
+        duration: Duration::from_micros(1_234_567),
+        encoding: Some("br"),
+        referer: None,
+        user_agent: Some("Prometheus/2.51"),
+    }
+}
+
+fn log_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("exporter-{}-{}.log", name, std::process::id()))
+}
+
+// =============================================================================
+// Config Tests
+// =============================================================================
+
+#[test]
+fn test_validation() {
+    let invalid = [
+        "sample_rate: 1.5",
+        "sample_rate: -0.1",
+        "exclude_paths: [health]",
+        "output: \"\"",
+        "output: /nonexistent-dir/access.log",
+    ];
+    for yaml in invalid {
+        let config: AccessLogConfig = serde_yaml::from_str(yaml).unwrap();
+        assert!(AccessLog::new(config).is_err(), "{}", yaml);
+    }
+}
+
+#[test]
+fn test_yaml_defaults() {
+    let yaml = "access_log:\n  exclude_paths: [/health]";
+    let config: YamlConfig = serde_yaml::from_str(yaml).unwrap();
+    let section = config.access_log.unwrap();
+    assert!(section.enabled);
+    assert_eq!(section.format, AccessLogFormat::Json);
+    assert_eq!(section.output, "stdout");
+    assert_eq!(section.sample_rate, 1.0);
+    assert_eq!(section.exclude_paths, ["/health"]);
+}
+
+// =============================================================================
+// Format Tests
+// =============================================================================
+
+#[test]
+fn test_format_json() {
+    let line = access_log("format: json").format(&entry());
+    assert!(line.ends_with('\n'));
+    let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(json["time"], "2026-03-05T14:07:09.000Z");
+    assert_eq!(json["client_ip"], "203.0.113.7");
+    assert_eq!(json["user"], "grafana");
+    assert_eq!(json["method"], "GET");
+    assert_eq!(json["path"], "/metrics");
+    assert_eq!(json["status"], 200);
+    assert_eq!(json["bytes"], 5120);
+    assert_eq!(json["duration_ms"], 1234.567);
+    assert_eq!(json["encoding"], "br");
+    assert_eq!(json["referer"], serde_json::Value::Null);
+    assert_eq!(json["user_agent"], "Prometheus/2.51");
+}
+
+#[test]
+fn test_format_common() {
+    let line = access_log("format: common").format(&entry());
+    assert_eq!(
+        line,
+        "203.0.113.7 - grafana [05/Mar/2026:14:07:09 +0000] \"GET /metrics HTTP/1.1\" 200 5120\n"
+    );
+}
+
+#[test]
+fn test_format_combined() {
+    let mut entry = entry();
+    entry.client_ip = None;
+    entry.user = None;
+    entry.user_agent = Some("evil\" 200 0\nfake");
+    let line = access_log("format: combined").format(&entry);
+    assert_eq!(
+        line,
+        "- - - [05/Mar/2026:14:07:09 +0000] \"GET /metrics HTTP/1.1\" 200 5120 \"-\" \"evil\\\" 200 0\\x0afake\"\n"
+    );
+}
+
+// =============================================================================
+// Filtering Tests
+// =============================================================================
+
+#[test]
+fn test_exclude_paths() {
+    let log = access_log("exclude_paths: [/health, /icons/*]");
+    assert!(!log.should_log("/health", 200));
+    assert!(!log.should_log("/icons/services/web", 200));
+    assert!(!log.should_log("/health", 503));
+    assert!(log.should_log("/metrics", 200));
+    assert!(log.should_log("/healthz", 200));
+}
+
+#[test]
+fn test_sampling() {
+    let log = access_log("sample_rate: 0.25");
+    let logged = (0..100).filter(|_| log.should_log("/metrics", 200)).count();
+    assert_eq!(logged, 25);
+
+    // Errors are always logged
+    assert!((0..10).all(|_| log.should_log("/metrics", 500)));
+    assert!((0..10).all(|_| log.should_log("/metrics", 401)));
+
+    let none = access_log("sample_rate: 0.0");
+    assert!(!(0..100).any(|_| none.should_log("/metrics", 200)));
+}
+
+// =============================================================================
+// Server Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_server_writes_access_log() {
+    let path = log_path("server");
+    let _ = std::fs::remove_file(&path);
+
+    let mut config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
+    config.access_log = Some(access_log(&format!(
+        "output: {}\nexclude_paths: [/health]",
+        path.display()
+    )));
+    config.auth = Some(
+        Auth::new(
+            serde_yaml::from_str::<AuthConfig>("tokens:\n  grafana: secret").unwrap(),
+            &TrustedProxies::default(),
+        )
+        .unwrap(),
+    );
+    config.trusted_proxies = TrustedProxies::new(&["127.0.0.1".to_string()]).unwrap();
+    let url = format!("http://{}", start_test_server(config).await);
+    let client = reqwest::Client::new();
+
+    client.get(format!("{}/health", url)).send().await.unwrap();
+    let status = client
+        .get(format!("{}/status?token=secret", url))
+        .bearer_auth("secret")
+        .header("User-Agent", "test-agent")
+        .header("X-Forwarded-For", "203.0.113.7")
+        .send()
+        .await
+        .unwrap();
+    let body = status.bytes().await.unwrap();
+    client.get(format!("{}/status", url)).send().await.unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<serde_json::Value> = contents
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2, "{}", contents);
+
+    assert_eq!(lines[0]["path"], "/status");
+    assert_eq!(lines[0]["status"], 200);
+    assert_eq!(lines[0]["user"], "grafana");
+    assert_eq!(lines[0]["client_ip"], "203.0.113.7");
+    assert_eq!(lines[0]["user_agent"], "test-agent");
+    assert_eq!(lines[0]["bytes"], body.len());
+    assert_eq!(lines[0]["protocol"], "HTTP/1.1");
+    assert!(!contents.contains("secret"));
+
+    assert_eq!(lines[1]["status"], 401);
+    assert_eq!(lines[1]["user"], serde_json::Value::Null);
+    assert_eq!(lines[1]["client_ip"], "127.0.0.1");
+
+    std::fs::remove_file(&path).unwrap();
+}
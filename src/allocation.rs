@@ -0,0 +1,168 @@
+//! Shared-cost redistribution between service groups.
+//!
+//! Platform services (databases, caches, monitoring) are often shared by several
+//! product groups. Allocation rules move a group's cost onto its consumers so
+//! chargeback reflects who actually uses the platform.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! cost_allocation:
+//!   # Split database cost 2:1 between api and worker
+//!   - source: database
+//!     targets: [api, worker]
+//!     method: weighted
+//!     weights: { api: 2, worker: 1 }
+//!   # Split monitoring cost by each group's own spend
+//!   - source: monitoring
+//!     targets: [api, worker, database]
+//!     method: proportional   # proportional (default) | weighted | even
+//! ```
+//!
+//! Rules run in order on the already-allocated amounts, so a later rule
+//! redistributes whatever earlier rules moved into its source group.
+//! Raw `railway_service_cost_usd` / `railway_group_cost_usd` are unaffected.
+//!
+//! With `service_groups_multi`, a service in several groups counts in full in
+//! each raw group rollup, but allocation starts from an even split of its
+//! cost between its groups, so allocated totals add up to the project cost.
+
+use crate::config::ConfigError;
+use crate::types::{AllocatedGroup, GroupSummary};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How a source group's cost is split between targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AllocationMethod {
+    /// Proportional to each target's own (direct) cost.
+    /// Falls back to an even split when all targets have zero cost.
+    #[default]
+    Proportional,
+    /// Fixed weights from `weights`.
+    Weighted,
+    /// Equal share for every target.
+    Even,
+}
+
+/// A single redistribution rule.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AllocationRule {
+    /// Group whose cost is redistributed.
+    pub source: String,
+    /// Groups receiving the cost.
+    pub targets: Vec<String>,
+    /// Split method.
+    #[serde(default)]
+    pub method: AllocationMethod,
+    /// Per-target weights (required for `weighted`).
+    #[serde(default)]
+    pub weights: HashMap<String, f64>,
+}
+
+/// Validates allocation rules at config load time.
+pub fn validate(rules: &[AllocationRule]) -> Result<(), ConfigError> {
+    for (i, rule) in rules.iter().enumerate() {
+        let err = |msg: String| {
+            ConfigError::InvalidValue(format!("cost_allocation[{}] ({}): {}", i, rule.source, msg))
+        };
+        if rule.targets.is_empty() {
+            return Err(err("targets must not be empty".to_string()));
+        }
+        if rule.targets.contains(&rule.source) {
+            return Err(err("source must not be one of its targets".to_string()));
+        }
+        if rule.method == AllocationMethod::Weighted {
+            for target in &rule.targets {
+                match rule.weights.get(target) {
+                    Some(w) if w.is_finite() && *w >= 0.0 => {}
+                    Some(_) => {
+                        return Err(err(format!("weight for '{}' must be >= 0", target)));
+                    }
+                    None => return Err(err(format!("missing weight for '{}'", target))),
+                }
+            }
+            if rule.targets.iter().all(|t| rule.weights[t] == 0.0) {
+                return Err(err("at least one weight must be > 0".to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Applies allocation rules to group rollups.
+///
+/// Returns one entry per group, in the same order as `groups`. Targets that
+/// had no services in this scrape are skipped; if no target is left, the
+/// source keeps its cost.
+pub fn allocate(groups: &[GroupSummary], rules: &[AllocationRule]) -> Vec<AllocatedGroup> {
+    let mut allocated: Vec<AllocatedGroup> = groups
+        .iter()
+        .map(|g| AllocatedGroup {
+            name: g.name.clone(),
+            direct_cost_usd: g.cost_usd,
+            received_usd: 0.0,
+            distributed_usd: 0.0,
+            allocated_cost_usd: g.cost_usd,
+            allocated_estimated_monthly_usd: g.estimated_monthly_usd,
+        })
+        .collect();
+
+    let index: HashMap<String, usize> = allocated
+        .iter()
+        .enumerate()
+        .map(|(i, g)| (g.name.clone(), i))
+        .collect();
+
+    for rule in rules {
+        let Some(&src) = index.get(&rule.source) else {
+            continue;
+        };
+        let targets: Vec<usize> = rule
+            .targets
+            .iter()
+            .filter_map(|t| index.get(t).copied())
+            .collect();
+
+        let shares = split(rule, &targets, &allocated);
+        let total: f64 = shares.iter().sum();
+        if total <= 0.0 {
+            continue;
+        }
+
+        let cost = allocated[src].allocated_cost_usd;
+        let monthly = allocated[src].allocated_estimated_monthly_usd;
+        for (&t, share) in targets.iter().zip(&shares) {
+            let ratio = share / total;
+            allocated[t].received_usd += cost * ratio;
+            allocated[t].allocated_cost_usd += cost * ratio;
+            allocated[t].allocated_estimated_monthly_usd += monthly * ratio;
+        }
+        allocated[src].distributed_usd += cost;
+        allocated[src].allocated_cost_usd = 0.0;
+        allocated[src].allocated_estimated_monthly_usd = 0.0;
+    }
+
+    allocated
+}
+
+/// Raw (unnormalized) share per target index.
+fn split(rule: &AllocationRule, targets: &[usize], groups: &[AllocatedGroup]) -> Vec<f64> {
+    let even = || vec![1.0; targets.len()];
+    match rule.method {
+        AllocationMethod::Even => even(),
+        AllocationMethod::Weighted => targets
+            .iter()
+            .map(|&t| rule.weights.get(&groups[t].name).copied().unwrap_or(0.0))
+            .collect(),
+        AllocationMethod::Proportional => {
+            let own: Vec<f64> = targets.iter().map(|&t| groups[t].direct_cost_usd).collect();
+            if own.iter().sum::<f64>() > 0.0 {
+                own
+            } else {
+                even()
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Tests for shared-cost redistribution.
+
+use crate::allocation::{allocate, validate, AllocationMethod, AllocationRule};
+use crate::types::GroupSummary;
+use std::collections::HashMap;
+
+fn group(name: &str, cost: f64) -> GroupSummary {
+    GroupSummary {
+        name: name.to_string(),
+        service_count: 1,
+        cpu_usage: 0.0,
+        memory_usage: 0.0,
+        disk_usage: 0.0,
+        network_tx: 0.0,
+        cost_usd: cost,
+        estimated_monthly_usd: cost * 10.0,
+        cost_share: 0.0,
+    }
+}
+
+fn rule(source: &str, targets: &[&str], method: AllocationMethod) -> AllocationRule {
+    AllocationRule {
+        source: source.to_string(),
+        targets: targets.iter().map(|t| t.to_string()).collect(),
+        method,
+        weights: HashMap::new(),
+    }
+}
+
+fn find<'a>(
+    allocated: &'a [crate::types::AllocatedGroup],
+    name: &str,
+) -> &'a crate::types::AllocatedGroup {
+    allocated.iter().find(|g| g.name == name).unwrap()
+}
+
+// =============================================================================
+// allocate Tests
+// =============================================================================
+
+#[test]
+fn test_allocate_proportional() {
+    let groups = vec![
+        group("database", 6.0),
+        group("api", 3.0),
+        group("worker", 1.0),
+    ];
+    let rules = vec![rule(
+        "database",
+        &["api", "worker"],
+        AllocationMethod::Proportional,
+    )];
+
+    let allocated = allocate(&groups, &rules);
+
+    let db = find(&allocated, "database");
+    assert_eq!(db.direct_cost_usd, 6.0);
+    assert_eq!(db.distributed_usd, 6.0);
+    assert_eq!(db.allocated_cost_usd, 0.0);
+    assert_eq!(db.allocated_estimated_monthly_usd, 0.0);
+
+    let api = find(&allocated, "api");
+    assert!((api.received_usd - 4.5).abs() < 1e-9);
+    assert!((api.allocated_cost_usd - 7.5).abs() < 1e-9);
+    assert!((api.allocated_estimated_monthly_usd - 75.0).abs() < 1e-9);
+
+    let worker = find(&allocated, "worker");
+    assert!((worker.allocated_cost_usd - 2.5).abs() < 1e-9);
+
+    // Total spend is preserved
+    let total: f64 = allocated.iter().map(|g| g.allocated_cost_usd).sum();
+    assert!((total - 10.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_allocate_weighted() {
+    let groups = vec![
+        group("database", 3.0),
+        group("api", 0.0),
+        group("worker", 0.0),
+    ];
+    let mut r = rule("database", &["api", "worker"], AllocationMethod::Weighted);
+    r.weights.insert("api".to_string(), 2.0);
+    r.weights.insert("worker".to_string(), 1.0);
+
+    let allocated = allocate(&groups, &[r]);
+
+    assert!((find(&allocated, "api").allocated_cost_usd - 2.0).abs() < 1e-9);
+    assert!((find(&allocated, "worker").allocated_cost_usd - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_allocate_proportional_falls_back_to_even() {
+    let groups = vec![
+        group("database", 4.0),
+        group("api", 0.0),
+        group("worker", 0.0),
+    ];
+    let rules = vec![rule(
+        "database",
+        &["api", "worker"],
+        AllocationMethod::Proportional,
+    )];
+
+    let allocated = allocate(&groups, &rules);
+
+    assert_eq!(find(&allocated, "api").allocated_cost_usd, 2.0);
+    assert_eq!(find(&allocated, "worker").allocated_cost_usd, 2.0);
+}
+
+#[test]
+fn test_allocate_chained_rules() {
+    let groups = vec![
+        group("monitoring", 2.0),
+        group("database", 2.0),
+        group("api", 1.0),
+    ];
+    let rules = vec![
+        rule("monitoring", &["database"], AllocationMethod::Even),
+        rule("database", &["api"], AllocationMethod::Even),
+    ];
+
+    let allocated = allocate(&groups, &rules);
+
+    assert_eq!(find(&allocated, "monitoring").allocated_cost_usd, 0.0);
+    assert_eq!(find(&allocated, "database").allocated_cost_usd, 0.0);
+    assert_eq!(find(&allocated, "database").received_usd, 2.0);
+    assert_eq!(find(&allocated, "database").distributed_usd, 4.0);
+    assert_eq!(find(&allocated, "api").allocated_cost_usd, 5.0);
+}
+
+#[test]
+fn test_allocate_missing_groups_keep_cost() {
+    let groups = vec![group("database", 4.0)];
+    let rules = vec![
+        rule("database", &["api"], AllocationMethod::Even),
+        rule("cache", &["database"], AllocationMethod::Even),
+    ];
+
+    let allocated = allocate(&groups, &rules);
+
+    assert_eq!(allocated.len(), 1);
+    assert_eq!(allocated[0].allocated_cost_usd, 4.0);
+    assert_eq!(allocated[0].distributed_usd, 0.0);
+}
+
+// =============================================================================
+// validate Tests
+// =============================================================================
+
+#[test]
+fn test_validate_rules() {
+    assert!(validate(&[rule("db", &["api"], AllocationMethod::Even)]).is_ok());
+    assert!(validate(&[rule("db", &[], AllocationMethod::Even)]).is_err());
+    assert!(validate(&[rule("db", &["db", "api"], AllocationMethod::Even)]).is_err());
+
+    let mut weighted = rule("db", &["api", "worker"], AllocationMethod::Weighted);
+    weighted.weights.insert("api".to_string(), 1.0);
+    assert!(validate(std::slice::from_ref(&weighted)).is_err()); // worker missing
+
+    weighted.weights.insert("worker".to_string(), -1.0);
+    assert!(validate(std::slice::from_ref(&weighted)).is_err());
+
+    weighted.weights.insert("worker".to_string(), 0.0);
+    assert!(validate(&[weighted]).is_ok());
+}
+
+#[test]
+fn test_allocation_rule_deserialize() {
+    let yaml = r#"
+- source: database
+  targets: [api, worker]
+  method: weighted
+  weights: { api: 2, worker: 1 }
+- source: monitoring
+  targets: [api]
+"#;
+    let rules: Vec<AllocationRule> = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(rules[0].method, AllocationMethod::Weighted);
+    assert_eq!(rules[0].weights["api"], 2.0);
+    assert_eq!(rules[1].method, AllocationMethod::Proportional);
+    assert!(validate(&rules).is_ok());
+}
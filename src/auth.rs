@@ -0,0 +1,620 @@
+//! Authentication for HTTP and WebSocket endpoints.
+//!
+//! Without an `auth` section every endpoint is open. With one, each request
+//! must pass one of the methods allowed for its route:
+//!
+//! - `bearer` — `Authorization: Bearer <token>` with one of `tokens`
+//! - `basic` — `Authorization: Basic ...` checked against the bcrypt hashes in
+//!   `users` (`htpasswd -nBC 10 <user>`)
+//! - `proxy` — user name taken from `trusted_proxy.header`, only honored on
+//!   connections from one of the top-level `trusted_proxies`
+//!
+//! Routes are matched in order, by exact path or by prefix (`/icons/*`).
+//! Requests to paths without a rule need one of the `default` methods (every
+//! configured method if unset); `methods: []` leaves a route open.
+//!
+//! Browsers cannot set headers on WebSocket connections, so `/ws` also accepts
+//! the bearer token as `?token=<token>` or as a `bearer.<token>` entry in
+//! `Sec-WebSocket-Protocol`:
+//!
+//! ```text
+//! new WebSocket(url, ["railway-exporter", "bearer." + token])
+//! ```
+//!
+//! Successful basic auth logins are remembered per user (as a SHA-256 digest
+//! of the password), so only the first request pays for bcrypt. After
+//! `MAX_FAILURES` wrong passwords in a row, a user is locked out for
+//! `LOCKOUT` from that client (the address resolved through
+//! `trusted_proxies`) without running bcrypt, and at most one bcrypt check per
+//! CPU runs at a time. Pair it with `limits.rate_limit` against attackers
+//! spread over many addresses.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! trusted_proxies: ["10.0.0.0/8", "127.0.0.1"]   # top level, shared
+//!
+//! auth:
+//!   tokens:
+//!     grafana: "long-random-token"
+//!   users:
+//!     prometheus: "$2y$10$..."
+//!   trusted_proxy:
+//!     header: X-Forwarded-User         # default
+//!   default: [basic, bearer]           # routes without a rule
+//!   routes:
+//!     - path: /health
+//!       methods: []                    # open
+//!     - path: /metrics
+//!       methods: [basic]
+//!     - path: /ws
+//!       methods: [bearer]
+//! ```
+
+use crate::config::ConfigError;
+use crate::handlers::HandlerResponse;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hyper::body::Bytes;
+use hyper::header::{HeaderMap, HeaderName};
+use hyper::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tracing::{debug, warn};
+
+/// Consecutive wrong passwords per user and client before the lockout.
+pub const MAX_FAILURES: u32 = 5;
+
+/// How long a locked-out user and client pair is refused without bcrypt.
+pub const LOCKOUT: Duration = Duration::from_secs(60);
+
+/// Tracked user and client pairs; expired entries are dropped when full.
+const MAX_TRACKED_FAILURES: usize = 4096;
+
+/// Realm announced in `WWW-Authenticate` challenges.
+const REALM: &str = "railway-exporter";
+
+/// Prefix of the `Sec-WebSocket-Protocol` entry carrying a bearer token.
+pub const WS_TOKEN_PROTOCOL: &str = "bearer.";
+
+/// A way of proving access to a route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMethod {
+    /// `Authorization: Bearer` token.
+    Bearer,
+    /// `Authorization: Basic` with a bcrypt-hashed password.
+    Basic,
+    /// User header set by a trusted reverse proxy.
+    Proxy,
+}
+
+impl fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthMethod::Bearer => write!(f, "bearer"),
+            AuthMethod::Basic => write!(f, "basic"),
+            AuthMethod::Proxy => write!(f, "proxy"),
+        }
+    }
+}
+
+/// Reverse proxy authentication settings (the proxies themselves are the
+/// top-level `trusted_proxies`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrustedProxyConfig {
+    /// Header carrying the authenticated user name.
+    #[serde(default = "default_user_header")]
+    pub header: String,
+}
+
+fn default_user_header() -> String {
+    "X-Forwarded-User".to_string()
+}
+
+/// Methods required for requests to a path.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AuthRoute {
+    /// Exact path, or a prefix ending in `/*`.
+    pub path: String,
+    /// Accepted methods (empty = open).
+    pub methods: Vec<AuthMethod>,
+}
+
+/// Auth section as written in YAML.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AuthConfig {
+    /// Bearer tokens by name.
+    #[serde(default)]
+    pub tokens: BTreeMap<String, String>,
+    /// Basic auth users and their bcrypt password hashes.
+    #[serde(default)]
+    pub users: BTreeMap<String, String>,
+    #[serde(default)]
+    pub trusted_proxy: Option<TrustedProxyConfig>,
+    /// Methods for paths without a route rule (default: all configured).
+    #[serde(default)]
+    pub default: Option<Vec<AuthMethod>>,
+    #[serde(default)]
+    pub routes: Vec<AuthRoute>,
+}
+
+/// An IPv4 or IPv6 network in CIDR notation (a bare address is a /32 or /128).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' is not an IP address or network", s))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("invalid prefix length in '{}'", s))?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl IpNetwork {
+    /// Returns true if the address belongs to the network.
+    ///
+    /// IPv4-mapped IPv6 addresses (dual-stack listeners) compare as IPv4.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Matches a route pattern: an exact path or a prefix (`/icons/*`, which
+/// also matches `/icons`).
+pub(crate) fn path_matches(pattern: &str, path: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => path.starts_with(prefix) || Some(path) == prefix.strip_suffix('/'),
+        None => pattern == path,
+    }
+}
+
+/// Reverse proxies whose forwarding headers are trusted (top-level
+/// `trusted_proxies`), shared by auth, rate limits and the access log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies(Vec<IpNetwork>);
+
+impl TrustedProxies {
+    /// Parses IPs and CIDR networks.
+    pub fn new(proxies: &[String]) -> Result<Self, ConfigError> {
+        proxies
+            .iter()
+            .map(|p| {
+                p.parse()
+                    .map_err(|e| ConfigError::InvalidValue(format!("trusted_proxies: {}", e)))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Returns true if no proxy is trusted.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns true if the address is a trusted proxy.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(ip))
+    }
+
+    /// Client address behind reverse proxies.
+    ///
+    /// Starting from the peer, `X-Forwarded-For` entries are read from the
+    /// right while the current hop is trusted; the first untrusted address is
+    /// the client. Unparseable entries stop the walk.
+    pub fn client(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        forwarded_client(peer, headers, &self.0)
+    }
+}
+
+fn forwarded_client(
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    trusted: &[IpNetwork],
+) -> Option<IpAddr> {
+    let mut client = peer?;
+    let forwarded: Vec<&str> = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .collect();
+    for hop in forwarded.iter().rev() {
+        if !trusted.iter().any(|net| net.contains(client)) {
+            break;
+        }
+        // `ip`, `ip:port` or `[ipv6]:port`
+        let ip = hop
+            .parse::<IpAddr>()
+            .ok()
+            .or_else(|| hop.parse::<SocketAddr>().ok().map(|addr| addr.ip()));
+        match ip {
+            Some(ip) => client = ip,
+            None => break,
+        }
+    }
+    Some(client)
+}
+
+#[derive(Debug, Clone)]
+struct TrustedProxy {
+    header: HeaderName,
+}
+
+/// A user name and the client it was tried from.
+type FailureKey = (String, Option<IpAddr>);
+
+/// Wrong passwords for one user from one client.
+#[derive(Debug, Clone, Copy)]
+struct Failures {
+    count: u32,
+    last: Instant,
+}
+
+impl Failures {
+    fn locked(&self, now: Instant) -> bool {
+        self.count >= MAX_FAILURES && now.duration_since(self.last) < LOCKOUT
+    }
+}
+
+/// A request that failed authentication.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unauthorized {
+    methods: Vec<AuthMethod>,
+}
+
+impl Unauthorized {
+    /// `401` with a challenge per header-based method (`403` for proxy-only routes).
+    pub fn response(&self) -> HandlerResponse {
+        let mut builder = Response::builder().status(StatusCode::UNAUTHORIZED);
+        let mut challenged = false;
+        for method in &self.methods {
+            let challenge = match method {
+                AuthMethod::Basic => format!("Basic realm=\"{}\", charset=\"UTF-8\"", REALM),
+                AuthMethod::Bearer => format!("Bearer realm=\"{}\"", REALM),
+                AuthMethod::Proxy => continue,
+            };
+            builder = builder.header("WWW-Authenticate", challenge);
+            challenged = true;
+        }
+        if !challenged {
+            return (
+                Response::builder().status(StatusCode::FORBIDDEN),
+                Bytes::from("Forbidden"),
+            );
+        }
+        (builder, Bytes::from("Unauthorized"))
+    }
+}
+
+/// Compiled authentication rules.
+#[derive(Debug, Clone)]
+pub struct Auth {
+    tokens: Vec<(String, String)>,
+    users: HashMap<String, String>,
+    trusted_proxy: Option<TrustedProxy>,
+    proxies: TrustedProxies,
+    default: Vec<AuthMethod>,
+    routes: Vec<AuthRoute>,
+    /// SHA-256 of the last password that passed bcrypt, per user.
+    verified: Arc<Mutex<HashMap<String, [u8; 32]>>>,
+    /// Recent wrong passwords per user and client.
+    failures: Arc<Mutex<HashMap<FailureKey, Failures>>>,
+    /// Bounds concurrent bcrypt checks.
+    bcrypt_permits: Arc<Semaphore>,
+}
+
+impl Auth {
+    /// Validates the section and compiles it; `proxies` are the top-level
+    /// `trusted_proxies` used by the `proxy` method.
+    pub fn new(config: AuthConfig, proxies: &TrustedProxies) -> Result<Self, ConfigError> {
+        let err = |msg: String| ConfigError::InvalidValue(format!("auth: {}", msg));
+
+        for (name, token) in &config.tokens {
+            if token.is_empty() {
+                return Err(err(format!("token '{}' must not be empty", name)));
+            }
+        }
+        for (user, hash) in &config.users {
+            if user.contains(':') {
+                return Err(err(format!("user name '{}' must not contain ':'", user)));
+            }
+            bcrypt::HashParts::from_str(hash)
+                .map_err(|e| err(format!("user '{}': invalid bcrypt hash: {}", user, e)))?;
+        }
+        let trusted_proxy = match config.trusted_proxy {
+            Some(proxy) => {
+                let header = HeaderName::from_str(&proxy.header).map_err(|_| {
+                    err(format!(
+                        "trusted_proxy.header '{}' is invalid",
+                        proxy.header
+                    ))
+                })?;
+                if proxies.is_empty() {
+                    return Err(err(
+                        "trusted_proxy requires the top-level trusted_proxies".to_string()
+                    ));
+                }
+                Some(TrustedProxy { header })
+            }
+            None => None,
+        };
+
+        let configured = |method: &AuthMethod| match method {
+            AuthMethod::Bearer => !config.tokens.is_empty(),
+            AuthMethod::Basic => !config.users.is_empty(),
+            AuthMethod::Proxy => trusted_proxy.is_some(),
+        };
+        let all = [AuthMethod::Bearer, AuthMethod::Basic, AuthMethod::Proxy];
+        if !all.iter().any(configured) {
+            return Err(err(
+                "configure at least one of tokens, users or trusted_proxy".to_string(),
+            ));
+        }
+        let check = |methods: &[AuthMethod], location: &str| match methods
+            .iter()
+            .find(|m| !configured(m))
+        {
+            Some(method) => Err(err(format!(
+                "{} uses '{}' but it is not configured",
+                location, method
+            ))),
+            None => Ok(()),
+        };
+
+        let default = match config.default {
+            Some(default) => {
+                check(&default, "default")?;
+                default
+            }
+            None => all.into_iter().filter(configured).collect(),
+        };
+        for (i, route) in config.routes.iter().enumerate() {
+            if !route.path.starts_with('/') {
+                return Err(err(format!(
+                    "routes[{}]: path '{}' must start with '/'",
+                    i, route.path
+                )));
+            }
+            check(&route.methods, &format!("routes[{}]", i))?;
+        }
+
+        Ok(Self {
+            tokens: config.tokens.into_iter().collect(),
+            users: config.users.into_iter().collect(),
+            trusted_proxy,
+            proxies: proxies.clone(),
+            default,
+            routes: config.routes,
+            verified: Arc::new(Mutex::new(HashMap::new())),
+            failures: Arc::new(Mutex::new(HashMap::new())),
+            bcrypt_permits: Arc::new(Semaphore::new(
+                std::thread::available_parallelism().map_or(1, |n| n.get()),
+            )),
+        })
+    }
+
+    /// Methods accepted for a path (empty = open).
+    pub fn methods(&self, path: &str) -> &[AuthMethod] {
+        self.routes
+            .iter()
+            .find(|r| path_matches(&r.path, path))
+            .map_or(&self.default, |r| &r.methods)
+    }
+
+    /// Checks a request, returning who made it (`None` on open routes).
+    ///
+    /// `peer` is the address of the connection, used for `proxy`.
+    pub async fn authenticate(
+        &self,
+        path: &str,
+        query: Option<&str>,
+        headers: &HeaderMap,
+        peer: Option<IpAddr>,
+    ) -> Result<Option<String>, Unauthorized> {
+        let methods = self.methods(path);
+        if methods.is_empty() {
+            return Ok(None);
+        }
+
+        let authorization = headers
+            .get("Authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split_once(' '))
+            .map(|(scheme, credentials)| (scheme.to_ascii_lowercase(), credentials.trim()));
+
+        for method in methods {
+            let identity = match method {
+                AuthMethod::Bearer => {
+                    let mut candidates: Vec<String> = authorization
+                        .iter()
+                        .filter(|(scheme, _)| scheme == "bearer")
+                        .map(|(_, token)| token.to_string())
+                        .collect();
+                    if path == "/ws" {
+                        candidates.extend(websocket_tokens(query, headers));
+                    }
+                    candidates.iter().find_map(|t| self.token_name(t))
+                }
+                AuthMethod::Basic => match &authorization {
+                    Some((scheme, credentials)) if scheme == "basic" => {
+                        let client = self.proxies.client(peer, headers);
+                        self.check_basic(credentials, client).await
+                    }
+                    _ => None,
+                },
+                AuthMethod::Proxy => self.proxy_user(headers, peer),
+            };
+            if identity.is_some() {
+                return Ok(identity);
+            }
+        }
+
+        debug!("Rejected unauthenticated request to {}", path);
+        Err(Unauthorized {
+            methods: methods.to_vec(),
+        })
+    }
+
+    /// Returns the name of a matching token.
+    fn token_name(&self, token: &str) -> Option<String> {
+        // Compare against every token so timing does not reveal which one matched
+        self.tokens.iter().fold(None, |found, (name, expected)| {
+            if constant_time_eq(token.as_bytes(), expected.as_bytes()) {
+                Some(name.clone())
+            } else {
+                found
+            }
+        })
+    }
+
+    /// Verifies base64 `user:password` credentials, returning the user.
+    ///
+    /// `client` is the resolved client address, used for the lockout.
+    async fn check_basic(&self, credentials: &str, client: Option<IpAddr>) -> Option<String> {
+        let decoded = STANDARD.decode(credentials).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (user, password) = decoded.split_once(':')?;
+        let hash = self.users.get(user)?.clone();
+
+        let digest: [u8; 32] = Sha256::digest(password.as_bytes()).into();
+        let cached = self
+            .verified
+            .lock()
+            .unwrap()
+            .get(user)
+            .is_some_and(|d| constant_time_eq(d, &digest));
+        if cached {
+            return Some(user.to_string());
+        }
+
+        let key = (user.to_string(), client);
+        let locked = self
+            .failures
+            .lock()
+            .unwrap()
+            .get(&key)
+            .is_some_and(|f| f.locked(Instant::now()));
+        if locked {
+            debug!("Basic auth for '{}' locked out for {:?}", user, client);
+            return None;
+        }
+
+        let _permit = self.bcrypt_permits.acquire().await.ok()?;
+        let password = password.to_string();
+        let valid =
+            tokio::task::spawn_blocking(move || bcrypt::verify(&password, &hash).unwrap_or(false))
+                .await
+                .ok()?;
+        if !valid {
+            self.record_failure(key);
+            return None;
+        }
+        self.failures.lock().unwrap().remove(&key);
+        self.verified
+            .lock()
+            .unwrap()
+            .insert(user.to_string(), digest);
+        Some(user.to_string())
+    }
+
+    /// Counts a wrong password; failures older than `LOCKOUT` start over.
+    fn record_failure(&self, key: FailureKey) {
+        let now = Instant::now();
+        let mut failures = self.failures.lock().unwrap();
+        if failures.len() >= MAX_TRACKED_FAILURES && !failures.contains_key(&key) {
+            failures.retain(|_, f| now.duration_since(f.last) < LOCKOUT);
+            if failures.len() >= MAX_TRACKED_FAILURES {
+                return;
+            }
+        }
+        let entry = failures.entry(key.clone()).or_insert(Failures {
+            count: 0,
+            last: now,
+        });
+        if now.duration_since(entry.last) >= LOCKOUT {
+            entry.count = 0;
+        }
+        entry.count += 1;
+        entry.last = now;
+        if entry.count == MAX_FAILURES {
+            warn!(
+                "Basic auth for '{}' from {:?} locked out for {:?} after {} failures",
+                key.0, key.1, LOCKOUT, MAX_FAILURES
+            );
+        }
+    }
+
+    /// Returns the user header if the connection comes from a trusted proxy.
+    fn proxy_user(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> Option<String> {
+        let proxy = self.trusted_proxy.as_ref()?;
+        let peer = peer?;
+        if !self.proxies.contains(peer) {
+            return None;
+        }
+        headers
+            .get(&proxy.header)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    }
+}
+
+/// Tokens offered by a WebSocket client in `?token=` or `Sec-WebSocket-Protocol`.
+fn websocket_tokens(query: Option<&str>, headers: &HeaderMap) -> Vec<String> {
+    let mut tokens = crate::handlers::query_values(query, "token");
+    tokens.extend(
+        headers
+            .get_all("Sec-WebSocket-Protocol")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(|p| p.trim().strip_prefix(WS_TOKEN_PROTOCOL))
+            .map(str::to_string),
+    );
+    tokens
+}
+
+/// Compares two byte strings without exiting early on the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,488 @@
+//! Tests for endpoint authentication.
+
+use crate::auth::{Auth, AuthConfig, AuthMethod, IpNetwork, TrustedProxies, MAX_FAILURES};
+use crate::config::Plan;
+use crate::state::AppState;
+use crate::test_support::serve_state;
+use crate::Config;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hyper::header::HeaderMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Parses an auth section trusting proxies in 10.0.0.0/8, panicking on YAML errors.
+fn auth(yaml: &str) -> Result<Auth, crate::config::ConfigError> {
+    auth_with_proxies(yaml, &["10.0.0.0/8"])
+}
+
+fn auth_with_proxies(yaml: &str, proxies: &[&str]) -> Result<Auth, crate::config::ConfigError> {
+    let proxies: Vec<String> = proxies.iter().map(|p| p.to_string()).collect();
+    Auth::new(
+        serde_yaml::from_str::<AuthConfig>(yaml).unwrap(),
+        &TrustedProxies::new(&proxies).unwrap(),
+    )
+}
+
+fn forwarded(value: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("X-Forwarded-For", value.parse().unwrap());
+    headers
+}
+
+/// Token `grafana`, user `prometheus` / `s3cret`, proxy from 10.0.0.0/8.
+fn full_auth() -> Auth {
+    let hash = bcrypt::hash("s3cret", 4).unwrap();
+    auth(&format!(
+        r#"
+tokens:
+  grafana: "token-123"
+users:
+  prometheus: "{}"
+trusted_proxy: {{}}
+routes:
+  - path: /health
+    methods: []
+  - path: /metrics
+    methods: [basic, bearer]
+  - path: /ws
+    methods: [bearer]
+  - path: /icons/*
+    methods: [proxy]
+"#,
+        hash
+    ))
+    .unwrap()
+}
+
+fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    for (name, value) in pairs {
+        map.append(
+            hyper::header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            value.parse().unwrap(),
+        );
+    }
+    map
+}
+
+fn basic(user: &str, password: &str) -> String {
+    format!(
+        "Basic {}",
+        STANDARD.encode(format!("{}:{}", user, password))
+    )
+}
+
+fn ip(s: &str) -> Option<IpAddr> {
+    Some(s.parse().unwrap())
+}
+
+// =============================================================================
+// Config Tests
+// =============================================================================
+
+#[test]
+fn test_ip_network() {
+    let net: IpNetwork = "10.0.0.0/8".parse().unwrap();
+    assert!(net.contains("10.1.2.3".parse().unwrap()));
+    assert!(!net.contains("11.0.0.1".parse().unwrap()));
+    // IPv4-mapped IPv6 from dual-stack listeners
+    assert!(net.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+    let host: IpNetwork = "127.0.0.1".parse().unwrap();
+    assert!(host.contains("127.0.0.1".parse().unwrap()));
+    assert!(!host.contains("127.0.0.2".parse().unwrap()));
+
+    let v6: IpNetwork = "fd00::/8".parse().unwrap();
+    assert!(v6.contains("fd12::1".parse().unwrap()));
+    assert!(!v6.contains("10.0.0.1".parse().unwrap()));
+
+    let any: IpNetwork = "0.0.0.0/0".parse().unwrap();
+    assert!(any.contains("192.168.1.1".parse().unwrap()));
+
+    for invalid in ["10.0.0.0/33", "fd00::/129", "localhost", "10.0.0.0/x"] {
+        assert!(invalid.parse::<IpNetwork>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_validation() {
+    let invalid = [
+        // Nothing configured
+        "routes: []",
+        "tokens:\n  grafana: \"\"",
+        "users:\n  prometheus: not-a-hash",
+        "trusted_proxy:\n  header: \"bad header\"",
+        // Method used but not configured
+        "tokens:\n  a: b\ndefault: [basic]",
+        "tokens:\n  a: b\nroutes:\n  - path: /metrics\n    methods: [proxy]",
+        "tokens:\n  a: b\nroutes:\n  - path: metrics\n    methods: []",
+    ];
+    for yaml in invalid {
+        assert!(auth(yaml).is_err(), "{}", yaml);
+    }
+    assert!(auth("tokens:\n  a: b").is_ok());
+
+    // Proxy auth needs the top-level trusted_proxies
+    assert!(auth_with_proxies("trusted_proxy: {}", &[]).is_err());
+    assert!(TrustedProxies::new(&["proxy.local".to_string()]).is_err());
+}
+
+#[test]
+fn test_route_methods() {
+    let auth = full_auth();
+    assert!(auth.methods("/health").is_empty());
+    assert_eq!(
+        auth.methods("/metrics"),
+        [AuthMethod::Basic, AuthMethod::Bearer]
+    );
+    assert_eq!(auth.methods("/icons/services/web"), [AuthMethod::Proxy]);
+    assert_eq!(auth.methods("/icons"), [AuthMethod::Proxy]);
+    // Exact paths do not cover sub-paths; default is every configured method
+    assert_eq!(
+        auth.methods("/metrics/influx"),
+        [AuthMethod::Bearer, AuthMethod::Basic, AuthMethod::Proxy]
+    );
+
+    let auth = self::auth("tokens:\n  a: b\ndefault: []").unwrap();
+    assert!(auth.methods("/status").is_empty());
+}
+
+// =============================================================================
+// Authentication Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_open_route() {
+    let auth = full_auth();
+    let result = auth
+        .authenticate("/health", None, &headers(&[]), None)
+        .await;
+    assert_eq!(result, Ok(None));
+}
+
+#[tokio::test]
+async fn test_bearer() {
+    let auth = full_auth();
+    let ok = headers(&[("Authorization", "Bearer token-123")]);
+    assert_eq!(
+        auth.authenticate("/metrics", None, &ok, None).await,
+        Ok(Some("grafana".to_string()))
+    );
+    // Scheme is case-insensitive
+    let ok = headers(&[("Authorization", "bearer token-123")]);
+    assert!(auth.authenticate("/metrics", None, &ok, None).await.is_ok());
+
+    let wrong = headers(&[("Authorization", "Bearer token-12")]);
+    assert!(auth
+        .authenticate("/metrics", None, &wrong, None)
+        .await
+        .is_err());
+    assert!(auth
+        .authenticate("/metrics", None, &headers(&[]), None)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_basic() {
+    let auth = full_auth();
+    let ok = headers(&[("Authorization", &basic("prometheus", "s3cret"))]);
+    assert_eq!(
+        auth.authenticate("/metrics", None, &ok, None).await,
+        Ok(Some("prometheus".to_string()))
+    );
+    // Second request is served from the verified cache
+    assert!(auth.authenticate("/metrics", None, &ok, None).await.is_ok());
+
+    for (user, password) in [("prometheus", "wrong"), ("nobody", "s3cret")] {
+        let bad = headers(&[("Authorization", &basic(user, password))]);
+        assert!(auth
+            .authenticate("/metrics", None, &bad, None)
+            .await
+            .is_err());
+    }
+    let garbage = headers(&[("Authorization", "Basic !!!")]);
+    assert!(auth
+        .authenticate("/metrics", None, &garbage, None)
+        .await
+        .is_err());
+
+    // Basic is not allowed on /ws
+    assert!(auth.authenticate("/ws", None, &ok, None).await.is_err());
+}
+
+#[tokio::test]
+async fn test_basic_lockout_per_client() {
+    let auth = full_auth();
+    let attacker: Option<IpAddr> = Some("192.0.2.1".parse().unwrap());
+    let other: Option<IpAddr> = Some("192.0.2.2".parse().unwrap());
+    let bad = headers(&[("Authorization", &basic("prometheus", "wrong"))]);
+    let ok = headers(&[("Authorization", &basic("prometheus", "s3cret"))]);
+
+    for _ in 0..MAX_FAILURES {
+        assert!(auth
+            .authenticate("/metrics", None, &bad, attacker)
+            .await
+            .is_err());
+    }
+    // Locked out: even the right password is refused from that client
+    assert!(auth
+        .authenticate("/metrics", None, &ok, attacker)
+        .await
+        .is_err());
+
+    // Other clients are unaffected, and once verified the user is cached
+    assert!(auth
+        .authenticate("/metrics", None, &ok, other)
+        .await
+        .is_ok());
+    assert!(auth
+        .authenticate("/metrics", None, &ok, attacker)
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_basic_lockout_uses_forwarded_client() {
+    let auth = full_auth();
+    let proxy: Option<IpAddr> = Some("10.0.0.1".parse().unwrap());
+    let bad = |client: &str| {
+        headers(&[
+            ("Authorization", &basic("prometheus", "wrong")),
+            ("X-Forwarded-For", client),
+        ])
+    };
+    let ok = |client: &str| {
+        headers(&[
+            ("Authorization", &basic("prometheus", "s3cret")),
+            ("X-Forwarded-For", client),
+        ])
+    };
+
+    for _ in 0..MAX_FAILURES {
+        assert!(auth
+            .authenticate("/metrics", None, &bad("192.0.2.1"), proxy)
+            .await
+            .is_err());
+    }
+    assert!(auth
+        .authenticate("/metrics", None, &ok("192.0.2.1"), proxy)
+        .await
+        .is_err());
+    // Clients behind the same proxy are tracked separately
+    assert!(auth
+        .authenticate("/metrics", None, &ok("192.0.2.2"), proxy)
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_trusted_proxy() {
+    let auth = full_auth();
+    let user = headers(&[("X-Forwarded-User", "alice")]);
+    assert_eq!(
+        auth.authenticate("/icons/services/web", None, &user, ip("10.1.1.1"))
+            .await,
+        Ok(Some("alice".to_string()))
+    );
+    // Header from an untrusted peer is ignored
+    assert!(auth
+        .authenticate("/icons/services/web", None, &user, ip("192.168.0.1"))
+        .await
+        .is_err());
+    assert!(auth
+        .authenticate("/icons/services/web", None, &user, None)
+        .await
+        .is_err());
+    // Trusted peer without the header
+    assert!(auth
+        .authenticate("/icons/services/web", None, &headers(&[]), ip("10.1.1.1"))
+        .await
+        .is_err());
+}
+
+#[test]
+fn test_trusted_proxies_client() {
+    let proxies =
+        TrustedProxies::new(&["10.0.0.0/8".to_string(), "127.0.0.1".to_string()]).unwrap();
+
+    // Direct connection: header is ignored
+    let spoofed = forwarded("1.2.3.4");
+    assert_eq!(
+        proxies.client(ip("198.51.100.1"), &spoofed),
+        ip("198.51.100.1")
+    );
+
+    // Through one trusted proxy
+    assert_eq!(proxies.client(ip("10.0.0.5"), &spoofed), ip("1.2.3.4"));
+
+    // Chain of trusted proxies; the client-supplied left part is not trusted
+    let chain = forwarded("6.6.6.6, 203.0.113.7, 10.0.0.9");
+    assert_eq!(proxies.client(ip("127.0.0.1"), &chain), ip("203.0.113.7"));
+
+    // Ports and IPv6
+    assert_eq!(
+        proxies.client(ip("10.0.0.5"), &forwarded("[2001:db8::1]:4711")),
+        ip("2001:db8::1")
+    );
+    assert_eq!(
+        proxies.client(ip("10.0.0.5"), &forwarded("203.0.113.7:4711")),
+        ip("203.0.113.7")
+    );
+
+    // Garbage stops the walk at the last proxy
+    assert_eq!(
+        proxies.client(ip("10.0.0.5"), &forwarded("unknown")),
+        ip("10.0.0.5")
+    );
+
+    // Without trusted proxies the peer is always the client
+    assert_eq!(
+        TrustedProxies::default().client(ip("10.0.0.5"), &spoofed),
+        ip("10.0.0.5")
+    );
+    assert_eq!(proxies.client(None, &spoofed), None);
+}
+
+#[tokio::test]
+async fn test_websocket_tokens() {
+    let auth = full_auth();
+    let none = headers(&[]);
+    assert!(auth
+        .authenticate("/ws", Some("token=token-123"), &none, None)
+        .await
+        .is_ok());
+
+    let protocol = headers(&[(
+        "Sec-WebSocket-Protocol",
+        "railway-exporter, bearer.token-123",
+    )]);
+    assert!(auth
+        .authenticate("/ws", None, &protocol, None)
+        .await
+        .is_ok());
+
+    let wrong = headers(&[("Sec-WebSocket-Protocol", "bearer.nope")]);
+    assert!(auth.authenticate("/ws", None, &wrong, None).await.is_err());
+
+    // Query tokens are only accepted for /ws
+    assert!(auth
+        .authenticate("/metrics", Some("token=token-123"), &none, None)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_unauthorized_response() {
+    let auth = full_auth();
+    let denied = auth
+        .authenticate("/metrics", None, &headers(&[]), None)
+        .await
+        .unwrap_err();
+    let (builder, _) = denied.response();
+    let response = builder.body(()).unwrap();
+    assert_eq!(response.status(), 401);
+    let challenges: Vec<_> = response
+        .headers()
+        .get_all("WWW-Authenticate")
+        .iter()
+        .map(|v| v.to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(challenges.len(), 2);
+    assert!(challenges[0].starts_with("Basic realm="));
+    assert!(challenges[1].starts_with("Bearer realm="));
+
+    // Proxy-only routes cannot be retried with credentials
+    let denied = auth
+        .authenticate("/icons/services/web", None, &headers(&[]), None)
+        .await
+        .unwrap_err();
+    let (builder, _) = denied.response();
+    assert_eq!(builder.body(()).unwrap().status(), 403);
+}
+
+// =============================================================================
+// Server Tests
+// =============================================================================
+
+/// Starts the real connection handler with `full_auth` on a random port.
+async fn start_auth_server() -> (Arc<AppState>, String) {
+    let mut config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
+    config.websocket_enabled = true;
+    config.auth = Some(full_auth());
+    let state = Arc::new(AppState::new(config));
+    let addr = serve_state(state.clone()).await;
+    (state, addr)
+}
+
+#[tokio::test]
+async fn test_server_enforces_auth() {
+    let (_state, addr) = start_auth_server().await;
+    let client = reqwest::Client::new();
+
+    let health = client
+        .get(format!("http://{}/health", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(health.status(), 200);
+
+    let metrics = client
+        .get(format!("http://{}/metrics", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(metrics.status(), 401);
+    assert!(metrics.headers().contains_key("WWW-Authenticate"));
+
+    let metrics = client
+        .get(format!("http://{}/metrics", addr))
+        .basic_auth("prometheus", Some("s3cret"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(metrics.status(), 200);
+
+    let status = client
+        .get(format!("http://{}/status", addr))
+        .bearer_auth("token-123")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(status.status(), 200);
+}
+
+#[tokio::test]
+async fn test_server_websocket_auth() {
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::Error;
+
+    let (state, addr) = start_auth_server().await;
+
+    // Rejected before the handshake
+    let result = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr)).await;
+    match result {
+        Err(Error::Http(response)) => assert_eq!(response.status(), 401),
+        other => panic!("expected 401, got {:?}", other.map(|(_, r)| r.status())),
+    }
+    assert_eq!(state.ws_client_count(), 0);
+
+    let (_ws, response) =
+        tokio_tungstenite::connect_async(format!("ws://{}/ws?token=token-123", addr))
+            .await
+            .unwrap();
+    assert_eq!(response.status(), 101);
+    assert!(response.headers().get("Sec-WebSocket-Protocol").is_none());
+
+    // Token in the subprotocol list; the other protocol is echoed
+    let mut request = format!("ws://{}/ws", addr).into_client_request().unwrap();
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        "railway-exporter, bearer.token-123".parse().unwrap(),
+    );
+    let (_ws, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+    assert_eq!(
+        response.headers()["Sec-WebSocket-Protocol"],
+        "railway-exporter"
+    );
+}
@@ -0,0 +1,337 @@
+//! Cost centers and monthly chargeback.
+//!
+//! Maps services to cost centers (team, product, customer, ...) independently
+//! of dashboard `service_groups`. Each dimension is an ordered rule list using
+//! the same matchers as service groups; services matching no rule of a
+//! dimension are booked to `unassigned`, so every dimension adds up to the
+//! project total.
+//!
+//! Monthly totals are tracked per calendar billing period. When the first
+//! scrape of a new month arrives, the previous period is frozen with its last
+//! observed values and kept as history. The ledger can be persisted to a JSON
+//! file so a restart across the month boundary doesn't lose the open period.
+//! The file is replaced atomically (temp file, fsync, rename); one that fails
+//! to parse at startup is moved aside to `<state_file>.invalid`.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! cost_centers:
+//!   - name: platform
+//!     dimension: team        # default: team
+//!     match: prefix          # same matchers as service_groups
+//!     patterns: [postgres, redis]
+//!   - name: checkout
+//!     dimension: product
+//!     patterns: [api, worker]
+//!
+//! chargeback:
+//!   state_file: /data/chargeback.json   # persist ledger across restarts (optional)
+//!   history_months: 12                   # closed periods to keep (default: 12)
+//! ```
+
+use crate::config::ConfigError;
+use crate::grouping::{GroupRule, MatchKind, ServiceGroups};
+use crate::types::ServiceData;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use tokio::io::AsyncWriteExt;
+use tracing::{error, warn};
+
+/// Cost center assigned to services that match no rule of a dimension.
+pub const UNASSIGNED: &str = "unassigned";
+
+/// A single cost center rule as written in YAML.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CostCenterRule {
+    /// Cost center name.
+    pub name: String,
+    /// Dimension the cost center belongs to (team, product, customer, ...).
+    #[serde(default = "default_dimension")]
+    pub dimension: String,
+    /// Matcher type for all patterns of this rule.
+    #[serde(default, rename = "match")]
+    pub kind: MatchKind,
+    /// Service name patterns (any may match).
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Compare case-sensitively (default: false).
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+fn default_dimension() -> String {
+    "team".to_string()
+}
+
+/// Chargeback ledger settings.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ChargebackConfig {
+    /// JSON file used to persist the ledger across restarts.
+    #[serde(default)]
+    pub state_file: Option<String>,
+    /// Number of closed periods to keep.
+    #[serde(default = "default_history_months")]
+    pub history_months: usize,
+}
+
+fn default_history_months() -> usize {
+    12
+}
+
+impl Default for ChargebackConfig {
+    fn default() -> Self {
+        Self {
+            state_file: None,
+            history_months: default_history_months(),
+        }
+    }
+}
+
+/// Compiled cost center rules, one first-match rule list per dimension.
+#[derive(Debug, Clone, Default)]
+pub struct CostCenters {
+    dimensions: Vec<(String, ServiceGroups)>,
+}
+
+impl CostCenters {
+    /// Compiles rules, grouping them by dimension in order of first appearance.
+    pub fn new(rules: Vec<CostCenterRule>) -> Result<Self, ConfigError> {
+        let mut by_dimension: Vec<(String, Vec<GroupRule>)> = Vec::new();
+        for rule in rules {
+            if rule.name.is_empty() || rule.dimension.is_empty() {
+                return Err(ConfigError::InvalidValue(
+                    "cost_centers entries need a non-empty name and dimension".to_string(),
+                ));
+            }
+            let group_rule = GroupRule {
+                group: rule.name,
+                kind: rule.kind,
+                patterns: rule.patterns,
+                case_sensitive: rule.case_sensitive,
+            };
+            match by_dimension.iter_mut().find(|(d, _)| *d == rule.dimension) {
+                Some((_, rules)) => rules.push(group_rule),
+                None => by_dimension.push((rule.dimension, vec![group_rule])),
+            }
+        }
+
+        let dimensions = by_dimension
+            .into_iter()
+            .map(|(dimension, rules)| {
+                ServiceGroups::new(rules, false)
+                    .map(|groups| (dimension.clone(), groups))
+                    .map_err(|e| {
+                        ConfigError::InvalidValue(format!("cost_centers.{}: {}", dimension, e))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { dimensions })
+    }
+
+    /// Returns true if no cost centers are configured.
+    pub fn is_empty(&self) -> bool {
+        self.dimensions.is_empty()
+    }
+
+    /// Configured dimension names in order.
+    pub fn dimensions(&self) -> Vec<&str> {
+        self.dimensions.iter().map(|(d, _)| d.as_str()).collect()
+    }
+
+    /// Aggregates service rows into per-cost-center totals.
+    ///
+    /// Rows are ordered by dimension (config order), then cost (highest first).
+    pub fn summarize(&self, services: &[ServiceData]) -> Vec<CostCenterRow> {
+        let mut rows: Vec<CostCenterRow> = Vec::new();
+
+        for (dimension, rules) in &self.dimensions {
+            let mut centers: BTreeMap<String, CostCenterRow> = BTreeMap::new();
+            for service in services {
+                let name = rules
+                    .classify(&service.name)
+                    .into_iter()
+                    .next()
+                    .map(|m| m.group)
+                    .unwrap_or_else(|| UNASSIGNED.to_string());
+                let row = centers
+                    .entry(name.clone())
+                    .or_insert_with(|| CostCenterRow {
+                        dimension: dimension.clone(),
+                        cost_center: name,
+                        services: Vec::new(),
+                        cost_usd: 0.0,
+                        estimated_monthly_usd: 0.0,
+                    });
+                row.services.push(service.name.clone());
+                row.cost_usd += service.cost_usd;
+                row.estimated_monthly_usd += service.estimated_monthly_usd;
+            }
+
+            let mut dim_rows: Vec<CostCenterRow> = centers.into_values().collect();
+            dim_rows.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+            rows.extend(dim_rows);
+        }
+
+        rows
+    }
+}
+
+/// Totals for one cost center in one billing period.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostCenterRow {
+    pub dimension: String,
+    pub cost_center: String,
+    /// Service names booked to this cost center.
+    pub services: Vec<String>,
+    pub cost_usd: f64,
+    pub estimated_monthly_usd: f64,
+}
+
+/// Cost center totals for one billing period (`YYYY-MM`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChargebackPeriod {
+    pub period: String,
+    pub project: String,
+    /// True once the period has ended and values are frozen.
+    pub closed: bool,
+    /// Unix timestamp of the scrape these values come from.
+    pub updated_at: i64,
+    pub rows: Vec<CostCenterRow>,
+}
+
+/// Atomically replaces a state file: the data goes to `<path>.tmp`, is
+/// synced to disk and then renamed over `path`.
+pub async fn write_state(path: &str, data: &[u8]) -> std::io::Result<()> {
+    let tmp = format!("{}.tmp", path);
+    let mut file = tokio::fs::File::create(&tmp).await?;
+    file.write_all(data).await?;
+    file.sync_all().await?;
+    drop(file);
+    tokio::fs::rename(&tmp, path).await
+}
+
+/// Open period plus frozen history.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ChargebackLedger {
+    /// Period currently being accumulated.
+    pub current: Option<ChargebackPeriod>,
+    /// Frozen periods, oldest first.
+    pub closed: Vec<ChargebackPeriod>,
+}
+
+impl ChargebackLedger {
+    /// Loads the ledger (open period and history) from a state file, or starts empty.
+    ///
+    /// A file that doesn't parse is renamed to `<path>.invalid` so the next
+    /// save doesn't overwrite it.
+    pub fn load(path: Option<&str>) -> Self {
+        let Some(path) = path else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        match serde_json::from_str::<ChargebackLedger>(&content) {
+            Ok(ledger) => ledger,
+            Err(e) => {
+                let aside = format!("{}.invalid", path);
+                match std::fs::rename(path, &aside) {
+                    Ok(()) => warn!(
+                        "Invalid chargeback state file {} ({}), moved to {}",
+                        path, e, aside
+                    ),
+                    Err(rename) => error!(
+                        "Invalid chargeback state file {} ({}) could not be moved aside ({}); it will be overwritten",
+                        path, e, rename
+                    ),
+                }
+                Self::default()
+            }
+        }
+    }
+
+    /// Records a fresh snapshot for `period.period`.
+    ///
+    /// If the snapshot belongs to a new period, the previous one is frozen and
+    /// returned. History beyond `history_months` is dropped (oldest first).
+    pub fn record(
+        &mut self,
+        period: ChargebackPeriod,
+        history_months: usize,
+    ) -> Option<ChargebackPeriod> {
+        let mut frozen = None;
+        if let Some(mut previous) = self.current.take() {
+            if previous.period == period.period {
+                // Same period: just replace with newer values
+            } else if previous.period < period.period {
+                previous.closed = true;
+                self.closed.retain(|p| p.period != previous.period);
+                self.closed.push(previous.clone());
+                frozen = Some(previous);
+            } else {
+                // Clock went backwards; keep the newer period open
+                self.current = Some(previous);
+                return None;
+            }
+        }
+        self.current = Some(period);
+
+        if self.closed.len() > history_months {
+            let excess = self.closed.len() - history_months;
+            self.closed.drain(..excess);
+        }
+        frozen
+    }
+
+    /// Returns the most recently closed period.
+    pub fn last_closed(&self) -> Option<&ChargebackPeriod> {
+        self.closed.last()
+    }
+
+    /// Returns all periods (closed first, then current), optionally filtered.
+    pub fn periods(&self, period: Option<&str>) -> Vec<&ChargebackPeriod> {
+        self.closed
+            .iter()
+            .chain(self.current.iter())
+            .filter(|p| period.is_none_or(|want| p.period == want))
+            .collect()
+    }
+}
+
+/// Renders periods as CSV (one row per period and cost center).
+pub fn to_csv(periods: &[&ChargebackPeriod]) -> String {
+    let mut out = String::from(
+        "period,status,project,dimension,cost_center,service_count,services,cost_usd,estimated_monthly_usd\n",
+    );
+    for p in periods {
+        let status = if p.closed { "closed" } else { "open" };
+        for row in &p.rows {
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{},{},{:.6},{:.6}",
+                p.period,
+                status,
+                csv_field(&p.project),
+                csv_field(&row.dimension),
+                csv_field(&row.cost_center),
+                row.services.len(),
+                csv_field(&row.services.join(";")),
+                row.cost_usd,
+                row.estimated_monthly_usd
+            );
+        }
+    }
+    out
+}
+
+/// Quotes a CSV field if it contains separators, quotes or newlines.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,208 @@
+//! Tests for cost centers and the chargeback ledger.
+
+use crate::chargeback::{
+    to_csv, write_state, ChargebackLedger, ChargebackPeriod, CostCenterRow, CostCenterRule,
+    CostCenters, UNASSIGNED,
+};
+use crate::grouping::MatchKind;
+use crate::test_support::service;
+
+fn center(name: &str, dimension: &str, patterns: &[&str]) -> CostCenterRule {
+    CostCenterRule {
+        name: name.to_string(),
+        dimension: dimension.to_string(),
+        kind: MatchKind::Contains,
+        patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        case_sensitive: false,
+    }
+}
+
+fn period(name: &str, cost: f64) -> ChargebackPeriod {
+    ChargebackPeriod {
+        period: name.to_string(),
+        project: "proj".to_string(),
+        closed: false,
+        updated_at: 0,
+        rows: vec![CostCenterRow {
+            dimension: "team".to_string(),
+            cost_center: "platform".to_string(),
+            services: vec!["postgres".to_string()],
+            cost_usd: cost,
+            estimated_monthly_usd: cost,
+        }],
+    }
+}
+
+// =============================================================================
+// CostCenters Tests
+// =============================================================================
+
+#[test]
+fn test_summarize_per_dimension() {
+    let centers = CostCenters::new(vec![
+        center("platform", "team", &["postgres", "redis"]),
+        center("checkout", "product", &["api"]),
+        center("payments", "team", &["api"]),
+    ])
+    .unwrap();
+    assert_eq!(centers.dimensions(), vec!["team", "product"]);
+
+    let services = vec![
+        service("postgres", &["ungrouped"], 3.0),
+        service("redis", &["ungrouped"], 1.0),
+        service("api", &["ungrouped"], 2.0),
+        service("web", &["ungrouped"], 0.5),
+    ];
+    let rows = centers.summarize(&services);
+
+    let team: Vec<_> = rows.iter().filter(|r| r.dimension == "team").collect();
+    assert_eq!(team[0].cost_center, "platform");
+    assert_eq!(team[0].cost_usd, 4.0);
+    assert_eq!(team[0].estimated_monthly_usd, 12.0);
+    assert_eq!(team[0].services, vec!["postgres", "redis"]);
+    assert_eq!(team[1].cost_center, "payments");
+    assert_eq!(team[2].cost_center, UNASSIGNED);
+    assert_eq!(team[2].services, vec!["web"]);
+
+    // Every dimension adds up to the project total
+    let product_total: f64 = rows
+        .iter()
+        .filter(|r| r.dimension == "product")
+        .map(|r| r.cost_usd)
+        .sum();
+    assert_eq!(product_total, 6.5);
+}
+
+#[test]
+fn test_cost_center_rule_deserialize_default_dimension() {
+    let yaml = r#"
+- name: platform
+  match: prefix
+  patterns: [pg]
+"#;
+    let rules: Vec<CostCenterRule> = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(rules[0].dimension, "team");
+    assert_eq!(rules[0].kind, MatchKind::Prefix);
+    assert!(CostCenters::new(rules).is_ok());
+}
+
+#[test]
+fn test_cost_centers_invalid() {
+    assert!(CostCenters::new(vec![center("", "team", &["a"])]).is_err());
+    let mut bad = center("x", "team", &["("]);
+    bad.kind = MatchKind::Regex;
+    assert!(CostCenters::new(vec![bad]).is_err());
+}
+
+// =============================================================================
+// Ledger Tests
+// =============================================================================
+
+#[test]
+fn test_ledger_freezes_previous_period() {
+    let mut ledger = ChargebackLedger::default();
+
+    assert!(ledger.record(period("2026-09", 1.0), 12).is_none());
+    assert!(ledger.record(period("2026-09", 5.0), 12).is_none());
+
+    let frozen = ledger.record(period("2026-10", 0.1), 12).unwrap();
+    assert_eq!(frozen.period, "2026-09");
+    assert!(frozen.closed);
+    assert_eq!(frozen.rows[0].cost_usd, 5.0);
+
+    assert_eq!(ledger.last_closed().unwrap().period, "2026-09");
+    assert_eq!(ledger.current.as_ref().unwrap().period, "2026-10");
+    assert_eq!(ledger.periods(None).len(), 2);
+    assert_eq!(ledger.periods(Some("2026-09")).len(), 1);
+    assert!(ledger.periods(Some("2025-01")).is_empty());
+}
+
+#[test]
+fn test_ledger_ignores_older_period() {
+    let mut ledger = ChargebackLedger::default();
+    ledger.record(period("2026-10", 1.0), 12);
+    assert!(ledger.record(period("2026-09", 2.0), 12).is_none());
+    assert_eq!(ledger.current.as_ref().unwrap().period, "2026-10");
+    assert!(ledger.closed.is_empty());
+}
+
+#[test]
+fn test_ledger_history_limit() {
+    let mut ledger = ChargebackLedger::default();
+    for month in ["2026-01", "2026-02", "2026-03", "2026-04"] {
+        ledger.record(period(month, 1.0), 2);
+    }
+    let closed: Vec<&str> = ledger.closed.iter().map(|p| p.period.as_str()).collect();
+    assert_eq!(closed, vec!["2026-02", "2026-03"]);
+}
+
+#[test]
+fn test_ledger_load_missing_file() {
+    let ledger = ChargebackLedger::load(Some("/nonexistent/chargeback.json"));
+    assert!(ledger.current.is_none());
+    assert!(ledger.closed.is_empty());
+}
+
+#[test]
+fn test_ledger_load_roundtrip() {
+    let path = std::env::temp_dir().join(format!("chargeback-{}.json", std::process::id()));
+    let mut ledger = ChargebackLedger::default();
+    ledger.record(period("2026-09", 1.0), 12);
+    ledger.record(period("2026-10", 2.0), 12);
+    std::fs::write(&path, serde_json::to_vec(&ledger).unwrap()).unwrap();
+
+    let loaded = ChargebackLedger::load(path.to_str());
+    assert_eq!(loaded.closed, ledger.closed);
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_ledger_load_invalid_file_moved_aside() {
+    let path = std::env::temp_dir().join(format!("chargeback-bad-{}.json", std::process::id()));
+    let aside = format!("{}.invalid", path.display());
+    std::fs::write(&path, "{not json").unwrap();
+
+    let ledger = ChargebackLedger::load(path.to_str());
+    assert!(ledger.current.is_none());
+    assert!(!path.exists());
+    assert_eq!(std::fs::read_to_string(&aside).unwrap(), "{not json");
+    std::fs::remove_file(aside).ok();
+}
+
+#[tokio::test]
+async fn test_write_state_replaces_file() {
+    let path = std::env::temp_dir().join(format!("chargeback-write-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    std::fs::write(path, "old").unwrap();
+
+    write_state(path, b"{\"current\":null,\"closed\":[]}")
+        .await
+        .unwrap();
+    assert!(ChargebackLedger::load(Some(path)).closed.is_empty());
+    assert_eq!(
+        std::fs::read_to_string(path).unwrap(),
+        "{\"current\":null,\"closed\":[]}"
+    );
+    assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+    std::fs::remove_file(path).ok();
+}
+
+// =============================================================================
+// CSV Tests
+// =============================================================================
+
+#[test]
+fn test_to_csv() {
+    let mut p = period("2026-09", 1.5);
+    p.closed = true;
+    p.rows[0].services.push("redis".to_string());
+    p.rows[0].cost_center = "platform, infra".to_string();
+
+    let csv = to_csv(&[&p]);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert!(lines[0].starts_with("period,status,project,dimension,cost_center"));
+    assert_eq!(
+        lines[1],
+        "2026-09,closed,proj,team,\"platform, infra\",2,postgres;redis,1.500000,1.500000"
+    );
+}
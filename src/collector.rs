@@ -1,18 +1,40 @@
 //! Metrics collection from Railway API.
 
+use crate::allocation;
+use crate::chargeback::{self, ChargebackPeriod};
 use crate::client::{ApiError, Client};
 use crate::config::IconMode;
+use crate::grouping::{GroupMatch, UNGROUPED};
 use crate::state::AppState;
-use crate::types::{MetricsJson, ProjectSummary, ServiceData, WsMessage};
-use chrono::{Datelike, Utc};
-use std::collections::HashMap;
+use crate::types::{GroupSummary, MetricsJson, ProjectSummary, ServiceData, WsMessage};
+use chrono::{DateTime, Datelike, Utc};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 /// Collects metrics from Railway API and updates Prometheus gauges.
+///
+/// Failures of any API call are counted in `/status` and the
+/// `exporter_scrapes_total` counter. The `/metrics` bodies are re-rendered
+/// either way.
 pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(), ApiError> {
     let start = Instant::now();
+    let result = collect(client, state, start).await;
+
+    state
+        .metrics
+        .observe_collection(result.is_ok(), start.elapsed().as_secs_f64());
+    if let Err(ref e) = result {
+        let mut status = state.api_status.write().await;
+        status.failed_scrapes += 1;
+        status.last_error = Some(e.to_string());
+    }
+    state.refresh_metrics_cache().await;
+    result
+}
+
+async fn collect(client: &Client, state: &Arc<AppState>, start: Instant) -> Result<(), ApiError> {
     let config = &state.config;
     let metrics = &state.metrics;
 
@@ -39,43 +61,52 @@ pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(
                 .api_up
                 .with_label_values(&[&config.project_id])
                 .set(0.0);
-            let mut status = state.api_status.write().await;
-            status.failed_scrapes += 1;
-            status.last_error = Some(e.to_string());
             return Err(e);
         }
     };
 
     let project_name = &project.name;
 
-    // Build service map: id -> (name, icon_url, group)
+    // Build service map: id -> (name, icon_url, groups)
     // First pass: collect service info with original icon URLs
-    let services_raw: Vec<(String, String, String, String)> = project
+    // Rules that matched each service, reported by /status
+    let mut group_matches: HashMap<String, Vec<GroupMatch>> = HashMap::new();
+    let services_raw: Vec<(String, String, String, Vec<String>)> = project
         .services
         .edges
         .iter()
         .map(|e| {
             let name = e.node.name.clone();
             let icon_url = e.node.icon.clone().unwrap_or_default();
-            // Find group for this service
-            let group = config
-                .service_groups
-                .iter()
-                .find(|(_, patterns)| patterns.iter().any(|p| name.contains(p) || p == &name))
-                .map(|(g, _)| g.clone())
-                .unwrap_or_else(|| "ungrouped".to_string());
-            (e.node.id.clone(), name, icon_url, group)
+            // Ordered rules: first match is the primary group
+            let matches = config.service_groups.classify(&name);
+            let mut groups: Vec<String> = matches.iter().map(|m| m.group.clone()).collect();
+            if groups.is_empty() {
+                groups.push(UNGROUPED.to_string());
+            }
+            group_matches.insert(e.node.id.clone(), matches);
+            (e.node.id.clone(), name, icon_url, groups)
         })
         .collect();
 
+    // Apply include/exclude filters before icons, gauges and ServiceData
+    let filter = &config.service_filter;
+    let (services_raw, excluded): (Vec<_>, Vec<_>) = services_raw
+        .into_iter()
+        .partition(|(id, name, _, groups)| filter.allows(id, name, groups));
+    let excluded: HashSet<String> = excluded.into_iter().map(|(id, ..)| id).collect();
+    if !excluded.is_empty() {
+        debug!("Excluded {} services by service_filter", excluded.len());
+    }
+
     // Second pass: process icons based on mode
     debug!(
         "Processing icons for {} services (mode: {})",
         services_raw.len(),
         config.icon_cache.mode
     );
-    let mut services: HashMap<String, (String, String, String)> = HashMap::new();
-    for (id, name, icon_url, group) in services_raw {
+    let mut services: HashMap<String, (String, String, Vec<String>)> = HashMap::new();
+    for (id, name, icon_url, groups) in services_raw {
         let icon_data = if !config.icon_cache.enabled {
             // Caching disabled - use original URL as-is
             icon_url.clone()
@@ -105,7 +136,7 @@ pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(
                 }
             }
         };
-        services.insert(id, (name, icon_data, group));
+        services.insert(id, (name, icon_data, groups));
     }
 
     // Get usage metrics
@@ -113,36 +144,38 @@ pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(
 
     let mut total_cost = 0.0;
     let mut services_data: Vec<ServiceData> = Vec::new();
+    // Excluded spend, folded into a single bucket so project totals stay accurate
+    let mut other: Option<ServiceData> = None;
 
     for (sid, measurements) in &usage {
-        let default_svc = (sid.clone(), String::new(), "ungrouped".to_string());
-        let (name, icon, group) = services.get(sid).unwrap_or(&default_svc);
+        let default_svc = (sid.clone(), String::new(), vec![UNGROUPED.to_string()]);
+        let (name, icon, groups) = services.get(sid).unwrap_or(&default_svc);
+        let group = &groups[0];
 
         let cpu = *measurements.get("CPU_USAGE").unwrap_or(&0.0);
         let mem = *measurements.get("MEMORY_USAGE_GB").unwrap_or(&0.0);
         let disk = *measurements.get("DISK_USAGE_GB").unwrap_or(&0.0);
         let tx = *measurements.get("NETWORK_TX_GB").unwrap_or(&0.0);
 
-        let labels = &[
-            name.as_str(),
-            project_name.as_str(),
-            icon.as_str(),
-            group.as_str(),
-        ];
-
-        metrics.cpu_usage.with_label_values(labels).set(cpu);
-        metrics.memory_usage.with_label_values(labels).set(mem);
-        metrics.disk_usage.with_label_values(labels).set(disk);
-        metrics.network_tx.with_label_values(labels).set(tx);
-
         let cost = cpu * config.pricing.get_price("CPU_USAGE")
             + mem * config.pricing.get_price("MEMORY_USAGE_GB")
             + disk * config.pricing.get_price("DISK_USAGE_GB")
             + tx * config.pricing.get_price("NETWORK_TX_GB");
-
-        metrics.service_cost.with_label_values(labels).set(cost);
         total_cost += cost;
 
+        // Deleted services are not in the project list; filter them by ID/name here
+        let is_excluded = excluded.contains(sid)
+            || (!services.contains_key(sid) && !filter.allows(sid, name, groups));
+        if is_excluded {
+            let bucket = other.get_or_insert_with(|| ServiceData::other(filter.other_name()));
+            bucket.cpu_usage += cpu;
+            bucket.memory_usage += mem;
+            bucket.disk_usage += disk;
+            bucket.network_tx += tx;
+            bucket.cost_usd += cost;
+            continue;
+        }
+
         // Check if service is deleted (exists in usage but not in services list)
         let is_deleted = !services.contains_key(sid);
 
@@ -151,6 +184,8 @@ pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(
             name: name.clone(),
             icon: icon.clone(),
             group: group.clone(),
+            groups: groups.clone(),
+            group_matches: group_matches.remove(sid).unwrap_or_default(),
             cpu_usage: cpu,
             memory_usage: mem,
             disk_usage: disk,
@@ -161,6 +196,10 @@ pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(
         });
     }
 
+    if let Some(bucket) = other {
+        services_data.push(bucket);
+    }
+
     // Get estimated usage
     let estimated = client.get_estimated_usage(&config.project_id).await?;
     let est_monthly: f64 = estimated
@@ -173,13 +212,51 @@ pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(
         for service in &mut services_data {
             let ratio = service.cost_usd / total_cost;
             service.estimated_monthly_usd = est_monthly * ratio;
+        }
+    }
 
-            let labels = &[
-                service.name.as_str(),
-                project_name,
-                service.icon.as_str(),
-                service.group.as_str(),
-            ];
+    // Calculate billing period
+    let now = Utc::now();
+
+    // Cost centers: update open period, freeze the previous one on month change.
+    // Matched against Railway names, before relabeling.
+    if !config.cost_centers.is_empty() {
+        record_chargeback(state, &services_data, project_name, now).await;
+    }
+
+    // Relabeling and max_series guard, shared by gauges and JSON
+    let services_data = metrics.relabel.apply(services_data, filter.other_name());
+
+    // Per-service gauges (reset first so renamed or collapsed services don't linger)
+    metrics.reset_services();
+    for service in &services_data {
+        let labels = &[
+            service.name.as_str(),
+            project_name.as_str(),
+            service.icon.as_str(),
+            service.group.as_str(),
+        ];
+        metrics
+            .cpu_usage
+            .with_label_values(labels)
+            .set(service.cpu_usage);
+        metrics
+            .memory_usage
+            .with_label_values(labels)
+            .set(service.memory_usage);
+        metrics
+            .disk_usage
+            .with_label_values(labels)
+            .set(service.disk_usage);
+        metrics
+            .network_tx
+            .with_label_values(labels)
+            .set(service.network_tx);
+        metrics
+            .service_cost
+            .with_label_values(labels)
+            .set(service.cost_usd);
+        if total_cost > 0.0 {
             metrics
                 .service_estimated_monthly
                 .with_label_values(labels)
@@ -187,6 +264,60 @@ pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(
         }
     }
 
+    // Group-level rollups (reset first so removed groups don't linger)
+    let groups = summarize_groups(&services_data, total_cost);
+    metrics.reset_groups();
+    for g in &groups {
+        let labels = &[g.name.as_str(), project_name.as_str()];
+        metrics
+            .group_cpu_usage
+            .with_label_values(labels)
+            .set(g.cpu_usage);
+        metrics
+            .group_memory_usage
+            .with_label_values(labels)
+            .set(g.memory_usage);
+        metrics
+            .group_disk_usage
+            .with_label_values(labels)
+            .set(g.disk_usage);
+        metrics
+            .group_network_tx
+            .with_label_values(labels)
+            .set(g.network_tx);
+        metrics.group_cost.with_label_values(labels).set(g.cost_usd);
+        metrics
+            .group_estimated_monthly
+            .with_label_values(labels)
+            .set(g.estimated_monthly_usd);
+        metrics
+            .group_services
+            .with_label_values(labels)
+            .set(g.service_count as f64);
+    }
+
+    // Shared-cost redistribution (only when rules are configured). Multi-group
+    // services are split between their groups so no cost is allocated twice.
+    let allocated = if config.cost_allocation.is_empty() {
+        Vec::new()
+    } else if config.service_groups.multi_group() {
+        let shares = summarize_group_shares(&services_data, total_cost);
+        allocation::allocate(&shares, &config.cost_allocation)
+    } else {
+        allocation::allocate(&groups, &config.cost_allocation)
+    };
+    for g in &allocated {
+        let labels = &[g.name.as_str(), project_name.as_str()];
+        metrics
+            .group_allocated_cost
+            .with_label_values(labels)
+            .set(g.allocated_cost_usd);
+        metrics
+            .group_allocated_estimated_monthly
+            .with_label_values(labels)
+            .set(g.allocated_estimated_monthly_usd);
+    }
+
     // Project-level metrics
     metrics
         .current_usage
@@ -197,8 +328,6 @@ pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(
         .with_label_values(&[project_name])
         .set(est_monthly);
 
-    // Calculate billing period
-    let now = Utc::now();
     let days_elapsed = now.day();
     let days_in_month = days_in_current_month(now.year(), now.month());
     let days_remaining = days_in_month - days_elapsed;
@@ -239,6 +368,8 @@ pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(
             days_remaining,
         },
         services: services_data,
+        groups,
+        allocated,
         scrape_timestamp: timestamp,
         scrape_duration_seconds: scrape_duration,
     };
@@ -270,6 +401,153 @@ pub async fn collect_metrics(client: &Client, state: &Arc<AppState>) -> Result<(
     Ok(())
 }
 
+/// Updates the chargeback ledger and cost center gauges from this scrape.
+async fn record_chargeback(
+    state: &AppState,
+    services: &[ServiceData],
+    project_name: &str,
+    now: DateTime<Utc>,
+) {
+    let config = &state.config;
+    let metrics = &state.metrics;
+    let rows = config.cost_centers.summarize(services);
+
+    metrics.cost_center_cost.reset();
+    metrics.cost_center_estimated_monthly.reset();
+    for row in &rows {
+        let labels = &[
+            row.dimension.as_str(),
+            row.cost_center.as_str(),
+            project_name,
+        ];
+        metrics
+            .cost_center_cost
+            .with_label_values(labels)
+            .set(row.cost_usd);
+        metrics
+            .cost_center_estimated_monthly
+            .with_label_values(labels)
+            .set(row.estimated_monthly_usd);
+    }
+
+    let period = ChargebackPeriod {
+        period: now.format("%Y-%m").to_string(),
+        project: project_name.to_string(),
+        closed: false,
+        updated_at: now.timestamp(),
+        rows,
+    };
+
+    let mut ledger = state.chargeback.write().await;
+    if let Some(frozen) = ledger.record(period, config.chargeback.history_months) {
+        info!("Billing period {} closed, chargeback frozen", frozen.period);
+    }
+
+    // Persist every snapshot so a restart across the month boundary still
+    // freezes the previous period with its last observed values. Serialized
+    // under the lock, written after releasing it.
+    let snapshot = config.chargeback.state_file.as_ref().and_then(|path| {
+        match serde_json::to_vec_pretty(&*ledger) {
+            Ok(json) => Some((path, json)),
+            Err(e) => {
+                warn!("Failed to serialize chargeback state: {}", e);
+                None
+            }
+        }
+    });
+
+    // Only the last closed period is exported to keep cardinality bounded
+    metrics.cost_center_closed_cost.reset();
+    if let Some(closed) = ledger.last_closed() {
+        for row in &closed.rows {
+            metrics
+                .cost_center_closed_cost
+                .with_label_values(&[
+                    row.dimension.as_str(),
+                    row.cost_center.as_str(),
+                    closed.project.as_str(),
+                    closed.period.as_str(),
+                ])
+                .set(row.cost_usd);
+        }
+    }
+    drop(ledger);
+
+    if let Some((path, json)) = snapshot {
+        if let Err(e) = chargeback::write_state(path, &json).await {
+            warn!("Failed to persist chargeback state to {}: {}", path, e);
+        }
+    }
+}
+
+/// Aggregates services into per-group totals, sorted by cost (highest first).
+///
+/// A service contributes in full to every group listed in `ServiceData::groups`,
+/// so with `service_groups_multi` the group totals are not additive.
+pub(crate) fn summarize_groups(services: &[ServiceData], total_cost: f64) -> Vec<GroupSummary> {
+    rollup(services, total_cost, false)
+}
+
+/// Like [`summarize_groups`], but a service in several groups contributes an
+/// equal share of its usage and cost to each, so the groups add up to the
+/// project total. Input for cost allocation.
+pub(crate) fn summarize_group_shares(
+    services: &[ServiceData],
+    total_cost: f64,
+) -> Vec<GroupSummary> {
+    rollup(services, total_cost, true)
+}
+
+fn rollup(services: &[ServiceData], total_cost: f64, split: bool) -> Vec<GroupSummary> {
+    let mut by_group: HashMap<&str, GroupSummary> = HashMap::new();
+
+    for service in services {
+        let weight = if split && !service.groups.is_empty() {
+            1.0 / service.groups.len() as f64
+        } else {
+            1.0
+        };
+        for group in &service.groups {
+            let entry = by_group
+                .entry(group.as_str())
+                .or_insert_with(|| GroupSummary {
+                    name: group.clone(),
+                    service_count: 0,
+                    cpu_usage: 0.0,
+                    memory_usage: 0.0,
+                    disk_usage: 0.0,
+                    network_tx: 0.0,
+                    cost_usd: 0.0,
+                    estimated_monthly_usd: 0.0,
+                    cost_share: 0.0,
+                });
+            entry.service_count += 1;
+            entry.cpu_usage += service.cpu_usage * weight;
+            entry.memory_usage += service.memory_usage * weight;
+            entry.disk_usage += service.disk_usage * weight;
+            entry.network_tx += service.network_tx * weight;
+            entry.cost_usd += service.cost_usd * weight;
+            entry.estimated_monthly_usd += service.estimated_monthly_usd * weight;
+        }
+    }
+
+    let mut groups: Vec<GroupSummary> = by_group
+        .into_values()
+        .map(|mut g| {
+            if total_cost > 0.0 {
+                g.cost_share = g.cost_usd / total_cost;
+            }
+            g
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.cost_usd
+            .total_cmp(&a.cost_usd)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    groups
+}
+
 /// Calculates days in a given month.
 pub(crate) fn days_in_current_month(year: i32, month: u32) -> u32 {
     if month == 12 {
@@ -1,9 +1,15 @@
 //! Tests for metrics collector with mock Railway API.
 
+use crate::allocation::{allocate, AllocationMethod, AllocationRule};
 use crate::client::Client;
-use crate::collector::{collect_metrics, days_in_current_month};
+use crate::collector::{
+    collect_metrics, days_in_current_month, summarize_group_shares, summarize_groups,
+};
 use crate::config::Plan;
+use crate::grouping::{GroupRule, ServiceGroups};
 use crate::state::AppState;
+use crate::test_support::service;
+use crate::types::OTHER_ID;
 use crate::Config;
 use http_body_util::Full;
 use hyper::body::Bytes;
@@ -47,6 +53,74 @@ fn test_days_in_december() {
     assert_eq!(days_in_current_month(2024, 12), 31);
 }
 
+// =============================================================================
+// summarize_groups Tests
+// =============================================================================
+
+#[test]
+fn test_summarize_groups_totals_and_share() {
+    let services = vec![
+        service("api", &["backend"], 2.0),
+        service("worker", &["backend"], 1.0),
+        service("postgres", &["database"], 1.0),
+    ];
+    let groups = summarize_groups(&services, 4.0);
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].name, "backend");
+    assert_eq!(groups[0].service_count, 2);
+    assert_eq!(groups[0].cost_usd, 3.0);
+    assert_eq!(groups[0].cpu_usage, 30.0);
+    assert_eq!(groups[0].network_tx, 2.0);
+    assert_eq!(groups[0].estimated_monthly_usd, 9.0);
+    assert_eq!(groups[0].cost_share, 0.75);
+    assert_eq!(groups[1].name, "database");
+    assert_eq!(groups[1].cost_share, 0.25);
+}
+
+#[test]
+fn test_summarize_groups_multi_membership() {
+    let services = vec![service("gateway", &["backend", "public"], 2.0)];
+    let groups = summarize_groups(&services, 2.0);
+
+    assert_eq!(groups.len(), 2);
+    assert!(groups
+        .iter()
+        .all(|g| g.cost_usd == 2.0 && g.cost_share == 1.0));
+}
+
+#[test]
+fn test_summarize_group_shares_split_multi_membership() {
+    let services = vec![
+        service("gateway", &["backend", "public"], 2.0),
+        service("db", &["database"], 2.0),
+    ];
+    let shares = summarize_group_shares(&services, 4.0);
+    let cost = |name: &str| shares.iter().find(|g| g.name == name).unwrap().cost_usd;
+    assert_eq!(cost("backend"), 1.0);
+    assert_eq!(cost("public"), 1.0);
+    assert_eq!(cost("database"), 2.0);
+    assert_eq!(shares.iter().map(|g| g.cost_usd).sum::<f64>(), 4.0);
+
+    // Allocated totals stay additive
+    let rules = vec![AllocationRule {
+        source: "database".to_string(),
+        targets: vec!["backend".to_string(), "public".to_string()],
+        method: AllocationMethod::Even,
+        weights: Default::default(),
+    }];
+    let allocated = allocate(&shares, &rules);
+    let total: f64 = allocated.iter().map(|g| g.allocated_cost_usd).sum();
+    assert!((total - 4.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_summarize_groups_zero_cost() {
+    let services = vec![service("idle", &["ungrouped"], 0.0)];
+    let groups = summarize_groups(&services, 0.0);
+    assert_eq!(groups[0].cost_share, 0.0);
+}
+
 // =============================================================================
 // Mock GraphQL Server
 // =============================================================================
@@ -193,6 +267,11 @@ async fn test_collect_metrics_api_error() {
     assert_eq!(status.total_scrapes, 1);
     assert_eq!(status.failed_scrapes, 1);
     assert!(status.last_error.is_some());
+
+    let scrapes = &state.metrics.scrapes;
+    assert_eq!(scrapes.with_label_values(&["failure"]).get(), 1);
+    assert_eq!(scrapes.with_label_values(&["success"]).get(), 0);
+    assert_eq!(state.metrics.collection_duration.get_sample_count(), 1);
 }
 
 #[tokio::test]
@@ -224,9 +303,8 @@ async fn test_collect_metrics_with_service_groups() {
 
     let mut config = Config::new("test-token", "project-123", Plan::Pro, 300, 9090);
     config.api_url = api_url.clone();
-    config
-        .service_groups
-        .insert("backend".to_string(), vec!["api".to_string()]);
+    config.service_groups =
+        ServiceGroups::new(vec![GroupRule::contains("backend", &["API"])], false).unwrap();
 
     let state = Arc::new(AppState::new(config));
     let client = Client::new("test-token", Some(&api_url));
@@ -252,3 +330,114 @@ async fn test_collect_metrics_with_service_groups() {
         .unwrap();
     assert_eq!(web_service.group, "ungrouped");
 }
+
+#[tokio::test]
+async fn test_status_reports_groups_recorded_at_collection() {
+    let api_url = start_mock_railway_server().await;
+
+    let mut config = Config::new("test-token", "project-123", Plan::Pro, 300, 9090);
+    config.api_url = api_url.clone();
+    config.service_groups =
+        ServiceGroups::new(vec![GroupRule::contains("backend", &["api"])], false).unwrap();
+    // The exported name no longer matches the rule that grouped it
+    config.metrics = serde_yaml::from_str(
+        "relabel:\n  - label: service\n    action: replace\n    regex: api\n    replacement: gateway",
+    )
+    .unwrap();
+
+    let state = Arc::new(AppState::new(config));
+    let client = Client::new("test-token", Some(&api_url));
+
+    collect_metrics(&client, &state).await.unwrap();
+
+    let (_builder, body) = crate::handlers::status(&state).await;
+    let parsed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let assignments = parsed["service_groups"].as_array().unwrap();
+    let gateway = assignments
+        .iter()
+        .find(|a| a["service"] == "gateway")
+        .unwrap();
+    assert_eq!(gateway["group"], "backend");
+    assert_eq!(gateway["groups"], serde_json::json!(["backend"]));
+    assert_eq!(gateway["matches"][0]["pattern"], "api");
+
+    let web = assignments.iter().find(|a| a["service"] == "web").unwrap();
+    assert_eq!(web["group"], "ungrouped");
+    assert!(web["matches"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_collect_metrics_exports_group_rollups() {
+    let api_url = start_mock_railway_server().await;
+
+    let mut config = Config::new("test-token", "project-123", Plan::Pro, 300, 9090);
+    config.api_url = api_url.clone();
+    config.service_groups =
+        ServiceGroups::new(vec![GroupRule::contains("backend", &["api"])], false).unwrap();
+
+    let state = Arc::new(AppState::new(config));
+    let client = Client::new("test-token", Some(&api_url));
+
+    collect_metrics(&client, &state).await.unwrap();
+
+    let json = state.metrics_json.read().await;
+    let groups = &json.as_ref().unwrap().groups;
+    let names: Vec<&str> = groups.iter().map(|g| g.name.as_str()).collect();
+    assert!(names.contains(&"backend"));
+    assert!(names.contains(&"ungrouped"));
+    let share: f64 = groups.iter().map(|g| g.cost_share).sum();
+    assert!((share - 1.0).abs() < 1e-9);
+
+    let output = state.metrics.encode();
+    assert!(output.contains("railway_group_cost_usd{group=\"backend\",project=\"test-project\"}"));
+    assert!(output.contains("railway_group_estimated_monthly_usd"));
+    assert!(output.contains("railway_group_cpu_usage_vcpu_minutes"));
+}
+
+#[tokio::test]
+async fn test_collect_metrics_excluded_services_fold_into_other() {
+    use crate::filter::{FilterConfig, FilterEntry, ServiceFilter};
+    use crate::grouping::MatchKind;
+
+    let api_url = start_mock_railway_server().await;
+
+    let mut config = Config::new("test-token", "project-123", Plan::Pro, 300, 9090);
+    config.api_url = api_url.clone();
+    config.service_filter = ServiceFilter::new(FilterConfig {
+        exclude: vec![FilterEntry {
+            name: Some("web".to_string()),
+            id: None,
+            group: None,
+            kind: MatchKind::Exact,
+            case_sensitive: false,
+        }],
+        ..FilterConfig::default()
+    })
+    .unwrap();
+
+    let state = Arc::new(AppState::new(config));
+    let client = Client::new("test-token", Some(&api_url));
+
+    collect_metrics(&client, &state).await.unwrap();
+
+    let json = state.metrics_json.read().await;
+    let metrics_json = json.as_ref().unwrap();
+    let names: Vec<&str> = metrics_json
+        .services
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["api", "other"]);
+
+    // Totals still include the excluded spend
+    let other = &metrics_json.services[1];
+    assert_eq!(other.id, OTHER_ID);
+    assert_eq!(other.groups, vec!["ungrouped".to_string()]);
+    assert_eq!(other.cpu_usage, 200.0);
+    let sum: f64 = metrics_json.services.iter().map(|s| s.cost_usd).sum();
+    assert!((sum - metrics_json.project.current_usage_usd).abs() < 1e-9);
+
+    let output = state.metrics.encode();
+    assert!(!output.contains("service=\"web\""));
+    assert!(output.contains("service=\"other\""));
+}
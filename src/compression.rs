@@ -0,0 +1,133 @@
+//! Response compression (zstd, brotli, gzip).
+//!
+//! The coding is picked from `Accept-Encoding` by q-value; codings with equal
+//! weight are preferred in the order br, zstd, gzip. `*` covers codings the
+//! client does not list and `q=0` rejects one. Each coding has its own
+//! section (`gzip`, `brotli`, `zstd`) with `enabled`, `min_size` and `level`;
+//! bodies no larger than `min_size` are sent as they are.
+//!
+//! Responses that could have been compressed carry `Vary: Accept-Encoding`
+//! so shared caches keep one copy per coding.
+
+use crate::config::{BrotliConfig, GzipConfig, ZstdConfig};
+use crate::utils::negotiate::quality_list;
+use flate2::write::GzEncoder;
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, Write};
+
+/// A supported `Content-Encoding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Brotli,
+    Zstd,
+    Gzip,
+}
+
+impl Encoding {
+    /// Every coding, in server preference order.
+    pub const ALL: [Encoding; 3] = [Encoding::Brotli, Encoding::Zstd, Encoding::Gzip];
+
+    /// `Content-Encoding` token.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /// Returns true if an `Accept-Encoding` token names this coding.
+    fn matches(self, token: &str) -> bool {
+        token == self.as_str() || (self == Encoding::Gzip && token == "x-gzip")
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Picks the coding the client prefers among `available`.
+///
+/// Returns None if the client accepts none of them (or sent no
+/// `Accept-Encoding`, which means it only asked for the identity coding).
+pub fn select(
+    accept_encoding: Option<&str>,
+    available: impl IntoIterator<Item = Encoding>,
+) -> Option<Encoding> {
+    let accepted = quality_list(accept_encoding?);
+    let weight = |encoding: Encoding| {
+        accepted
+            .iter()
+            .find(|item| encoding.matches(&item.value))
+            .or_else(|| accepted.iter().find(|item| item.value == "*"))
+            .map_or(0.0, |item| item.q)
+    };
+
+    let mut best: Option<(Encoding, f32)> = None;
+    for encoding in available {
+        let q = weight(encoding);
+        let rank = |e: Encoding| Encoding::ALL.iter().position(|a| *a == e);
+        let better = match best {
+            None => q > 0.0,
+            Some((current, best_q)) => {
+                q > best_q || (q == best_q && rank(encoding) < rank(current))
+            }
+        };
+        if better {
+            best = Some((encoding, q));
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+/// Settings of every coding.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Compression {
+    pub gzip: GzipConfig,
+    pub brotli: BrotliConfig,
+    pub zstd: ZstdConfig,
+}
+
+impl Compression {
+    /// Codings enabled for a body of `len` bytes.
+    pub fn available(&self, len: usize) -> impl Iterator<Item = Encoding> + '_ {
+        Encoding::ALL.into_iter().filter(move |encoding| {
+            let (enabled, min_size) = match encoding {
+                Encoding::Brotli => (self.brotli.enabled, self.brotli.min_size),
+                Encoding::Zstd => (self.zstd.enabled, self.zstd.min_size),
+                Encoding::Gzip => (self.gzip.enabled, self.gzip.min_size),
+            };
+            enabled && len > min_size
+        })
+    }
+
+    /// Best coding for a body of `len` bytes.
+    pub fn negotiate(&self, accept_encoding: Option<&str>, len: usize) -> Option<Encoding> {
+        select(accept_encoding, self.available(len))
+    }
+
+    /// Compresses `data` with the configured level.
+    pub fn compress(&self, encoding: Encoding, data: &[u8]) -> io::Result<Vec<u8>> {
+        match encoding {
+            Encoding::Gzip => {
+                let level = flate2::Compression::new(self.gzip.level);
+                let mut encoder = GzEncoder::new(Vec::new(), level);
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Encoding::Brotli => {
+                let params = brotli::enc::BrotliEncoderParams {
+                    quality: self.brotli.level as i32,
+                    ..Default::default()
+                };
+                let mut out = Vec::new();
+                brotli::BrotliCompress(&mut &data[..], &mut out, &params)?;
+                Ok(out)
+            }
+            Encoding::Zstd => zstd::bulk::compress(data, self.zstd.level),
+        }
+    }
+}
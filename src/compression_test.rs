@@ -0,0 +1,127 @@
+//! Tests for response compression.
+
+use crate::compression::{select, Compression, Encoding};
+use crate::config::{GzipConfig, YamlConfig};
+use std::io::Read;
+
+const ALL: [Encoding; 3] = Encoding::ALL;
+
+fn payload() -> Vec<u8> {
+    r#"{"service":"web","icon":"data:image/png;base64,iVBORw0KGgo"}"#
+        .repeat(100)
+        .into_bytes()
+}
+
+// =============================================================================
+// Negotiation Tests
+// =============================================================================
+
+#[test]
+fn test_select_by_quality() {
+    assert_eq!(select(Some("gzip, br;q=0.5"), ALL), Some(Encoding::Gzip));
+    assert_eq!(
+        select(Some("gzip;q=0.8, zstd;q=0.9"), ALL),
+        Some(Encoding::Zstd)
+    );
+    assert_eq!(select(Some("x-gzip"), ALL), Some(Encoding::Gzip));
+    assert_eq!(select(Some("GZIP"), ALL), Some(Encoding::Gzip));
+}
+
+#[test]
+fn test_select_ties_prefer_server_order() {
+    assert_eq!(
+        select(Some("gzip, deflate, br, zstd"), ALL),
+        Some(Encoding::Brotli)
+    );
+    assert_eq!(select(Some("gzip, zstd"), ALL), Some(Encoding::Zstd));
+    assert_eq!(select(Some("*"), ALL), Some(Encoding::Brotli));
+}
+
+#[test]
+fn test_select_rejections() {
+    // No header means identity only
+    assert_eq!(select(None, ALL), None);
+    assert_eq!(select(Some(""), ALL), None);
+    assert_eq!(select(Some("identity, deflate"), ALL), None);
+
+    // q=0 rejects a coding, even when the wildcard allows the rest
+    assert_eq!(select(Some("gzip;q=0"), ALL), None);
+    assert_eq!(select(Some("br;q=0, *"), ALL), Some(Encoding::Zstd));
+    assert_eq!(select(Some("*;q=0"), ALL), None);
+
+    // Only codings the server has are picked
+    assert_eq!(
+        select(Some("br, gzip;q=0.1"), [Encoding::Gzip]),
+        Some(Encoding::Gzip)
+    );
+    assert_eq!(select(Some("br"), [Encoding::Gzip]), None);
+}
+
+#[test]
+fn test_negotiate_respects_config() {
+    let mut compression = Compression::default();
+    compression.brotli.enabled = false;
+    compression.zstd.min_size = 4096;
+
+    let accept = Some("br, zstd, gzip");
+    assert_eq!(compression.negotiate(accept, 1000), Some(Encoding::Gzip));
+    assert_eq!(compression.negotiate(accept, 5000), Some(Encoding::Zstd));
+    assert_eq!(compression.negotiate(accept, 100), None);
+    assert_eq!(compression.available(100).count(), 0);
+}
+
+// =============================================================================
+// Encoder Tests
+// =============================================================================
+
+#[test]
+fn test_compress_round_trips() {
+    let compression = Compression::default();
+    let data = payload();
+
+    let gzip = compression.compress(Encoding::Gzip, &data).unwrap();
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(&gzip[..])
+        .read_to_end(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, data);
+
+    let br = compression.compress(Encoding::Brotli, &data).unwrap();
+    let mut decoded = Vec::new();
+    brotli::Decompressor::new(&br[..], 4096)
+        .read_to_end(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, data);
+
+    let zstd = compression.compress(Encoding::Zstd, &data).unwrap();
+    assert_eq!(zstd::decode_all(&zstd[..]).unwrap(), data);
+
+    for body in [gzip, br, zstd] {
+        assert!(body.len() < data.len() / 10);
+    }
+}
+
+#[test]
+fn test_yaml_sections() {
+    let yaml = r#"
+gzip:
+  level: 6
+brotli:
+  enabled: false
+  level: 11
+zstd:
+  min_size: 1024
+"#;
+    let config: YamlConfig = serde_yaml::from_str(yaml).unwrap();
+    let compression = Compression {
+        gzip: config.gzip.unwrap(),
+        brotli: config.brotli.unwrap(),
+        zstd: config.zstd.unwrap(),
+    };
+    assert_eq!(compression.gzip.min_size, GzipConfig::default().min_size);
+    assert!(!compression.brotli.enabled);
+    assert_eq!(compression.brotli.level, 11);
+    assert!(compression.zstd.enabled);
+    assert_eq!(compression.zstd.min_size, 1024);
+    assert_eq!(compression.zstd.level, 3);
+}
@@ -26,6 +26,15 @@
 //!   min_size: 256       # Minimum response size in bytes (default: 256)
 //!   level: 1            # Compression level 1-9 (default: 1 = fast)
 //!
+//! # Brotli and zstd (same fields; preferred over gzip when the client allows)
+//! brotli:
+//!   level: 4            # 0-11 (default: 4)
+//! zstd:
+//!   level: 3            # 1-19 (default: 3)
+//!
+//! # Dashboard files (default: embedded dashboard if built in, else /static)
+//! static_dir: /srv/dashboard
+//!
 //! # Icon cache settings (LRU cache with raw bytes storage)
 //! icon_cache:
 //!   enabled: true       # Enable icon caching (default: true)
@@ -43,19 +52,87 @@
 //!       cpu: 0.000231
 //!       memory: 0.000116
 //!
+//! # Ordered grouping rules (see `grouping` module for matcher types)
 //! service_groups:
-//!   monitoring:
-//!     - prometheus
-//!     - grafana
-//!   database:
-//!     - postgres
-//!     - redis
+//!   - group: monitoring
+//!     patterns: [prometheus, grafana]
+//!   - group: database
+//!     match: glob
+//!     patterns: ["postgres*", "redis*"]
+//! service_groups_multi: false   # true = join every matching group
+//!
+//! # Shared-cost redistribution (see `allocation` module)
+//! cost_allocation:
+//!   - source: database
+//!     targets: [api, worker]
+//!     method: proportional
+//!
+//! # Drop throwaway services (see `filter` module)
+//! service_filter:
+//!   exclude:
+//!     - name: "pr-*"
+//!       match: glob
+//!
+//! # Cost centers for chargeback (see `chargeback` module)
+//! cost_centers:
+//!   - name: platform
+//!     dimension: team
+//!     patterns: [postgres, redis]
+//!
+//! # Push sinks fed after every collection (see `sinks` module)
+//! sinks:
+//!   remote_write:
+//!     url: https://prometheus.example.com/api/v1/write
+//!     bearer_token: "secret"
+//!   otlp:
+//!     endpoint: http://otel-collector:4318/v1/metrics
+//!
+//! # Metric name prefix and constant labels (see `metrics` module)
+//! metrics:
+//!   prefix: railway
+//!   const_labels:
+//!     env: prod
+//!
+//! # Reverse proxies trusted for X-Forwarded-For (auth, limits, access log)
+//! trusted_proxies: ["10.0.0.0/8"]
+//!
+//! # Endpoint authentication (see `auth` module)
+//! auth:
+//!   tokens:
+//!     grafana: "long-random-token"
+//!   routes:
+//!     - path: /health
+//!       methods: []
+//!
+//! # Access log (see `access_log` module)
+//! access_log:
+//!   format: combined
+//!   exclude_paths: [/health]
+//!
+//! # Rate and connection limits (see `limits` module)
+//! limits:
+//!   max_connections: 1024
+//!   rate_limit:
+//!     requests_per_second: 5
 //! ```
 
+use crate::access_log::{AccessLog, AccessLogConfig};
+use crate::allocation::AllocationRule;
+use crate::auth::{Auth, AuthConfig, TrustedProxies};
+use crate::chargeback::{ChargebackConfig, CostCenterRule, CostCenters};
+use crate::compression::Compression;
+use crate::cors::{CorsConfig, CorsPolicy};
+use crate::filter::{FilterConfig, ServiceFilter};
+use crate::grouping::{GroupRule, MatchKind, ServiceGroups};
+use crate::handlers::StaticFiles;
+use crate::limits::{Limits, LimitsConfig};
+use crate::listen::ListenAddr;
+use crate::metrics::MetricsConfig;
 use crate::pricing::PricingConfig;
+use crate::sinks::SinksConfig;
+use crate::tls::TlsConfig;
 use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::Deserialize;
-use std::collections::HashMap;
 use std::env;
 use std::fmt;
 use std::fs;
@@ -133,19 +210,53 @@ pub(crate) struct YamlConfig {
     pub(crate) railway_plan: Option<Plan>,
     pub(crate) railway_api_url: Option<String>,
     pub(crate) port: Option<u16>,
+    /// Listen addresses (default: 0.0.0.0:port).
+    pub(crate) listen: Option<Vec<String>>,
     pub(crate) scrape_interval: Option<u16>,
     pub(crate) pricing: Option<PricingSection>,
-    pub(crate) service_groups: Option<HashMap<String, Vec<String>>>,
+    pub(crate) service_groups: Option<ServiceGroupsSection>,
+    /// Allow a service to belong to every matching group.
+    pub(crate) service_groups_multi: Option<bool>,
+    /// Service include/exclude filters.
+    pub(crate) service_filter: Option<FilterConfig>,
+    /// Shared-cost redistribution rules.
+    pub(crate) cost_allocation: Option<Vec<AllocationRule>>,
+    /// Cost center rules for chargeback.
+    pub(crate) cost_centers: Option<Vec<CostCenterRule>>,
+    /// Chargeback ledger settings.
+    pub(crate) chargeback: Option<ChargebackConfig>,
+    /// Push sinks fed after every collection.
+    pub(crate) sinks: Option<SinksConfig>,
+    /// Metric name prefix and constant labels.
+    pub(crate) metrics: Option<MetricsConfig>,
     /// Project display name (for /status endpoint).
     pub(crate) project_name: Option<String>,
     /// Enable CORS headers on all responses.
     pub(crate) cors_enabled: Option<bool>,
+    /// CORS policy (default: any origin).
+    pub(crate) cors: Option<CorsConfig>,
     /// Enable WebSocket endpoint.
     pub(crate) websocket_enabled: Option<bool>,
     /// Gzip compression settings.
     pub(crate) gzip: Option<GzipConfig>,
+    /// Brotli compression settings.
+    pub(crate) brotli: Option<BrotliConfig>,
+    /// Zstandard compression settings.
+    pub(crate) zstd: Option<ZstdConfig>,
+    /// Directory with the dashboard's static export.
+    pub(crate) static_dir: Option<String>,
     /// Icon cache settings.
     pub(crate) icon_cache: Option<IconCacheConfig>,
+    /// Native TLS termination.
+    pub(crate) tls: Option<TlsConfig>,
+    /// Reverse proxies whose forwarding headers are trusted.
+    pub(crate) trusted_proxies: Option<Vec<String>>,
+    /// Endpoint authentication.
+    pub(crate) auth: Option<AuthConfig>,
+    /// Request access log.
+    pub(crate) access_log: Option<AccessLogConfig>,
+    /// Rate limits, connection limits and timeouts.
+    pub(crate) limits: Option<LimitsConfig>,
 }
 
 use serde::Serialize;
@@ -154,20 +265,20 @@ use serde::Serialize;
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct GzipConfig {
     /// Enable gzip compression for HTTP responses.
-    #[serde(default = "default_gzip_enabled")]
+    #[serde(default = "default_compression_enabled")]
     pub enabled: bool,
     /// Minimum response size in bytes to trigger compression.
-    #[serde(default = "default_gzip_min_size")]
+    #[serde(default = "default_compression_min_size")]
     pub min_size: usize,
     /// Compression level (1-9). 1 = fast, 9 = best compression.
     #[serde(default = "default_gzip_level")]
     pub level: u32,
 }
 
-fn default_gzip_enabled() -> bool {
+fn default_compression_enabled() -> bool {
     true
 }
-fn default_gzip_min_size() -> usize {
+fn default_compression_min_size() -> usize {
     256
 }
 fn default_gzip_level() -> u32 {
@@ -177,13 +288,69 @@ fn default_gzip_level() -> u32 {
 impl Default for GzipConfig {
     fn default() -> Self {
         Self {
-            enabled: default_gzip_enabled(),
-            min_size: default_gzip_min_size(),
+            enabled: default_compression_enabled(),
+            min_size: default_compression_min_size(),
             level: default_gzip_level(),
         }
     }
 }
 
+/// Brotli compression configuration.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BrotliConfig {
+    /// Enable brotli compression for HTTP responses.
+    #[serde(default = "default_compression_enabled")]
+    pub enabled: bool,
+    /// Minimum response size in bytes to trigger compression.
+    #[serde(default = "default_compression_min_size")]
+    pub min_size: usize,
+    /// Compression level (0-11). 0 = fast, 11 = best compression.
+    #[serde(default = "default_brotli_level")]
+    pub level: u32,
+}
+
+fn default_brotli_level() -> u32 {
+    4
+}
+
+impl Default for BrotliConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_compression_enabled(),
+            min_size: default_compression_min_size(),
+            level: default_brotli_level(),
+        }
+    }
+}
+
+/// Zstandard compression configuration.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ZstdConfig {
+    /// Enable zstd compression for HTTP responses.
+    #[serde(default = "default_compression_enabled")]
+    pub enabled: bool,
+    /// Minimum response size in bytes to trigger compression.
+    #[serde(default = "default_compression_min_size")]
+    pub min_size: usize,
+    /// Compression level (1-19). 1 = fast, 19 = best compression.
+    #[serde(default = "default_zstd_level")]
+    pub level: i32,
+}
+
+fn default_zstd_level() -> i32 {
+    3
+}
+
+impl Default for ZstdConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_compression_enabled(),
+            min_size: default_compression_min_size(),
+            level: default_zstd_level(),
+        }
+    }
+}
+
 /// Icon delivery mode.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -281,6 +448,41 @@ pub struct PricingEntry {
 /// Pricing section as array of named entries.
 type PricingSection = Vec<PricingEntry>;
 
+/// Service groups section: ordered rule list, or legacy `group: [patterns]` map.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ServiceGroupsSection {
+    Rules(Vec<GroupRule>),
+    /// Legacy map form. `Mapping` keeps file order, so the first group listed wins.
+    Legacy(serde_yaml::Mapping),
+}
+
+impl ServiceGroupsSection {
+    /// Converts either form into ordered rules.
+    pub(crate) fn into_rules(self) -> Result<Vec<GroupRule>, ConfigError> {
+        match self {
+            ServiceGroupsSection::Rules(rules) => Ok(rules),
+            ServiceGroupsSection::Legacy(map) => map
+                .into_iter()
+                .map(|(k, v)| {
+                    let group = k.as_str().map(str::to_string).ok_or_else(|| {
+                        ConfigError::InvalidValue("service_groups keys must be strings".into())
+                    })?;
+                    let patterns: Vec<String> = serde_yaml::from_value(v).map_err(|e| {
+                        ConfigError::InvalidValue(format!("service_groups.{}: {}", group, e))
+                    })?;
+                    Ok(GroupRule {
+                        group,
+                        kind: MatchKind::Contains,
+                        patterns,
+                        case_sensitive: false,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
 /// Default Railway GraphQL API URL.
 pub const DEFAULT_API_URL: &str = "https://backboard.railway.app/graphql/v2";
 
@@ -314,6 +516,9 @@ pub struct Config {
     /// HTTP server port for metrics endpoint.
     pub port: u16,
 
+    /// Sockets to listen on.
+    pub listen: Vec<ListenAddr>,
+
     /// Railway GraphQL API URL.
     pub api_url: String,
 
@@ -323,8 +528,26 @@ pub struct Config {
     /// Pricing values for current plan (for API response).
     pub pricing_values: PriceValues,
 
-    /// Service groups mapping (group name -> list of service patterns).
-    pub service_groups: HashMap<String, Vec<String>>,
+    /// Ordered service grouping rules.
+    pub service_groups: ServiceGroups,
+
+    /// Service include/exclude filters.
+    pub service_filter: ServiceFilter,
+
+    /// Shared-cost redistribution rules (applied in order).
+    pub cost_allocation: Vec<AllocationRule>,
+
+    /// Cost center rules, per dimension.
+    pub cost_centers: CostCenters,
+
+    /// Chargeback ledger settings.
+    pub chargeback: ChargebackConfig,
+
+    /// Push sinks (remote_write, OTLP, Pushgateway, StatsD, InfluxDB).
+    pub sinks: SinksConfig,
+
+    /// Metric name prefix and constant labels.
+    pub metrics: MetricsConfig,
 
     /// Project display name (for /status endpoint and frontend).
     pub project_name: String,
@@ -332,14 +555,35 @@ pub struct Config {
     /// Enable CORS headers on all responses.
     pub cors_enabled: bool,
 
+    /// Allowed origins, methods and headers (used if `cors_enabled`).
+    pub cors: CorsPolicy,
+
     /// Enable WebSocket endpoint.
     pub websocket_enabled: bool,
 
-    /// Gzip compression settings.
-    pub gzip: GzipConfig,
+    /// Gzip, brotli and zstd settings.
+    pub compression: Compression,
+
+    /// Where the dashboard is served from.
+    pub static_files: StaticFiles,
 
     /// Icon cache settings.
     pub icon_cache: IconCacheConfig,
+
+    /// TLS settings (plain HTTP if None).
+    pub tls: Option<TlsConfig>,
+
+    /// Reverse proxies whose `X-Forwarded-For` is trusted.
+    pub trusted_proxies: TrustedProxies,
+
+    /// Endpoint authentication (all endpoints open if None).
+    pub auth: Option<Auth>,
+
+    /// Request access log (disabled if None).
+    pub access_log: Option<AccessLog>,
+
+    /// Rate limits, connection limits and timeouts.
+    pub limits: Limits,
 }
 
 /// Error type for configuration loading.
@@ -423,6 +667,19 @@ impl Config {
 
         let port = yaml_config.port.unwrap_or(9090);
 
+        let listen = match yaml_config.listen {
+            Some(entries) => {
+                if entries.is_empty() {
+                    return Err(ConfigError::InvalidValue(
+                        "listen must not be empty".to_string(),
+                    ));
+                }
+                ListenAddr::parse_all(&entries, port)
+                    .map_err(|e| ConfigError::InvalidValue(format!("listen: {}", e)))?
+            }
+            None => vec![ListenAddr::any(port)],
+        };
+
         let api_url = yaml_config
             .railway_api_url
             .unwrap_or_else(|| DEFAULT_API_URL.to_string());
@@ -466,7 +723,50 @@ impl Config {
             .map(|e| e.price.clone())
             .unwrap_or_default();
 
-        let service_groups = yaml_config.service_groups.unwrap_or_default();
+        let service_groups = ServiceGroups::new(
+            match yaml_config.service_groups {
+                Some(section) => section.into_rules()?,
+                None => Vec::new(),
+            },
+            yaml_config.service_groups_multi.unwrap_or(false),
+        )?;
+
+        let service_filter = ServiceFilter::new(yaml_config.service_filter.unwrap_or_default())?;
+        for rule in service_groups.rules() {
+            for pattern in &rule.patterns {
+                service_filter.check_other_name(pattern, rule.case_sensitive)?;
+            }
+        }
+
+        let cost_allocation = yaml_config.cost_allocation.unwrap_or_default();
+        crate::allocation::validate(&cost_allocation)?;
+
+        let cost_centers = CostCenters::new(yaml_config.cost_centers.unwrap_or_default())?;
+        let chargeback = yaml_config.chargeback.unwrap_or_default();
+
+        let sinks = yaml_config.sinks.unwrap_or_default();
+        sinks.validate()?;
+
+        let metrics = yaml_config.metrics.unwrap_or_default();
+        metrics.validate()?;
+
+        let tls = yaml_config.tls;
+        if let Some(ref tls) = tls {
+            tls.validate()?;
+        }
+
+        let trusted_proxies =
+            TrustedProxies::new(&yaml_config.trusted_proxies.unwrap_or_default())?;
+        let auth = yaml_config
+            .auth
+            .map(|auth| Auth::new(auth, &trusted_proxies))
+            .transpose()?;
+        let access_log = yaml_config
+            .access_log
+            .filter(|c| c.enabled)
+            .map(AccessLog::new)
+            .transpose()?;
+        let limits = Limits::new(yaml_config.limits.unwrap_or_default())?;
 
         // Default project_name to project_id if not specified
         let project_name = yaml_config
@@ -474,15 +774,40 @@ impl Config {
             .unwrap_or_else(|| project_id.clone());
 
         let cors_enabled = yaml_config.cors_enabled.unwrap_or(true);
+        let cors = CorsPolicy::new(yaml_config.cors.unwrap_or_default())?;
         let websocket_enabled = yaml_config.websocket_enabled.unwrap_or(true);
 
-        // Gzip configuration with validation
+        // Compression configuration with validation
         let gzip = yaml_config.gzip.unwrap_or_default();
         if gzip.level < 1 || gzip.level > 9 {
             return Err(ConfigError::InvalidValue(
                 "gzip.level must be between 1 and 9".to_string(),
             ));
         }
+        let brotli = yaml_config.brotli.unwrap_or_default();
+        if brotli.level > 11 {
+            return Err(ConfigError::InvalidValue(
+                "brotli.level must be between 0 and 11".to_string(),
+            ));
+        }
+        let zstd = yaml_config.zstd.unwrap_or_default();
+        if zstd.level < 1 || zstd.level > 19 {
+            return Err(ConfigError::InvalidValue(
+                "zstd.level must be between 1 and 19".to_string(),
+            ));
+        }
+        let compression = Compression { gzip, brotli, zstd };
+
+        if yaml_config
+            .static_dir
+            .as_deref()
+            .is_some_and(|d| d.trim().is_empty())
+        {
+            return Err(ConfigError::InvalidValue(
+                "static_dir must not be empty".to_string(),
+            ));
+        }
+        let static_files = StaticFiles::new(yaml_config.static_dir);
 
         let icon_cache = yaml_config.icon_cache.unwrap_or_default();
 
@@ -492,15 +817,29 @@ impl Config {
             plan,
             scrape_interval,
             port,
+            listen,
             api_url,
             pricing,
             pricing_values,
             service_groups,
+            service_filter,
+            cost_allocation,
+            cost_centers,
+            chargeback,
+            sinks,
+            metrics,
             project_name,
             cors_enabled,
+            cors,
             websocket_enabled,
-            gzip,
+            compression,
+            static_files,
             icon_cache,
+            tls,
+            trusted_proxies,
+            auth,
+            access_log,
+            limits,
         })
     }
 
@@ -576,15 +915,29 @@ impl Config {
             plan,
             scrape_interval,
             port,
+            listen: vec![ListenAddr::any(port)],
             api_url: DEFAULT_API_URL.to_string(),
             pricing: PricingConfig::new(plan.as_str()),
             pricing_values: PriceValues::default(),
-            service_groups: HashMap::new(),
+            service_groups: ServiceGroups::default(),
+            service_filter: ServiceFilter::default(),
+            cost_allocation: Vec::new(),
+            cost_centers: CostCenters::default(),
+            chargeback: ChargebackConfig::default(),
+            sinks: SinksConfig::default(),
+            metrics: MetricsConfig::default(),
             project_name: project_id.to_string(),
             cors_enabled: true,
+            cors: CorsPolicy::default(),
             websocket_enabled: true,
-            gzip: GzipConfig::default(),
+            compression: Compression::default(),
+            static_files: StaticFiles::default(),
             icon_cache: IconCacheConfig::default(),
+            tls: None,
+            trusted_proxies: TrustedProxies::default(),
+            auth: None,
+            access_log: None,
+            limits: Limits::default(),
         }
     }
 }
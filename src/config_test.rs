@@ -1,6 +1,9 @@
 //! Configuration tests for Railway Exporter.
 
+use crate::auth::TrustedProxies;
 use crate::config::{Config, ConfigError, GzipConfig, Plan, YamlConfig};
+use crate::grouping::MatchKind;
+use crate::handlers::StaticFiles;
 use std::str::FromStr;
 
 // =============================================================================
@@ -76,9 +79,23 @@ fn test_config_pricing_uses_plan() {
 #[test]
 fn test_config_default_gzip() {
     let config = Config::new("t", "p", Plan::Hobby, 60, 8080);
-    assert!(config.gzip.enabled);
-    assert_eq!(config.gzip.min_size, 256);
-    assert_eq!(config.gzip.level, 1);
+    assert!(config.compression.gzip.enabled);
+    assert_eq!(config.compression.gzip.min_size, 256);
+    assert_eq!(config.compression.gzip.level, 1);
+}
+
+#[test]
+fn test_config_static_files() {
+    let config = Config::new("t", "p", Plan::Hobby, 60, 8080);
+    assert_eq!(config.static_files, StaticFiles::default());
+    #[cfg(not(feature = "embedded-dashboard"))]
+    assert_eq!(config.static_files, StaticFiles::Dir("/static".into()));
+
+    let yaml: YamlConfig = serde_yaml::from_str("static_dir: /srv/dashboard").unwrap();
+    assert_eq!(
+        StaticFiles::new(yaml.static_dir),
+        StaticFiles::Dir("/srv/dashboard".into())
+    );
 }
 
 // =============================================================================
@@ -190,10 +207,114 @@ service_groups:
     assert_eq!(gzip.min_size, 512);
     assert_eq!(gzip.level, 3);
 
-    let groups = config.service_groups.unwrap();
-    assert_eq!(
-        groups.get("monitoring").unwrap(),
-        &vec!["prometheus", "grafana"]
-    );
-    assert_eq!(groups.get("database").unwrap(), &vec!["postgres"]);
+    let groups = config.service_groups.unwrap().into_rules().unwrap();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].group, "monitoring");
+    assert_eq!(groups[0].patterns, vec!["prometheus", "grafana"]);
+    assert_eq!(groups[0].kind, MatchKind::Contains);
+    assert!(!groups[0].case_sensitive);
+    assert_eq!(groups[1].group, "database");
+    assert_eq!(groups[1].patterns, vec!["postgres"]);
+}
+
+#[test]
+fn test_yaml_config_service_group_rules() {
+    let yaml = r#"
+service_groups:
+  - group: database
+    match: glob
+    patterns: ["postgres*"]
+  - group: api
+    match: regex
+    case_sensitive: true
+    patterns: ["^api-"]
+service_groups_multi: true
+"#;
+    let config: YamlConfig = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(config.service_groups_multi, Some(true));
+
+    let rules = config.service_groups.unwrap().into_rules().unwrap();
+    assert_eq!(rules[0].group, "database");
+    assert_eq!(rules[0].kind, MatchKind::Glob);
+    assert_eq!(rules[1].kind, MatchKind::Regex);
+    assert!(rules[1].case_sensitive);
+}
+
+#[test]
+fn test_yaml_config_sinks() {
+    let yaml = r#"
+sinks:
+  remote_write:
+    url: http://prometheus:9090/api/v1/write
+    max_retries: 5
+  pushgateway:
+    url: http://pushgateway:9091
+  statsd:
+    flavor: statsd
+"#;
+    let config: YamlConfig = serde_yaml::from_str(yaml).unwrap();
+    let sinks = config.sinks.unwrap();
+    assert!(sinks.validate().is_ok());
+    assert!(!sinks.is_empty());
+    assert!(sinks.otlp.is_none());
+    assert_eq!(sinks.remote_write.unwrap().delivery.max_retries, 5);
+    assert_eq!(sinks.pushgateway.unwrap().job, "railway_exporter");
+    assert_eq!(sinks.statsd.unwrap().address, "127.0.0.1:8125");
+}
+
+#[test]
+fn test_yaml_config_sinks_invalid() {
+    let yaml = r#"
+sinks:
+  pushgateway:
+    url: pushgateway:9091
+"#;
+    let config: YamlConfig = serde_yaml::from_str(yaml).unwrap();
+    assert!(config.sinks.unwrap().validate().is_err());
+}
+
+#[test]
+fn test_yaml_config_cors_invalid_header() {
+    use crate::cors::CorsPolicy;
+    let yaml = r#"
+cors:
+  allowed_origins: ["https://dashboard.example.com"]
+  exposed_headers: [ETag, "Last Modified"]
+"#;
+    let config: YamlConfig = serde_yaml::from_str(yaml).unwrap();
+    let err = CorsPolicy::new(config.cors.unwrap()).unwrap_err();
+    assert!(err.to_string().contains("exposed_headers"));
+}
+
+#[test]
+fn test_yaml_config_metrics() {
+    let yaml = r#"
+metrics:
+  prefix: acme
+  const_labels:
+    env: prod
+    team: platform
+"#;
+    let config: YamlConfig = serde_yaml::from_str(yaml).unwrap();
+    let metrics = config.metrics.unwrap();
+    assert!(metrics.validate().is_ok());
+    assert_eq!(metrics.prefix, "acme");
+    assert_eq!(metrics.const_labels["env"], "prod");
+
+    let config: YamlConfig = serde_yaml::from_str("metrics: {}").unwrap();
+    assert_eq!(config.metrics.unwrap().prefix, "railway");
+}
+
+#[test]
+fn test_yaml_config_trusted_proxies() {
+    let yaml = r#"
+trusted_proxies: ["10.0.0.0/8", "::1"]
+"#;
+    let config: YamlConfig = serde_yaml::from_str(yaml).unwrap();
+    let proxies = TrustedProxies::new(&config.trusted_proxies.unwrap()).unwrap();
+    assert!(proxies.contains("10.1.2.3".parse().unwrap()));
+    assert!(proxies.contains("::1".parse().unwrap()));
+    assert!(!proxies.contains("192.168.0.1".parse().unwrap()));
+
+    assert!(TrustedProxies::new(&["10.0.0.0/33".to_string()]).is_err());
 }
@@ -0,0 +1,335 @@
+//! Cross-origin resource sharing (CORS).
+//!
+//! With `cors_enabled: true` and no `cors` section every origin may read
+//! responses (`Access-Control-Allow-Origin: *`), as before. The `cors` section
+//! restricts that to a list of origins, exact (`https://dash.example.com`) or
+//! with a wildcard subdomain (`https://*.example.com`, which matches any
+//! subdomain depth but not `example.com` itself).
+//!
+//! `OPTIONS` preflight requests are answered before authentication (browsers
+//! never send credentials with them) with the allowed methods, request headers
+//! and `max_age`. Responses whose headers depend on the request's `Origin`
+//! carry `Vary: Origin`.
+//!
+//! `allow_credentials: true` lets the browser send cookies or HTTP auth with
+//! cross-origin requests; it cannot be combined with `*`.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! cors:
+//!   allowed_origins: ["https://dashboard.example.com", "https://*.example.com"]
+//!   allowed_methods: [GET, HEAD, OPTIONS]                        # default
+//!   allowed_headers: [Authorization, Accept, If-None-Match]      # default adds If-Modified-Since
+//!   exposed_headers: [ETag, Last-Modified, Content-Disposition]  # default
+//!   allow_credentials: false
+//!   max_age: 600                                                 # seconds
+//! ```
+
+use crate::config::ConfigError;
+use crate::handlers::HandlerResponse;
+use hyper::body::Bytes;
+use hyper::header::{HeaderMap, HeaderName};
+use hyper::http::response::Builder;
+use hyper::{Method, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+
+/// CORS section as written in YAML.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CorsConfig {
+    /// Origins allowed to read responses (`*` = any).
+    #[serde(default = "default_allowed_origins")]
+    pub allowed_origins: Vec<String>,
+    /// Methods allowed in preflight requests.
+    #[serde(default = "default_allowed_methods")]
+    pub allowed_methods: Vec<String>,
+    /// Request headers allowed in preflight requests (`*` = any).
+    #[serde(default = "default_allowed_headers")]
+    pub allowed_headers: Vec<String>,
+    /// Response headers readable by scripts.
+    #[serde(default = "default_exposed_headers")]
+    pub exposed_headers: Vec<String>,
+    /// Allow cookies and HTTP auth on cross-origin requests.
+    #[serde(default)]
+    pub allow_credentials: bool,
+    /// Seconds browsers may cache a preflight response.
+    #[serde(default = "default_max_age")]
+    pub max_age: u32,
+}
+
+fn default_allowed_origins() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+fn default_allowed_methods() -> Vec<String> {
+    ["GET", "HEAD", "OPTIONS"].map(String::from).to_vec()
+}
+
+fn default_allowed_headers() -> Vec<String> {
+    [
+        "Authorization",
+        "Accept",
+        "If-None-Match",
+        "If-Modified-Since",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn default_exposed_headers() -> Vec<String> {
+    ["ETag", "Last-Modified", "Content-Disposition"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_max_age() -> u32 {
+    600
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: default_allowed_origins(),
+            allowed_methods: default_allowed_methods(),
+            allowed_headers: default_allowed_headers(),
+            exposed_headers: default_exposed_headers(),
+            allow_credentials: false,
+            max_age: default_max_age(),
+        }
+    }
+}
+
+/// A single `allowed_origins` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OriginPattern {
+    Any,
+    /// Full origin, lowercase.
+    Exact(String),
+    /// `scheme://` prefix and `.domain[:port]` suffix of `scheme://*.domain[:port]`.
+    Subdomain {
+        prefix: String,
+        suffix: String,
+    },
+}
+
+impl OriginPattern {
+    fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim().trim_end_matches('/').to_ascii_lowercase();
+        if s == "*" {
+            return Ok(OriginPattern::Any);
+        }
+        let Some((scheme, host)) = s.split_once("://") else {
+            return Err(format!("origin '{}' must look like scheme://host", s));
+        };
+        if scheme.is_empty() || host.is_empty() || host.contains('/') {
+            return Err(format!("origin '{}' must look like scheme://host", s));
+        }
+        match host.strip_prefix('*') {
+            Some(suffix)
+                if suffix.starts_with('.') && suffix.len() > 1 && !suffix.contains('*') =>
+            {
+                Ok(OriginPattern::Subdomain {
+                    prefix: format!("{}://", scheme),
+                    suffix: suffix.to_string(),
+                })
+            }
+            Some(_) => Err(format!(
+                "origin '{}': wildcard must be a leading '*.' label",
+                s
+            )),
+            None if host.contains('*') => Err(format!(
+                "origin '{}': wildcard must be a leading '*.' label",
+                s
+            )),
+            None => Ok(OriginPattern::Exact(s)),
+        }
+    }
+
+    fn matches(&self, origin: &str) -> bool {
+        match self {
+            OriginPattern::Any => true,
+            OriginPattern::Exact(exact) => origin.eq_ignore_ascii_case(exact),
+            OriginPattern::Subdomain { prefix, suffix } => {
+                let origin = origin.to_ascii_lowercase();
+                origin
+                    .strip_prefix(prefix.as_str())
+                    .and_then(|host| host.strip_suffix(suffix.as_str()))
+                    .is_some_and(|sub| {
+                        !sub.is_empty()
+                            && sub
+                                .split('.')
+                                .all(|label| !label.is_empty() && is_host_label(label))
+                    })
+            }
+        }
+    }
+}
+
+/// Letters, digits and hyphens only (rejects `:`, `@` and other tricks).
+fn is_host_label(label: &str) -> bool {
+    label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Compiled CORS policy.
+#[derive(Debug, Clone)]
+pub struct CorsPolicy {
+    origins: Vec<OriginPattern>,
+    allowed_methods: Vec<Method>,
+    allowed_headers: Vec<String>,
+    exposed_headers: String,
+    allow_credentials: bool,
+    max_age: u32,
+}
+
+impl Default for CorsPolicy {
+    /// Any origin, default methods and headers.
+    fn default() -> Self {
+        Self::new(CorsConfig::default()).expect("default CORS config is valid")
+    }
+}
+
+impl CorsPolicy {
+    /// Validates the section and compiles it.
+    pub fn new(config: CorsConfig) -> Result<Self, ConfigError> {
+        let err = |msg: String| ConfigError::InvalidValue(format!("cors: {}", msg));
+
+        if config.allowed_origins.is_empty() {
+            return Err(err("allowed_origins must not be empty".to_string()));
+        }
+        let origins = config
+            .allowed_origins
+            .iter()
+            .map(|o| OriginPattern::parse(o).map_err(err))
+            .collect::<Result<Vec<_>, _>>()?;
+        if config.allow_credentials && origins.contains(&OriginPattern::Any) {
+            return Err(err(
+                "allow_credentials cannot be combined with allowed_origins '*'".to_string(),
+            ));
+        }
+        let allowed_methods = config
+            .allowed_methods
+            .iter()
+            .map(|m| {
+                Method::from_bytes(m.trim().to_ascii_uppercase().as_bytes())
+                    .map_err(|_| err(format!("invalid method '{}'", m)))
+            })
+            .collect::<Result<_, _>>()?;
+        // Joined into response headers, so each entry must be a header name
+        for (key, names) in [
+            ("allowed_headers", &config.allowed_headers),
+            ("exposed_headers", &config.exposed_headers),
+        ] {
+            if let Some(bad) = names
+                .iter()
+                .find(|h| *h != "*" && HeaderName::from_bytes(h.as_bytes()).is_err())
+            {
+                return Err(err(format!("{}: invalid header name '{}'", key, bad)));
+            }
+        }
+
+        Ok(Self {
+            origins,
+            allowed_methods,
+            allowed_headers: config.allowed_headers,
+            exposed_headers: config.exposed_headers.join(", "),
+            allow_credentials: config.allow_credentials,
+            max_age: config.max_age,
+        })
+    }
+
+    /// True if responses are the same for every origin (`*` without credentials).
+    fn is_wildcard(&self) -> bool {
+        self.origins.contains(&OriginPattern::Any)
+    }
+
+    /// Value for `Access-Control-Allow-Origin`, if the origin is allowed.
+    pub fn allow_origin(&self, origin: Option<&str>) -> Option<String> {
+        if self.is_wildcard() {
+            return Some("*".to_string());
+        }
+        let origin = origin?;
+        self.origins
+            .iter()
+            .any(|p| p.matches(origin))
+            .then(|| origin.to_string())
+    }
+
+    /// Returns true for a CORS preflight (`OPTIONS` with `Origin` and
+    /// `Access-Control-Request-Method`).
+    pub fn is_preflight(method: &Method, headers: &HeaderMap) -> bool {
+        method == Method::OPTIONS
+            && headers.contains_key("Origin")
+            && headers.contains_key("Access-Control-Request-Method")
+    }
+
+    /// Adds CORS headers to a regular response.
+    pub fn apply(&self, mut builder: Builder, origin: Option<&str>) -> Builder {
+        if !self.is_wildcard() {
+            builder = builder.header("Vary", "Origin");
+        }
+        let Some(allowed) = self.allow_origin(origin) else {
+            return builder;
+        };
+        builder = builder.header("Access-Control-Allow-Origin", allowed);
+        if !self.exposed_headers.is_empty() {
+            builder = builder.header("Access-Control-Expose-Headers", &self.exposed_headers);
+        }
+        if self.allow_credentials {
+            builder = builder.header("Access-Control-Allow-Credentials", "true");
+        }
+        builder
+    }
+
+    /// Answers a preflight request (`204`, or `403` if it is not allowed).
+    pub fn preflight(&self, headers: &HeaderMap) -> HandlerResponse {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let mut builder = Response::builder().header(
+            "Vary",
+            "Origin, Access-Control-Request-Method, Access-Control-Request-Headers",
+        );
+
+        let origin = self.allow_origin(header("Origin"));
+        let method_allowed = header("Access-Control-Request-Method")
+            .and_then(|m| Method::from_bytes(m.trim().as_bytes()).ok())
+            .is_some_and(|m| self.allowed_methods.contains(&m));
+        let requested: Vec<&str> = header("Access-Control-Request-Headers")
+            .unwrap_or("")
+            .split(',')
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .collect();
+        let any_header = self.allowed_headers.iter().any(|h| h == "*");
+        let headers_allowed = any_header
+            || requested.iter().all(|r| {
+                self.allowed_headers
+                    .iter()
+                    .any(|h| h.eq_ignore_ascii_case(r))
+            });
+
+        let Some(origin) = origin.filter(|_| method_allowed && headers_allowed) else {
+            return (
+                builder.status(StatusCode::FORBIDDEN),
+                Bytes::from("CORS request not allowed"),
+            );
+        };
+
+        let methods: Vec<&str> = self.allowed_methods.iter().map(Method::as_str).collect();
+        let allow_headers = if any_header {
+            requested.join(", ")
+        } else {
+            self.allowed_headers.join(", ")
+        };
+        builder = builder
+            .status(StatusCode::NO_CONTENT)
+            .header("Access-Control-Allow-Origin", origin)
+            .header("Access-Control-Allow-Methods", methods.join(", "))
+            .header("Access-Control-Max-Age", self.max_age.to_string());
+        if !allow_headers.is_empty() {
+            builder = builder.header("Access-Control-Allow-Headers", allow_headers);
+        }
+        if self.allow_credentials {
+            builder = builder.header("Access-Control-Allow-Credentials", "true");
+        }
+        (builder, Bytes::new())
+    }
+}
@@ -0,0 +1,292 @@
+//! Tests for the CORS policy.
+
+use crate::auth::{Auth, AuthConfig, TrustedProxies};
+use crate::config::Plan;
+use crate::cors::{CorsConfig, CorsPolicy};
+use crate::test_support::start_test_server;
+use crate::Config;
+use hyper::header::HeaderMap;
+use hyper::http::response::Parts;
+use hyper::{Method, Response};
+
+fn policy(yaml: &str) -> CorsPolicy {
+    CorsPolicy::new(serde_yaml::from_str::<CorsConfig>(yaml).unwrap()).unwrap()
+}
+
+fn dashboard_policy() -> CorsPolicy {
+    policy(
+        r#"
+allowed_origins: ["https://dashboard.example.com", "https://*.preview.example.com"]
+allow_credentials: true
+"#,
+    )
+}
+
+fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    for (name, value) in pairs {
+        map.insert(*name, value.parse().unwrap());
+    }
+    map
+}
+
+fn parts(builder: hyper::http::response::Builder) -> Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn header<'a>(parts: &'a Parts, name: &str) -> Option<&'a str> {
+    parts.headers.get(name).map(|v| v.to_str().unwrap())
+}
+
+// =============================================================================
+// Config Tests
+// =============================================================================
+
+#[test]
+fn test_validation() {
+    let invalid = [
+        "allowed_origins: []",
+        "allowed_origins: [dashboard.example.com]",
+        "allowed_origins: [\"https://dash.*.example.com\"]",
+        "allowed_origins: [\"https://*example.com\"]",
+        "allowed_origins: [\"https://example.com/app\"]",
+        "allow_credentials: true",
+        "allowed_methods: [\"GE T\"]",
+        "allowed_headers: [\"X Custom\"]",
+        "allowed_headers: [\"X-Custom\\r\\n\"]",
+        "exposed_headers: [ETag, \"Content-Disposition;\"]",
+        "exposed_headers: [\"\"]",
+    ];
+    for yaml in invalid {
+        let config: CorsConfig = serde_yaml::from_str(yaml).unwrap();
+        assert!(CorsPolicy::new(config).is_err(), "{}", yaml);
+    }
+}
+
+#[test]
+fn test_allow_origin() {
+    let policy = dashboard_policy();
+    let allowed = [
+        "https://dashboard.example.com",
+        "https://Dashboard.Example.com",
+        "https://pr-42.preview.example.com",
+        "https://a.b.preview.example.com",
+    ];
+    for origin in allowed {
+        assert_eq!(
+            policy.allow_origin(Some(origin)).as_deref(),
+            Some(origin),
+            "{}",
+            origin
+        );
+    }
+
+    let denied = [
+        "https://preview.example.com",
+        "https://evilpreview.example.com",
+        "http://pr-42.preview.example.com",
+        "https://pr-42.preview.example.com:8443",
+        "https://dashboard.example.com.evil.com",
+        "https://evil.com#.preview.example.com",
+        "https://.preview.example.com",
+        "null",
+    ];
+    for origin in denied {
+        assert_eq!(policy.allow_origin(Some(origin)), None, "{}", origin);
+    }
+    assert_eq!(policy.allow_origin(None), None);
+
+    // Default policy: any origin, even without an Origin header
+    assert_eq!(
+        CorsPolicy::default().allow_origin(None).as_deref(),
+        Some("*")
+    );
+}
+
+// =============================================================================
+// Response Header Tests
+// =============================================================================
+
+#[test]
+fn test_apply_wildcard() {
+    let parts = parts(CorsPolicy::default().apply(Response::builder(), Some("https://x.io")));
+    assert_eq!(header(&parts, "Access-Control-Allow-Origin"), Some("*"));
+    assert_eq!(
+        header(&parts, "Access-Control-Expose-Headers"),
+        Some("ETag, Last-Modified, Content-Disposition")
+    );
+    assert!(header(&parts, "Access-Control-Allow-Credentials").is_none());
+    assert!(header(&parts, "Vary").is_none());
+}
+
+#[test]
+fn test_apply_origin_list() {
+    let policy = dashboard_policy();
+
+    let allowed = parts(policy.apply(Response::builder(), Some("https://dashboard.example.com")));
+    assert_eq!(
+        header(&allowed, "Access-Control-Allow-Origin"),
+        Some("https://dashboard.example.com")
+    );
+    assert_eq!(
+        header(&allowed, "Access-Control-Allow-Credentials"),
+        Some("true")
+    );
+    assert_eq!(header(&allowed, "Vary"), Some("Origin"));
+
+    // Other origins get no CORS headers, but caches still vary on Origin
+    let denied = parts(policy.apply(Response::builder(), Some("https://evil.com")));
+    assert!(header(&denied, "Access-Control-Allow-Origin").is_none());
+    assert_eq!(header(&denied, "Vary"), Some("Origin"));
+}
+
+#[test]
+fn test_is_preflight() {
+    let request = headers(&[
+        ("Origin", "https://dashboard.example.com"),
+        ("Access-Control-Request-Method", "GET"),
+    ]);
+    assert!(CorsPolicy::is_preflight(&Method::OPTIONS, &request));
+    assert!(!CorsPolicy::is_preflight(&Method::GET, &request));
+    assert!(!CorsPolicy::is_preflight(
+        &Method::OPTIONS,
+        &headers(&[("Origin", "https://dashboard.example.com")])
+    ));
+}
+
+#[test]
+fn test_preflight() {
+    let policy = dashboard_policy();
+    let (builder, body) = policy.preflight(&headers(&[
+        ("Origin", "https://dashboard.example.com"),
+        ("Access-Control-Request-Method", "GET"),
+        (
+            "Access-Control-Request-Headers",
+            "authorization, if-none-match",
+        ),
+    ]));
+    let parts = parts(builder);
+    assert_eq!(parts.status, 204);
+    assert!(body.is_empty());
+    assert_eq!(
+        header(&parts, "Access-Control-Allow-Origin"),
+        Some("https://dashboard.example.com")
+    );
+    assert_eq!(
+        header(&parts, "Access-Control-Allow-Methods"),
+        Some("GET, HEAD, OPTIONS")
+    );
+    assert_eq!(
+        header(&parts, "Access-Control-Allow-Headers"),
+        Some("Authorization, Accept, If-None-Match, If-Modified-Since")
+    );
+    assert_eq!(header(&parts, "Access-Control-Max-Age"), Some("600"));
+    assert_eq!(
+        header(&parts, "Access-Control-Allow-Credentials"),
+        Some("true")
+    );
+    assert!(header(&parts, "Vary").unwrap().starts_with("Origin"));
+}
+
+#[test]
+fn test_preflight_rejected() {
+    let policy = dashboard_policy();
+    let rejected = [
+        // Origin not listed
+        [
+            ("Origin", "https://evil.com"),
+            ("Access-Control-Request-Method", "GET"),
+            ("Access-Control-Request-Headers", ""),
+        ],
+        // Method not allowed
+        [
+            ("Origin", "https://dashboard.example.com"),
+            ("Access-Control-Request-Method", "DELETE"),
+            ("Access-Control-Request-Headers", ""),
+        ],
+        // Header not allowed
+        [
+            ("Origin", "https://dashboard.example.com"),
+            ("Access-Control-Request-Method", "GET"),
+            ("Access-Control-Request-Headers", "X-Custom"),
+        ],
+    ];
+    for request in rejected {
+        let parts = parts(policy.preflight(&headers(&request)).0);
+        assert_eq!(parts.status, 403, "{:?}", request);
+        assert!(header(&parts, "Access-Control-Allow-Origin").is_none());
+    }
+}
+
+#[test]
+fn test_preflight_any_header() {
+    let policy = policy("allowed_headers: [\"*\"]");
+    let parts = parts(
+        policy
+            .preflight(&headers(&[
+                ("Origin", "https://x.io"),
+                ("Access-Control-Request-Method", "GET"),
+                ("Access-Control-Request-Headers", "X-Custom, X-Other"),
+            ]))
+            .0,
+    );
+    assert_eq!(parts.status, 204);
+    assert_eq!(
+        header(&parts, "Access-Control-Allow-Headers"),
+        Some("X-Custom, X-Other")
+    );
+}
+
+// =============================================================================
+// Server Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_server_cors_with_auth() {
+    let mut config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
+    config.cors = dashboard_policy();
+    config.auth = Some(
+        Auth::new(
+            serde_yaml::from_str::<AuthConfig>("tokens:\n  dashboard: secret").unwrap(),
+            &TrustedProxies::default(),
+        )
+        .unwrap(),
+    );
+    let url = format!("http://{}", start_test_server(config).await);
+    let client = reqwest::Client::new();
+    let origin = "https://dashboard.example.com";
+
+    // Preflight succeeds without credentials
+    let preflight = client
+        .request(reqwest::Method::OPTIONS, format!("{}/status", url))
+        .header("Origin", origin)
+        .header("Access-Control-Request-Method", "GET")
+        .header("Access-Control-Request-Headers", "authorization")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(preflight.status(), 204);
+    assert_eq!(preflight.headers()["Access-Control-Allow-Origin"], origin);
+
+    // Rejections stay readable by the dashboard
+    let denied = client
+        .get(format!("{}/status", url))
+        .header("Origin", origin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(denied.status(), 401);
+    assert_eq!(denied.headers()["Access-Control-Allow-Origin"], origin);
+
+    let ok = client
+        .get(format!("{}/status", url))
+        .header("Origin", origin)
+        .bearer_auth("secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(ok.status(), 200);
+    assert_eq!(ok.headers()["Access-Control-Allow-Origin"], origin);
+    assert_eq!(ok.headers()["Access-Control-Allow-Credentials"], "true");
+    assert_eq!(ok.headers()["Vary"], "Origin");
+}
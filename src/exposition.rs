@@ -0,0 +1,270 @@
+//! Metrics exposition formats.
+//!
+//! `/metrics` serves the classic Prometheus text format (0.0.4) by default and
+//! switches to OpenMetrics 1.0 when the scraper asks for it via `Accept`
+//! (Prometheus does since 2.x):
+//!
+//! ```text
+//! Accept: application/openmetrics-text;version=1.0.0,text/plain;version=0.0.4;q=0.5
+//! ```
+//!
+//! The OpenMetrics output differs from the text format in a few places:
+//!
+//! - `# UNIT` lines for families whose name ends in a known unit suffix
+//!   (`_usd`, `_seconds`, `_bytes`, `_gb`, ...)
+//! - counter families are named without `_total`, samples keep the suffix
+//! - counters, histograms and summaries get a `_created` sample holding the
+//!   exporter start time (their values are reset on restart)
+//! - the body ends with `# EOF`
+//!
+//! Scrapers that prefer the binary protobuf format (Prometheus with
+//! `scrape_protocols: [PrometheusProto, ...]`, some agents) get
+//! length-delimited `io.prometheus.client.MetricFamily` messages:
+//!
+//! ```text
+//! Accept: application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;encoding=delimited
+//! ```
+//!
+//! Histograms are sent with their classic buckets; the `prometheus` crate's
+//! data model has no sparse (native) buckets.
+
+use crate::utils::negotiate::quality_list;
+use prometheus::proto::{Metric, MetricFamily, MetricType};
+use std::fmt::Write;
+
+/// Content type of the Prometheus text format.
+pub const TEXT_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Content type of the OpenMetrics text format.
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Content type of the Prometheus protobuf format.
+pub const PROTOBUF_CONTENT_TYPE: &str =
+    "application/vnd.google.protobuf; proto=io.prometheus.client.MetricFamily; encoding=delimited";
+
+/// Unit suffixes recognised for `# UNIT` metadata, longest first.
+const UNIT_SUFFIXES: &[&str] = &[
+    "vcpu_minutes",
+    "gb_minutes",
+    "seconds",
+    "percent",
+    "bytes",
+    "usd",
+    "gb",
+];
+
+/// Exposition format served on `/metrics`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExpositionFormat {
+    /// Prometheus text format 0.0.4.
+    #[default]
+    Text,
+    /// OpenMetrics 1.0 text format.
+    OpenMetrics,
+    /// Length-delimited `MetricFamily` protobuf messages.
+    Protobuf,
+}
+
+impl ExpositionFormat {
+    /// Picks a format from an `Accept` header (text format if absent or unsupported).
+    pub fn negotiate(accept: Option<&str>) -> Self {
+        let Some(accept) = accept else {
+            return Self::Text;
+        };
+        for item in quality_list(accept) {
+            if item.q <= 0.0 {
+                continue;
+            }
+            match item.value.as_str() {
+                "application/openmetrics-text" => return Self::OpenMetrics,
+                "application/vnd.google.protobuf"
+                    if item.param("proto") == Some("io.prometheus.client.MetricFamily")
+                        && item.param("encoding") == Some("delimited") =>
+                {
+                    return Self::Protobuf
+                }
+                "text/plain" | "text/*" | "*/*" => return Self::Text,
+                _ => {}
+            }
+        }
+        Self::Text
+    }
+
+    /// Returns the `Content-Type` header value for this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Text => TEXT_CONTENT_TYPE,
+            Self::OpenMetrics => OPENMETRICS_CONTENT_TYPE,
+            Self::Protobuf => PROTOBUF_CONTENT_TYPE,
+        }
+    }
+}
+
+/// Returns the OpenMetrics unit of a metric family, if its name ends in one.
+pub fn unit_for(name: &str) -> Option<&'static str> {
+    UNIT_SUFFIXES
+        .iter()
+        .find(|unit| {
+            name.strip_suffix(*unit)
+                .is_some_and(|rest| rest.ends_with('_'))
+        })
+        .copied()
+}
+
+/// Encodes metric families in OpenMetrics 1.0 text format.
+///
+/// `created` is the Unix time (seconds) reported as `_created` for counters,
+/// histograms and summaries.
+pub fn encode_openmetrics(families: &[MetricFamily], created: f64) -> String {
+    let mut out = String::new();
+
+    for family in families {
+        let metric_type = family.get_field_type();
+        let sample_name = family.get_name();
+        let name = match metric_type {
+            MetricType::COUNTER => sample_name.strip_suffix("_total").unwrap_or(sample_name),
+            _ => sample_name,
+        };
+
+        let _ = writeln!(out, "# TYPE {} {}", name, type_name(metric_type));
+        if let Some(unit) = unit_for(name) {
+            let _ = writeln!(out, "# UNIT {} {}", name, unit);
+        }
+        if !family.get_help().is_empty() {
+            let _ = writeln!(out, "# HELP {} {}", name, escape(family.get_help()));
+        }
+
+        for metric in family.get_metric() {
+            match metric_type {
+                MetricType::GAUGE => {
+                    write_sample(&mut out, name, metric, None, metric.get_gauge().get_value());
+                }
+                MetricType::UNTYPED => {
+                    write_sample(
+                        &mut out,
+                        name,
+                        metric,
+                        None,
+                        metric.get_untyped().get_value(),
+                    );
+                }
+                MetricType::COUNTER => {
+                    let total = format!("{}_total", name);
+                    write_sample(
+                        &mut out,
+                        &total,
+                        metric,
+                        None,
+                        metric.get_counter().get_value(),
+                    );
+                    write_created(&mut out, name, metric, created);
+                }
+                MetricType::HISTOGRAM => {
+                    let h = metric.get_histogram();
+                    let bucket = format!("{}_bucket", name);
+                    let mut saw_inf = false;
+                    for b in h.get_bucket() {
+                        saw_inf |= b.get_upper_bound().is_infinite();
+                        let le = format_value(b.get_upper_bound());
+                        let count = b.get_cumulative_count() as f64;
+                        write_sample(&mut out, &bucket, metric, Some(("le", &le)), count);
+                    }
+                    if !saw_inf {
+                        let count = h.get_sample_count() as f64;
+                        write_sample(&mut out, &bucket, metric, Some(("le", "+Inf")), count);
+                    }
+                    let count = format!("{}_count", name);
+                    write_sample(&mut out, &count, metric, None, h.get_sample_count() as f64);
+                    let sum = format!("{}_sum", name);
+                    write_sample(&mut out, &sum, metric, None, h.get_sample_sum());
+                    write_created(&mut out, name, metric, created);
+                }
+                MetricType::SUMMARY => {
+                    let s = metric.get_summary();
+                    for q in s.get_quantile() {
+                        let quantile = format_value(q.get_quantile());
+                        let value = q.get_value();
+                        write_sample(&mut out, name, metric, Some(("quantile", &quantile)), value);
+                    }
+                    let count = format!("{}_count", name);
+                    write_sample(&mut out, &count, metric, None, s.get_sample_count() as f64);
+                    let sum = format!("{}_sum", name);
+                    write_sample(&mut out, &sum, metric, None, s.get_sample_sum());
+                    write_created(&mut out, name, metric, created);
+                }
+            }
+        }
+    }
+
+    out.push_str("# EOF\n");
+    out
+}
+
+fn type_name(metric_type: MetricType) -> &'static str {
+    match metric_type {
+        MetricType::COUNTER => "counter",
+        MetricType::GAUGE => "gauge",
+        MetricType::HISTOGRAM => "histogram",
+        MetricType::SUMMARY => "summary",
+        MetricType::UNTYPED => "unknown",
+    }
+}
+
+fn write_created(out: &mut String, name: &str, metric: &Metric, created: f64) {
+    let created_name = format!("{}_created", name);
+    write_sample(out, &created_name, metric, None, created);
+}
+
+/// Writes `name{labels} value [timestamp]`.
+fn write_sample(
+    out: &mut String,
+    name: &str,
+    metric: &Metric,
+    extra: Option<(&str, &str)>,
+    value: f64,
+) {
+    out.push_str(name);
+
+    let labels = metric
+        .get_label()
+        .iter()
+        .map(|l| (l.get_name(), l.get_value()))
+        .chain(extra);
+    let mut first = true;
+    for (label, label_value) in labels {
+        out.push(if first { '{' } else { ',' });
+        first = false;
+        let _ = write!(out, "{}=\"{}\"", label, escape(label_value));
+    }
+    if !first {
+        out.push('}');
+    }
+
+    let _ = write!(out, " {}", format_value(value));
+    if metric.get_timestamp_ms() != 0 {
+        // OpenMetrics timestamps are in seconds
+        let _ = write!(out, " {}", metric.get_timestamp_ms() as f64 / 1000.0);
+    }
+    out.push('\n');
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escapes label values and HELP text (both use the same rules in OpenMetrics).
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,213 @@
+//! Tests for metrics exposition formats.
+
+use crate::exposition::{
+    encode_openmetrics, unit_for, ExpositionFormat, OPENMETRICS_CONTENT_TYPE, TEXT_CONTENT_TYPE,
+};
+use crate::metrics::Metrics;
+use prometheus::{CounterVec, Histogram, HistogramOpts, Opts, Registry};
+
+// =============================================================================
+// Negotiation Tests
+// =============================================================================
+
+#[test]
+fn test_negotiate_defaults_to_text() {
+    assert_eq!(ExpositionFormat::negotiate(None), ExpositionFormat::Text);
+    assert_eq!(
+        ExpositionFormat::negotiate(Some("text/plain;version=0.0.4")),
+        ExpositionFormat::Text
+    );
+    assert_eq!(
+        ExpositionFormat::negotiate(Some("application/xml")),
+        ExpositionFormat::Text
+    );
+}
+
+#[test]
+fn test_negotiate_prometheus_scraper_header() {
+    let accept = "application/openmetrics-text;version=1.0.0,application/openmetrics-text;version=0.0.1;q=0.75,text/plain;version=0.0.4;q=0.5,*/*;q=0.1";
+    assert_eq!(
+        ExpositionFormat::negotiate(Some(accept)),
+        ExpositionFormat::OpenMetrics
+    );
+}
+
+#[test]
+fn test_negotiate_respects_q_values() {
+    let accept = "application/openmetrics-text;q=0.3,text/plain;q=0.9";
+    assert_eq!(
+        ExpositionFormat::negotiate(Some(accept)),
+        ExpositionFormat::Text
+    );
+    let rejected = "application/openmetrics-text;q=0";
+    assert_eq!(
+        ExpositionFormat::negotiate(Some(rejected)),
+        ExpositionFormat::Text
+    );
+}
+
+#[test]
+fn test_content_types() {
+    assert_eq!(ExpositionFormat::Text.content_type(), TEXT_CONTENT_TYPE);
+    assert_eq!(
+        ExpositionFormat::OpenMetrics.content_type(),
+        OPENMETRICS_CONTENT_TYPE
+    );
+    assert_eq!(
+        ExpositionFormat::Protobuf.content_type(),
+        prometheus::PROTOBUF_FORMAT
+    );
+}
+
+#[test]
+fn test_negotiate_protobuf() {
+    let accept = "application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;encoding=delimited;q=0.7,text/plain;version=0.0.4;q=0.3,*/*;q=0.2";
+    assert_eq!(
+        ExpositionFormat::negotiate(Some(accept)),
+        ExpositionFormat::Protobuf
+    );
+
+    // Other protobuf messages or framings are not supported
+    for accept in [
+        "application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;encoding=text",
+        "application/vnd.google.protobuf;proto=other.Message;encoding=delimited",
+        "application/vnd.google.protobuf",
+    ] {
+        assert_eq!(
+            ExpositionFormat::negotiate(Some(accept)),
+            ExpositionFormat::Text
+        );
+    }
+}
+
+// =============================================================================
+// Unit Tests
+// =============================================================================
+
+#[test]
+fn test_unit_for() {
+    assert_eq!(unit_for("railway_service_cost_usd"), Some("usd"));
+    assert_eq!(
+        unit_for("railway_cpu_usage_vcpu_minutes"),
+        Some("vcpu_minutes")
+    );
+    assert_eq!(
+        unit_for("railway_memory_usage_gb_minutes"),
+        Some("gb_minutes")
+    );
+    assert_eq!(unit_for("railway_network_tx_gb"), Some("gb"));
+    assert_eq!(
+        unit_for("railway_exporter_scrape_duration_seconds"),
+        Some("seconds")
+    );
+    assert_eq!(unit_for("railway_api_up"), None);
+    assert_eq!(unit_for("railway_exporter_last_scrape_timestamp"), None);
+    // Suffix must be a whole name component
+    assert_eq!(unit_for("railway_busd"), None);
+}
+
+// =============================================================================
+// Encoding Tests
+// =============================================================================
+
+#[test]
+fn test_encode_openmetrics_gauges() {
+    let metrics = Metrics::new();
+    metrics
+        .service_cost
+        .with_label_values(&["web \"main\"", "proj", "🌐", "frontend"])
+        .set(1.5);
+
+    let output = metrics.encode_openmetrics();
+    assert!(output.contains("# TYPE railway_service_cost_usd gauge\n"));
+    assert!(output.contains("# UNIT railway_service_cost_usd usd\n"));
+    assert!(output.contains("# HELP railway_service_cost_usd "));
+    assert!(output.contains(r#"service="web \"main\"""#));
+    assert!(output.contains("} 1.5\n"));
+    assert!(output.ends_with("# EOF\n"));
+    assert_eq!(output.matches("# EOF").count(), 1);
+    assert!(!output.contains("railway_service_cost_usd_created"));
+}
+
+#[test]
+fn test_encode_openmetrics_counter() {
+    let registry = Registry::new();
+    let counter = CounterVec::new(
+        Opts::new("test_requests_total", "Requests served"),
+        &["route"],
+    )
+    .unwrap();
+    registry.register(Box::new(counter.clone())).unwrap();
+    counter.with_label_values(&["/metrics"]).inc_by(3.0);
+
+    let output = encode_openmetrics(&registry.gather(), 1700000000.5);
+    assert!(output.contains("# TYPE test_requests counter\n"));
+    assert!(output.contains("test_requests_total{route=\"/metrics\"} 3\n"));
+    assert!(output.contains("test_requests_created{route=\"/metrics\"} 1700000000.5\n"));
+}
+
+#[test]
+fn test_encode_openmetrics_histogram() {
+    let registry = Registry::new();
+    let histogram = Histogram::with_opts(
+        HistogramOpts::new("test_duration_seconds", "Duration").buckets(vec![0.1, 1.0]),
+    )
+    .unwrap();
+    registry.register(Box::new(histogram.clone())).unwrap();
+    histogram.observe(0.5);
+
+    let output = encode_openmetrics(&registry.gather(), 10.0);
+    assert!(output.contains("# UNIT test_duration_seconds seconds\n"));
+    assert!(output.contains("test_duration_seconds_bucket{le=\"0.1\"} 0\n"));
+    assert!(output.contains("test_duration_seconds_bucket{le=\"1\"} 1\n"));
+    assert!(output.contains("test_duration_seconds_bucket{le=\"+Inf\"} 1\n"));
+    assert!(output.contains("test_duration_seconds_count 1\n"));
+    assert!(output.contains("test_duration_seconds_sum 0.5\n"));
+    assert!(output.contains("test_duration_seconds_created 10\n"));
+}
+
+#[test]
+fn test_encode_as_matches_format() {
+    let metrics = Metrics::new();
+    metrics.api_up.with_label_values(&["proj"]).set(1.0);
+
+    let text = String::from_utf8(metrics.encode_as(ExpositionFormat::Text)).unwrap();
+    assert!(!text.contains("# EOF"));
+    let open = String::from_utf8(metrics.encode_as(ExpositionFormat::OpenMetrics)).unwrap();
+    assert!(open.ends_with("# EOF\n"));
+}
+
+#[test]
+fn test_encode_protobuf_round_trip() {
+    use prometheus::proto::MetricType;
+    use protobuf::{CodedInputStream, Message};
+
+    let metrics = Metrics::new();
+    metrics.api_up.with_label_values(&["proj"]).set(1.0);
+    metrics.observe_collection(true, 0.5);
+
+    let body = metrics.encode_as(ExpositionFormat::Protobuf);
+    let mut input = CodedInputStream::from_bytes(&body);
+    let mut families = Vec::new();
+    while !input.eof().unwrap() {
+        let len = input.read_raw_varint32().unwrap();
+        let bytes = input.read_raw_bytes(len).unwrap();
+        families.push(prometheus::proto::MetricFamily::parse_from_bytes(&bytes).unwrap());
+    }
+
+    let api_up = families
+        .iter()
+        .find(|f| f.get_name() == "railway_api_up")
+        .unwrap();
+    assert_eq!(api_up.get_metric()[0].get_gauge().get_value(), 1.0);
+
+    let duration = families
+        .iter()
+        .find(|f| f.get_name() == "railway_exporter_collection_duration_seconds")
+        .unwrap();
+    assert_eq!(duration.get_field_type(), MetricType::HISTOGRAM);
+    assert_eq!(
+        duration.get_metric()[0].get_histogram().get_sample_count(),
+        1
+    );
+}
@@ -0,0 +1,185 @@
+//! Service include/exclude filters.
+//!
+//! Drops throwaway services from gauges and `ServiceData` rows. Their spend is
+//! not lost: it is folded into a single `other` bucket so project totals stay
+//! accurate.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! service_filter:
+//!   # If set, only services matching at least one entry are kept
+//!   include:
+//!     - group: backend
+//!   # Services matching any entry are dropped (applied after include)
+//!   exclude:
+//!     - name: "pr-*"
+//!       match: glob            # same matchers as service_groups (default: contains)
+//!     - id: 4f1c9a2e-...       # exact Railway service ID
+//!     - group: scratch
+//!   other_name: other          # bucket name for excluded spend (default: other)
+//! ```
+//!
+//! Each entry sets exactly one of `name`, `id` or `group`. Group entries
+//! compare against every group the service belongs to.
+//!
+//! The bucket has the reserved ID `__other__` and is rolled up under
+//! `ungrouped`. `other_name` must not be a service name written in
+//! `service_filter` or `service_groups` patterns.
+
+use crate::config::ConfigError;
+use crate::grouping::{MatchKind, NamePattern};
+use serde::{Deserialize, Serialize};
+
+/// A single include/exclude entry as written in YAML.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FilterEntry {
+    /// Service name pattern.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Exact Railway service ID.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Group name (exact).
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Matcher type for `name` (default: contains).
+    #[serde(default, rename = "match")]
+    pub kind: MatchKind,
+    /// Compare `name` case-sensitively (default: false).
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+/// Filter section as written in YAML.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FilterConfig {
+    #[serde(default)]
+    pub include: Vec<FilterEntry>,
+    #[serde(default)]
+    pub exclude: Vec<FilterEntry>,
+    /// Bucket name for excluded spend.
+    #[serde(default)]
+    pub other_name: Option<String>,
+}
+
+/// Default bucket name for excluded spend.
+pub const DEFAULT_OTHER_NAME: &str = "other";
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Name(NamePattern),
+    Id(String),
+    Group(String),
+}
+
+impl Selector {
+    fn compile(entry: &FilterEntry, section: &str, index: usize) -> Result<Self, ConfigError> {
+        let err = |msg: String| {
+            ConfigError::InvalidValue(format!("service_filter.{}[{}]: {}", section, index, msg))
+        };
+        match (&entry.name, &entry.id, &entry.group) {
+            (Some(name), None, None) => NamePattern::new(entry.kind, name, entry.case_sensitive)
+                .map(Selector::Name)
+                .map_err(|e| err(format!("invalid {} pattern '{}': {}", entry.kind, name, e))),
+            (None, Some(id), None) => Ok(Selector::Id(id.clone())),
+            (None, None, Some(group)) => Ok(Selector::Group(group.clone())),
+            _ => Err(err("set exactly one of name, id or group".to_string())),
+        }
+    }
+
+    fn is_match(&self, id: &str, name: &str, groups: &[String]) -> bool {
+        match self {
+            Selector::Name(p) => p.is_match(name),
+            Selector::Id(want) => want == id,
+            Selector::Group(want) => groups.iter().any(|g| g == want),
+        }
+    }
+}
+
+/// Compiled service filter.
+#[derive(Debug, Clone)]
+pub struct ServiceFilter {
+    include: Vec<Selector>,
+    exclude: Vec<Selector>,
+    other_name: String,
+}
+
+impl Default for ServiceFilter {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            other_name: DEFAULT_OTHER_NAME.to_string(),
+        }
+    }
+}
+
+impl ServiceFilter {
+    /// Compiles include/exclude entries.
+    pub fn new(config: FilterConfig) -> Result<Self, ConfigError> {
+        let compile = |entries: &[FilterEntry], section: &str| {
+            entries
+                .iter()
+                .enumerate()
+                .map(|(i, e)| Selector::compile(e, section, i))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let other_name = config
+            .other_name
+            .unwrap_or_else(|| DEFAULT_OTHER_NAME.to_string());
+        if other_name.is_empty() {
+            return Err(ConfigError::InvalidValue(
+                "service_filter.other_name must not be empty".to_string(),
+            ));
+        }
+        let filter = Self {
+            include: compile(&config.include, "include")?,
+            exclude: compile(&config.exclude, "exclude")?,
+            other_name,
+        };
+        let named = config
+            .include
+            .iter()
+            .chain(&config.exclude)
+            .filter_map(|e| e.name.as_deref().map(|name| (name, e.case_sensitive)));
+        for (name, case_sensitive) in named {
+            filter.check_other_name(name, case_sensitive)?;
+        }
+        Ok(filter)
+    }
+
+    /// Fails if `pattern` names the `other` bucket, i.e. a real service would
+    /// be exported with the bucket's name.
+    pub fn check_other_name(&self, pattern: &str, case_sensitive: bool) -> Result<(), ConfigError> {
+        let same = if case_sensitive {
+            pattern == self.other_name
+        } else {
+            pattern.to_lowercase() == self.other_name.to_lowercase()
+        };
+        if same {
+            return Err(ConfigError::InvalidValue(format!(
+                "service_filter.other_name '{}' is also a configured service name",
+                self.other_name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns true if no include/exclude entries are configured.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Name of the bucket that collects excluded spend.
+    pub fn other_name(&self) -> &str {
+        &self.other_name
+    }
+
+    /// Returns true if the service should be kept.
+    pub fn allows(&self, id: &str, name: &str, groups: &[String]) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|s| s.is_match(id, name, groups));
+        included && !self.exclude.iter().any(|s| s.is_match(id, name, groups))
+    }
+}
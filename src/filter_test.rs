@@ -0,0 +1,109 @@
+//! Tests for service include/exclude filters.
+
+use crate::filter::{FilterConfig, FilterEntry, ServiceFilter, DEFAULT_OTHER_NAME};
+use crate::grouping::MatchKind;
+
+fn by_name(name: &str, kind: MatchKind) -> FilterEntry {
+    FilterEntry {
+        name: Some(name.to_string()),
+        id: None,
+        group: None,
+        kind,
+        case_sensitive: false,
+    }
+}
+
+fn groups(g: &[&str]) -> Vec<String> {
+    g.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_empty_filter_allows_everything() {
+    let filter = ServiceFilter::default();
+    assert!(filter.is_empty());
+    assert!(filter.allows("id", "anything", &groups(&["ungrouped"])));
+    assert_eq!(filter.other_name(), DEFAULT_OTHER_NAME);
+}
+
+#[test]
+fn test_exclude_by_name_id_and_group() {
+    let yaml = r#"
+exclude:
+  - name: "PR-*"
+    match: glob
+  - id: svc-123
+  - group: scratch
+"#;
+    let config: FilterConfig = serde_yaml::from_str(yaml).unwrap();
+    let filter = ServiceFilter::new(config).unwrap();
+
+    assert!(!filter.allows("a", "pr-42", &groups(&["ungrouped"])));
+    assert!(!filter.allows("svc-123", "api", &groups(&["backend"])));
+    assert!(!filter.allows("b", "tmp", &groups(&["tools", "scratch"])));
+    assert!(filter.allows("c", "api", &groups(&["backend"])));
+}
+
+#[test]
+fn test_include_then_exclude() {
+    let config = FilterConfig {
+        include: vec![FilterEntry {
+            name: None,
+            id: None,
+            group: Some("backend".to_string()),
+            kind: MatchKind::Contains,
+            case_sensitive: false,
+        }],
+        exclude: vec![by_name("canary", MatchKind::Contains)],
+        other_name: Some("rest".to_string()),
+    };
+    let filter = ServiceFilter::new(config).unwrap();
+
+    assert!(filter.allows("1", "api", &groups(&["backend"])));
+    assert!(!filter.allows("2", "web", &groups(&["frontend"])));
+    assert!(!filter.allows("3", "api-canary", &groups(&["backend"])));
+    assert_eq!(filter.other_name(), "rest");
+}
+
+#[test]
+fn test_invalid_entries_rejected() {
+    let both = FilterEntry {
+        id: Some("x".to_string()),
+        ..by_name("a", MatchKind::Contains)
+    };
+    let none = FilterEntry {
+        name: None,
+        ..by_name("a", MatchKind::Contains)
+    };
+    for entry in [both, none, by_name("(", MatchKind::Regex)] {
+        let config = FilterConfig {
+            exclude: vec![entry],
+            ..FilterConfig::default()
+        };
+        assert!(ServiceFilter::new(config).is_err());
+    }
+
+    let config = FilterConfig {
+        other_name: Some(String::new()),
+        ..FilterConfig::default()
+    };
+    assert!(ServiceFilter::new(config).is_err());
+}
+
+#[test]
+fn test_other_name_must_not_name_a_service() {
+    let config = FilterConfig {
+        exclude: vec![by_name("Other", MatchKind::Exact)],
+        ..FilterConfig::default()
+    };
+    assert!(ServiceFilter::new(config).is_err());
+
+    let config = FilterConfig {
+        exclude: vec![by_name("Other", MatchKind::Exact)],
+        other_name: Some("rest".to_string()),
+        ..FilterConfig::default()
+    };
+    let filter = ServiceFilter::new(config).unwrap();
+    assert!(filter.check_other_name("REST", false).is_err());
+    assert!(filter.check_other_name("REST", true).is_ok());
+    assert!(filter.check_other_name("rest-api", false).is_ok());
+}
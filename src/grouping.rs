@@ -0,0 +1,318 @@
+//! Service grouping rules.
+//!
+//! Assigns Railway services to dashboard groups using an ordered list of rules.
+//! Rules are evaluated top to bottom; by default the first matching rule wins,
+//! optionally a service can join every group whose rule matches.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! service_groups:
+//!   - group: database
+//!     match: prefix          # contains (default) | exact | prefix | glob | regex
+//!     patterns: [postgres, redis]
+//!   - group: monitoring
+//!     match: regex
+//!     case_sensitive: true   # default: false
+//!     patterns: ["^(grafana|prometheus)(-.*)?$"]
+//!
+//! # Put a service in every matching group instead of the first one only
+//! service_groups_multi: true
+//! ```
+//!
+//! The legacy map form (`group: [patterns]`) is still accepted and is treated
+//! as case-insensitive `contains` rules in file order.
+
+use crate::config::ConfigError;
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Group assigned to services that match no rule.
+pub const UNGROUPED: &str = "ungrouped";
+
+/// How a rule pattern is compared against a service name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    /// Pattern appears anywhere in the name.
+    #[default]
+    Contains,
+    /// Pattern equals the whole name.
+    Exact,
+    /// Name starts with the pattern.
+    Prefix,
+    /// Shell-style glob (`*`, `?`, `[abc]`, `{a,b}`).
+    Glob,
+    /// Regular expression (unanchored unless `^`/`$` are used).
+    Regex,
+}
+
+impl MatchKind {
+    /// Returns the matcher name as used in YAML.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchKind::Contains => "contains",
+            MatchKind::Exact => "exact",
+            MatchKind::Prefix => "prefix",
+            MatchKind::Glob => "glob",
+            MatchKind::Regex => "regex",
+        }
+    }
+}
+
+impl fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A single grouping rule as written in YAML.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupRule {
+    /// Group name assigned on match.
+    pub group: String,
+    /// Matcher type for all patterns of this rule.
+    #[serde(default, rename = "match")]
+    pub kind: MatchKind,
+    /// Patterns compared against the service name (any may match).
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Compare case-sensitively (default: false).
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+impl GroupRule {
+    /// Creates a case-insensitive `contains` rule (legacy semantics).
+    pub fn contains(group: &str, patterns: &[&str]) -> Self {
+        Self {
+            group: group.to_string(),
+            kind: MatchKind::Contains,
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            case_sensitive: false,
+        }
+    }
+}
+
+/// Compiled form of a single pattern.
+#[derive(Debug, Clone)]
+enum Matcher {
+    Contains(String),
+    Exact(String),
+    Prefix(String),
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+/// A single compiled name pattern.
+///
+/// Also used by other modules that match service names (e.g. service filters).
+#[derive(Debug, Clone)]
+pub struct NamePattern {
+    matcher: Matcher,
+    case_sensitive: bool,
+}
+
+impl NamePattern {
+    /// Compiles a pattern. Fails on invalid glob/regex syntax.
+    pub fn new(kind: MatchKind, pattern: &str, case_sensitive: bool) -> Result<Self, String> {
+        let literal = || {
+            if case_sensitive {
+                pattern.to_string()
+            } else {
+                pattern.to_lowercase()
+            }
+        };
+        let matcher = match kind {
+            MatchKind::Contains => Matcher::Contains(literal()),
+            MatchKind::Exact => Matcher::Exact(literal()),
+            MatchKind::Prefix => Matcher::Prefix(literal()),
+            MatchKind::Glob => Matcher::Glob(
+                GlobBuilder::new(pattern)
+                    .case_insensitive(!case_sensitive)
+                    .literal_separator(false)
+                    .build()
+                    .map_err(|e| e.to_string())?
+                    .compile_matcher(),
+            ),
+            MatchKind::Regex => Matcher::Regex(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .map_err(|e| e.to_string())?,
+            ),
+        };
+        Ok(Self {
+            matcher,
+            case_sensitive,
+        })
+    }
+
+    /// Returns true if the name matches.
+    pub fn is_match(&self, name: &str) -> bool {
+        let folded;
+        let subject = if self.case_sensitive {
+            name
+        } else {
+            folded = name.to_lowercase();
+            &folded
+        };
+        match &self.matcher {
+            Matcher::Contains(p) => subject.contains(p.as_str()),
+            Matcher::Exact(p) => subject == p,
+            Matcher::Prefix(p) => subject.starts_with(p.as_str()),
+            Matcher::Glob(g) => g.is_match(name),
+            Matcher::Regex(r) => r.is_match(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    rule: GroupRule,
+    matchers: Vec<NamePattern>,
+}
+
+/// A rule that matched a service (reported by `/status`).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GroupMatch {
+    /// Group the service was assigned to.
+    pub group: String,
+    /// Zero-based index of the rule in `service_groups`.
+    pub rule: usize,
+    /// Matcher type of the rule.
+    #[serde(rename = "match")]
+    pub kind: MatchKind,
+    /// The pattern that matched.
+    pub pattern: String,
+}
+
+/// Ordered, compiled service grouping rules.
+///
+/// # Example
+///
+/// ```rust
+/// use railway_exporter::grouping::{GroupRule, ServiceGroups};
+///
+/// let groups = ServiceGroups::new(vec![GroupRule::contains("database", &["postgres"])], false).unwrap();
+/// assert_eq!(groups.primary_group("PostgreSQL-main"), "database");
+/// assert_eq!(groups.primary_group("web"), "ungrouped");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ServiceGroups {
+    rules: Vec<CompiledRule>,
+    multi_group: bool,
+}
+
+impl ServiceGroups {
+    /// Compiles rules in order. Fails on invalid glob/regex patterns.
+    pub fn new(rules: Vec<GroupRule>, multi_group: bool) -> Result<Self, ConfigError> {
+        let rules = rules
+            .into_iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                if rule.group.is_empty() {
+                    return Err(ConfigError::InvalidValue(format!(
+                        "service_groups[{}].group must not be empty",
+                        i
+                    )));
+                }
+                let matchers = rule
+                    .patterns
+                    .iter()
+                    .map(|p| {
+                        NamePattern::new(rule.kind, p, rule.case_sensitive).map_err(|e| {
+                            ConfigError::InvalidValue(format!(
+                                "service_groups[{}] ({}) invalid {} pattern '{}': {}",
+                                i, rule.group, rule.kind, p, e
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(CompiledRule { rule, matchers })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { rules, multi_group })
+    }
+
+    /// Returns true if no rules are configured.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether services may belong to several groups.
+    pub fn multi_group(&self) -> bool {
+        self.multi_group
+    }
+
+    /// Configured rules in evaluation order.
+    pub fn rules(&self) -> impl Iterator<Item = &GroupRule> {
+        self.rules.iter().map(|r| &r.rule)
+    }
+
+    /// Distinct group names in rule order (for the dashboard dropdown).
+    pub fn group_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for r in &self.rules {
+            if !names.contains(&r.rule.group) {
+                names.push(r.rule.group.clone());
+            }
+        }
+        names
+    }
+
+    /// Returns matching rules for a service name, in rule order.
+    ///
+    /// Contains at most one entry unless multi-group membership is enabled;
+    /// a group is only listed once even if several of its rules match.
+    pub fn classify(&self, name: &str) -> Vec<GroupMatch> {
+        let mut matches: Vec<GroupMatch> = Vec::new();
+
+        for (i, compiled) in self.rules.iter().enumerate() {
+            if matches.iter().any(|m| m.group == compiled.rule.group) {
+                continue;
+            }
+            let hit = compiled
+                .matchers
+                .iter()
+                .zip(&compiled.rule.patterns)
+                .find(|(m, _)| m.is_match(name));
+
+            if let Some((_, pattern)) = hit {
+                matches.push(GroupMatch {
+                    group: compiled.rule.group.clone(),
+                    rule: i,
+                    kind: compiled.rule.kind,
+                    pattern: pattern.clone(),
+                });
+                if !self.multi_group {
+                    break;
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// Returns every group for a service, or `["ungrouped"]` if none match.
+    pub fn groups_for(&self, name: &str) -> Vec<String> {
+        let groups: Vec<String> = self.classify(name).into_iter().map(|m| m.group).collect();
+        if groups.is_empty() {
+            vec![UNGROUPED.to_string()]
+        } else {
+            groups
+        }
+    }
+
+    /// Returns the first matching group, or `"ungrouped"`.
+    pub fn primary_group(&self, name: &str) -> String {
+        self.classify(name)
+            .into_iter()
+            .next()
+            .map(|m| m.group)
+            .unwrap_or_else(|| UNGROUPED.to_string())
+    }
+}
@@ -0,0 +1,143 @@
+//! Tests for service grouping rules.
+
+use crate::grouping::{GroupRule, MatchKind, ServiceGroups, UNGROUPED};
+
+fn rule(group: &str, kind: MatchKind, patterns: &[&str], case_sensitive: bool) -> GroupRule {
+    GroupRule {
+        group: group.to_string(),
+        kind,
+        patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        case_sensitive,
+    }
+}
+
+// =============================================================================
+// Matcher Tests
+// =============================================================================
+
+#[test]
+fn test_contains_is_case_insensitive_by_default() {
+    let groups =
+        ServiceGroups::new(vec![GroupRule::contains("database", &["postgres"])], false).unwrap();
+    assert_eq!(groups.primary_group("PostgreSQL"), "database");
+    assert_eq!(groups.primary_group("my-postgres-db"), "database");
+    assert_eq!(groups.primary_group("redis"), UNGROUPED);
+}
+
+#[test]
+fn test_contains_case_sensitive() {
+    let groups = ServiceGroups::new(
+        vec![rule("db", MatchKind::Contains, &["Postgres"], true)],
+        false,
+    )
+    .unwrap();
+    assert_eq!(groups.primary_group("Postgres-main"), "db");
+    assert_eq!(groups.primary_group("postgres-main"), UNGROUPED);
+}
+
+#[test]
+fn test_exact_and_prefix() {
+    let groups = ServiceGroups::new(
+        vec![
+            rule("exact", MatchKind::Exact, &["api"], false),
+            rule("prefix", MatchKind::Prefix, &["worker-"], false),
+        ],
+        false,
+    )
+    .unwrap();
+    assert_eq!(groups.primary_group("API"), "exact");
+    assert_eq!(groups.primary_group("api-gateway"), UNGROUPED);
+    assert_eq!(groups.primary_group("Worker-emails"), "prefix");
+    assert_eq!(groups.primary_group("email-worker-"), UNGROUPED);
+}
+
+#[test]
+fn test_glob() {
+    let groups = ServiceGroups::new(
+        vec![rule("db", MatchKind::Glob, &["{postgres,redis}*"], false)],
+        false,
+    )
+    .unwrap();
+    assert_eq!(groups.primary_group("Redis-cache"), "db");
+    assert_eq!(groups.primary_group("postgres"), "db");
+    assert_eq!(groups.primary_group("my-redis"), UNGROUPED);
+}
+
+#[test]
+fn test_regex() {
+    let groups = ServiceGroups::new(
+        vec![rule("api", MatchKind::Regex, &["^api-v[0-9]+$"], false)],
+        false,
+    )
+    .unwrap();
+    assert_eq!(groups.primary_group("API-V2"), "api");
+    assert_eq!(groups.primary_group("api-v2-staging"), UNGROUPED);
+}
+
+#[test]
+fn test_invalid_patterns_rejected() {
+    assert!(ServiceGroups::new(vec![rule("x", MatchKind::Regex, &["("], false)], false).is_err());
+    assert!(ServiceGroups::new(vec![rule("x", MatchKind::Glob, &["[a"], false)], false).is_err());
+    assert!(ServiceGroups::new(vec![rule("", MatchKind::Exact, &["a"], false)], false).is_err());
+}
+
+// =============================================================================
+// Ordering / Multi-Group Tests
+// =============================================================================
+
+#[test]
+fn test_first_rule_wins() {
+    let rules = vec![
+        GroupRule::contains("backend", &["api"]),
+        GroupRule::contains("public", &["api"]),
+    ];
+    let groups = ServiceGroups::new(rules, false).unwrap();
+    for _ in 0..10 {
+        assert_eq!(groups.groups_for("api"), vec!["backend"]);
+    }
+}
+
+#[test]
+fn test_multi_group_membership() {
+    let rules = vec![
+        GroupRule::contains("backend", &["api"]),
+        GroupRule::contains("web", &["web"]),
+        GroupRule::contains("public", &["api", "gateway"]),
+    ];
+    let groups = ServiceGroups::new(rules, true).unwrap();
+    assert_eq!(groups.groups_for("api-gateway"), vec!["backend", "public"]);
+    assert_eq!(groups.primary_group("api-gateway"), "backend");
+    assert_eq!(groups.groups_for("db"), vec![UNGROUPED]);
+}
+
+#[test]
+fn test_classify_reports_rule_and_pattern() {
+    let rules = vec![
+        GroupRule::contains("db", &["redis"]),
+        rule("db", MatchKind::Prefix, &["pg"], false),
+        GroupRule::contains("infra", &["pg"]),
+    ];
+    let groups = ServiceGroups::new(rules, true).unwrap();
+
+    let matches = groups.classify("pg-main");
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].group, "db");
+    assert_eq!(matches[0].rule, 1);
+    assert_eq!(matches[0].kind, MatchKind::Prefix);
+    assert_eq!(matches[0].pattern, "pg");
+    assert_eq!(matches[1].group, "infra");
+    assert_eq!(matches[1].rule, 2);
+}
+
+#[test]
+fn test_group_names_in_rule_order() {
+    let rules = vec![
+        GroupRule::contains("zeta", &["z"]),
+        GroupRule::contains("alpha", &["a"]),
+        GroupRule::contains("zeta", &["y"]),
+    ];
+    let groups = ServiceGroups::new(rules, false).unwrap();
+    assert_eq!(groups.group_names(), vec!["zeta", "alpha"]);
+    assert!(!groups.is_empty());
+    assert!(ServiceGroups::default().is_empty());
+}
@@ -0,0 +1,238 @@
+//! Pre-rendered `/metrics` bodies.
+//!
+//! Railway metric values only change once per collection, so the collector
+//! renders them in every exposition format (Prometheus text, OpenMetrics,
+//! protobuf) right after it finishes, together with the JSON body (plain and
+//! compressed with every enabled coding).
+//!
+//! Exporter self-metrics (`railway_exporter_http_*`, `railway_exporter_ws_*`,
+//! scrape counters, process memory/CPU) change between collections, so they
+//! are refreshed every [`SELF_METRICS_INTERVAL`] and appended to the cached
+//! Railway part. Every body is kept plain and compressed with every enabled
+//! coding, with `ETag` / `Last-Modified` (the render time), and answers
+//! `If-None-Match` / `If-Modified-Since` with `304`; a scrape does no
+//! gathering, encoding or compression of its own.
+//!
+//! Filtered requests (`name[]` / `match[]`) are rendered on demand.
+
+use super::HandlerResponse;
+use crate::compression::{self, Compression, Encoding};
+use crate::exposition::ExpositionFormat;
+use crate::metrics::Metrics;
+use crate::types::MetricsJson;
+use chrono::{DateTime, Utc};
+use hyper::body::Bytes;
+use hyper::header::HeaderMap;
+use hyper::{Response, StatusCode};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+/// How often self-metrics are re-rendered into the exposition bodies.
+pub const SELF_METRICS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Terminator of an OpenMetrics body.
+const OPENMETRICS_EOF: &[u8] = b"# EOF\n";
+
+/// One response body with its compressed variants and validators.
+#[derive(Debug, Clone)]
+pub struct CachedBody {
+    content_type: &'static str,
+    etag: String,
+    last_modified: String,
+    modified: i64,
+    plain: Bytes,
+    /// Compressed variants, only kept if smaller than `plain`.
+    encoded: Vec<(Encoding, Bytes)>,
+}
+
+impl CachedBody {
+    /// Renders the compressed variants of a body modified at `modified`.
+    pub fn new(
+        content_type: &'static str,
+        body: Bytes,
+        modified: DateTime<Utc>,
+        compression: &Compression,
+    ) -> Self {
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+
+        let encoded = compression
+            .available(body.len())
+            .filter_map(|encoding| match compression.compress(encoding, &body) {
+                Ok(compressed) if compressed.len() < body.len() => {
+                    Some((encoding, Bytes::from(compressed)))
+                }
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            content_type,
+            etag: format!("\"{:016x}\"", hasher.finish()),
+            last_modified: modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+            modified: modified.timestamp(),
+            plain: body,
+            encoded,
+        }
+    }
+
+    /// `ETag` of the plain body (encoded variants append `-<encoding>`).
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    /// Returns the `ETag` of a variant.
+    fn variant_etag(&self, encoding: Option<Encoding>) -> String {
+        match encoding {
+            Some(encoding) => format!("{}-{}\"", self.etag.trim_end_matches('"'), encoding),
+            None => self.etag.clone(),
+        }
+    }
+
+    /// Returns true if the client's validators still match this body.
+    ///
+    /// `If-None-Match` takes precedence over `If-Modified-Since`.
+    pub fn not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(tags) = headers.get("If-None-Match").and_then(|v| v.to_str().ok()) {
+            let mut variants = std::iter::once(self.etag.clone()).chain(
+                self.encoded
+                    .iter()
+                    .map(|(e, _)| self.variant_etag(Some(*e))),
+            );
+            let tags: Vec<&str> = tags
+                .split(',')
+                .map(|t| t.trim().trim_start_matches("W/"))
+                .collect();
+            return tags.contains(&"*") || variants.any(|v| tags.contains(&v.as_str()));
+        }
+        headers
+            .get("If-Modified-Since")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+            .is_some_and(|since| self.modified <= since.timestamp())
+    }
+
+    /// Builds the response for a request (304, compressed or plain).
+    pub fn response(&self, headers: &HeaderMap) -> HandlerResponse {
+        let accepted = headers.get("Accept-Encoding").and_then(|v| v.to_str().ok());
+        let variant = compression::select(accepted, self.encoded.iter().map(|(e, _)| *e))
+            .and_then(|chosen| self.encoded.iter().find(|(e, _)| *e == chosen));
+
+        let mut builder = Response::builder()
+            .header("ETag", self.variant_etag(variant.map(|(e, _)| *e)))
+            .header("Last-Modified", &self.last_modified)
+            .header("Vary", "Accept, Accept-Encoding");
+
+        if self.not_modified(headers) {
+            return (builder.status(StatusCode::NOT_MODIFIED), Bytes::new());
+        }
+
+        builder = builder.header("Content-Type", self.content_type);
+        match variant {
+            Some((encoding, body)) => (
+                builder.header("Content-Encoding", encoding.as_str()),
+                body.clone(),
+            ),
+            None => (builder, self.plain.clone()),
+        }
+    }
+}
+
+/// All `/metrics` bodies rendered after one collection.
+#[derive(Debug, Clone)]
+pub struct MetricsCache {
+    text: Exposition,
+    openmetrics: Exposition,
+    protobuf: Exposition,
+    json: Option<CachedBody>,
+}
+
+/// One exposition format: the Railway part from the last collection and the
+/// full body with the self-metrics from the last refresh.
+#[derive(Debug, Clone)]
+struct Exposition {
+    /// Railway families (OpenMetrics without `# EOF`).
+    railway: Bytes,
+    body: CachedBody,
+}
+
+impl Exposition {
+    fn new(
+        railway: Bytes,
+        metrics: &Metrics,
+        format: ExpositionFormat,
+        compression: &Compression,
+    ) -> Self {
+        let live = metrics.encode_families(&metrics.gather_self(), format);
+        let mut body = Vec::with_capacity(railway.len() + live.len());
+        body.extend_from_slice(&railway);
+        body.extend_from_slice(&live);
+
+        Self {
+            body: CachedBody::new(
+                format.content_type(),
+                Bytes::from(body),
+                Utc::now(),
+                compression,
+            ),
+            railway,
+        }
+    }
+}
+
+impl MetricsCache {
+    /// Renders the current Railway metrics and JSON snapshot.
+    pub fn render(
+        metrics: &Metrics,
+        json: Option<&MetricsJson>,
+        compression: &Compression,
+    ) -> Self {
+        let families = metrics.gather_railway();
+        let exposition = |format: ExpositionFormat| {
+            let mut railway = metrics.encode_families(&families, format);
+            if format == ExpositionFormat::OpenMetrics {
+                // The self-metrics appended on refresh end the body
+                railway.truncate(railway.len() - OPENMETRICS_EOF.len());
+            }
+            Exposition::new(Bytes::from(railway), metrics, format, compression)
+        };
+
+        Self {
+            text: exposition(ExpositionFormat::Text),
+            openmetrics: exposition(ExpositionFormat::OpenMetrics),
+            protobuf: exposition(ExpositionFormat::Protobuf),
+            json: json.and_then(|m| serde_json::to_string(m).ok()).map(|s| {
+                CachedBody::new("application/json", Bytes::from(s), Utc::now(), compression)
+            }),
+        }
+    }
+
+    /// Re-renders the exposition bodies with the current self-metrics,
+    /// keeping the Railway part and JSON body of this collection.
+    pub fn with_self_metrics(&self, metrics: &Metrics, compression: &Compression) -> Self {
+        let refresh = |cached: &Exposition, format| {
+            Exposition::new(cached.railway.clone(), metrics, format, compression)
+        };
+        Self {
+            text: refresh(&self.text, ExpositionFormat::Text),
+            openmetrics: refresh(&self.openmetrics, ExpositionFormat::OpenMetrics),
+            protobuf: refresh(&self.protobuf, ExpositionFormat::Protobuf),
+            json: self.json.clone(),
+        }
+    }
+
+    /// Returns the exposition body for `format`.
+    pub fn exposition(&self, format: ExpositionFormat) -> &CachedBody {
+        match format {
+            ExpositionFormat::Text => &self.text.body,
+            ExpositionFormat::OpenMetrics => &self.openmetrics.body,
+            ExpositionFormat::Protobuf => &self.protobuf.body,
+        }
+    }
+
+    /// Returns the JSON body (if a snapshot exists).
+    pub fn json(&self) -> Option<&CachedBody> {
+        self.json.as_ref()
+    }
+}
@@ -0,0 +1,80 @@
+//! Chargeback report handler.
+//!
+//! GET /chargeback - cost center totals per billing period.
+//!
+//! Query parameters:
+//! - `format=json|csv` (default: json; csv is served as a download)
+//! - `period=YYYY-MM` (default: all known periods)
+
+use super::{query_param, HandlerResponse};
+use crate::chargeback::to_csv;
+use crate::state::AppState;
+use hyper::body::Bytes;
+use hyper::{Response, StatusCode};
+use serde_json::json;
+
+/// GET /chargeback - Cost center report (JSON or CSV).
+pub async fn handle(state: &AppState, query: Option<&str>) -> HandlerResponse {
+    if state.config.cost_centers.is_empty() {
+        return (
+            Response::builder().status(StatusCode::NOT_FOUND),
+            Bytes::from("Cost centers not configured"),
+        );
+    }
+
+    let period = query_param(query, "period");
+    if period.as_deref().is_some_and(|p| !is_period(p)) {
+        return (
+            Response::builder().status(StatusCode::BAD_REQUEST),
+            Bytes::from("Invalid period (use YYYY-MM)"),
+        );
+    }
+    let format = query_param(query, "format").unwrap_or_else(|| "json".to_string());
+
+    let ledger = state.chargeback.read().await;
+    let periods = ledger.periods(period.as_deref());
+
+    match format.as_str() {
+        "csv" => {
+            let filename = match &period {
+                Some(p) => format!("chargeback-{}.csv", p),
+                None => "chargeback.csv".to_string(),
+            };
+            (
+                Response::builder()
+                    .header("Content-Type", "text/csv; charset=utf-8")
+                    .header(
+                        "Content-Disposition",
+                        format!("attachment; filename=\"{}\"", filename),
+                    ),
+                Bytes::from(to_csv(&periods)),
+            )
+        }
+        "json" => {
+            let body = json!({
+                "dimensions": state.config.cost_centers.dimensions(),
+                "periods": periods,
+            });
+            (
+                Response::builder().header("Content-Type", "application/json"),
+                Bytes::from(serde_json::to_string(&body).unwrap_or_else(|_| "{}".to_string())),
+            )
+        }
+        _ => (
+            Response::builder().status(StatusCode::BAD_REQUEST),
+            Bytes::from("Unsupported format (use json or csv)"),
+        ),
+    }
+}
+
+/// True for a `YYYY-MM` billing period (also safe to put in a header).
+fn is_period(value: &str) -> bool {
+    let b = value.as_bytes();
+    b.len() == 7
+        && b[4] == b'-'
+        && b[..4].iter().chain(&b[5..]).all(u8::is_ascii_digit)
+        && matches!(
+            &value[5..],
+            "01" | "02" | "03" | "04" | "05" | "06" | "07" | "08" | "09" | "10" | "11" | "12"
+        )
+}
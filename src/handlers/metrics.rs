@@ -1,22 +1,60 @@
-//! Metrics handlers (Prometheus and JSON formats).
+//! Metrics handlers (Prometheus text/OpenMetrics/protobuf, JSON and InfluxDB line protocol formats).
 
-use super::HandlerResponse;
+use super::{query_values, HandlerResponse};
+use crate::exposition::ExpositionFormat;
+use crate::influx;
+use crate::selector::SeriesFilter;
 use crate::state::AppState;
 use hyper::body::Bytes;
-use hyper::Response;
+use hyper::header::HeaderMap;
+use hyper::{Response, StatusCode};
 
-/// GET /metrics - Prometheus format.
-pub fn handle_prometheus(state: &AppState) -> HandlerResponse {
-    state.metrics.update_process_metrics();
+/// GET /metrics - Prometheus text, OpenMetrics or protobuf format, negotiated via `Accept`.
+///
+/// Unfiltered bodies come pre-rendered and pre-compressed from the cache (see
+/// `cache`); `name[]` and `match[]` query parameters narrow the output (see
+/// `crate::selector`) and are rendered on demand.
+pub fn handle_prometheus(
+    state: &AppState,
+    headers: &HeaderMap,
+    query: Option<&str>,
+) -> HandlerResponse {
+    let filter = match SeriesFilter::new(
+        query_values(query, "name[]"),
+        &query_values(query, "match[]"),
+    ) {
+        Ok(filter) => filter,
+        Err(e) => {
+            return (
+                Response::builder().status(StatusCode::BAD_REQUEST),
+                Bytes::from(format!("Invalid selector: {}", e)),
+            )
+        }
+    };
+
+    let accept = headers.get("Accept").and_then(|v| v.to_str().ok());
+    let format = ExpositionFormat::negotiate(accept);
+    if filter.is_empty() {
+        if let Some(cache) = state.metrics_cache() {
+            return cache.exposition(format).response(headers);
+        }
+    }
 
+    // Filtered request, or nothing collected yet
+    let mut families = state.metrics.gather();
+    filter.apply(&mut families);
     (
-        Response::builder().header("Content-Type", "text/plain; version=0.0.4; charset=utf-8"),
-        Bytes::from(state.metrics.encode()),
+        Response::builder().header("Content-Type", format.content_type()),
+        Bytes::from(state.metrics.encode_families(&families, format)),
     )
 }
 
 /// GET /metrics with Accept: application/json - JSON format.
-pub async fn handle_json(state: &AppState) -> HandlerResponse {
+pub async fn handle_json(state: &AppState, headers: &HeaderMap) -> HandlerResponse {
+    if let Some(body) = state.metrics_cache().as_ref().and_then(|c| c.json()) {
+        return body.response(headers);
+    }
+
     let json = state.metrics_json.read().await;
     let body = match json.as_ref() {
         Some(m) => serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string()),
@@ -28,3 +66,17 @@ pub async fn handle_json(state: &AppState) -> HandlerResponse {
         Bytes::from(body),
     )
 }
+
+/// GET /metrics/influx - InfluxDB line protocol, timestamped with the last scrape.
+pub async fn handle_influx(state: &AppState) -> HandlerResponse {
+    match state.metrics_json.read().await.as_ref() {
+        Some(m) => (
+            Response::builder().header("Content-Type", influx::CONTENT_TYPE),
+            Bytes::from(influx::encode(m)),
+        ),
+        None => (
+            Response::builder().status(StatusCode::SERVICE_UNAVAILABLE),
+            Bytes::from("No data yet"),
+        ),
+    }
+}
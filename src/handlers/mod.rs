@@ -2,8 +2,12 @@
 //!
 //! Each handler returns `(http::response::Builder, Bytes)` tuple.
 //! The server finalizes the response by adding CORS headers (if enabled),
-//! gzip compression (if configured), and calling `.body()` + `.unwrap()`.
+//! compression negotiated from `Accept-Encoding` (br, zstd or gzip, if
+//! configured), and calling `.body()` + `.unwrap()`.
+//! `/metrics` bodies come pre-compressed from the metrics cache.
 
+mod cache;
+mod chargeback;
 mod health;
 mod icons;
 mod metrics;
@@ -15,67 +19,136 @@ mod static_files_test;
 #[cfg(test)]
 mod tests;
 
+pub use cache::{CachedBody, MetricsCache, SELF_METRICS_INTERVAL};
+pub use chargeback::handle as chargeback;
 pub use health::handle as health;
 pub use icons::handle as icons;
+pub use metrics::handle_influx as metrics_influx;
 pub use metrics::handle_json as metrics_json;
 pub use metrics::handle_prometheus as metrics_prometheus;
-pub use static_files::handle as static_file;
+pub use static_files::{handle as static_file, StaticFiles};
 pub use status::handle as status;
 
-use crate::config::GzipConfig;
-use flate2::write::GzEncoder;
-use flate2::Compression;
+use crate::compression::Compression;
+use crate::cors::CorsPolicy;
 use http_body_util::Full;
 use hyper::body::Bytes;
+use hyper::header::{HeaderValue, ETAG, VARY};
 use hyper::http::response::Builder;
 use hyper::Response;
 use hyper::StatusCode;
-use std::io::Write;
 
 /// Handler response type - builder + body bytes.
 pub type HandlerResponse = (Builder, Bytes);
 
-/// Finalize response: add CORS headers if enabled, gzip if configured, build response.
+/// Finalize response: add CORS headers if enabled, compress if configured, build response.
+///
+/// `origin` is the request's `Origin` header, checked against the CORS policy.
+/// `accept_encoding` is the request's `Accept-Encoding` header.
 pub fn finalize(
     response: HandlerResponse,
-    cors_enabled: bool,
-    gzip_requested: bool,
-    gzip_config: &GzipConfig,
+    cors: Option<&CorsPolicy>,
+    origin: Option<&str>,
+    accept_encoding: Option<&str>,
+    compression: &Compression,
 ) -> Response<Full<Bytes>> {
     let (mut builder, body) = response;
 
-    // Add CORS header if enabled
-    if cors_enabled {
-        builder = builder.header("Access-Control-Allow-Origin", "*");
+    // Add CORS headers if enabled
+    if let Some(cors) = cors {
+        builder = cors.apply(builder, origin);
     }
 
-    // Gzip compress if enabled, requested, and body exceeds min_size
-    let should_compress =
-        gzip_config.enabled && gzip_requested && body.len() > gzip_config.min_size;
+    // Cached bodies arrive already encoded, with their own Vary; byte ranges
+    // refer to the plain body
+    let skip = builder
+        .headers_ref()
+        .is_some_and(|h| h.contains_key("Content-Encoding") || h.contains_key("Content-Range"));
+    if skip || compression.available(body.len()).next().is_none() {
+        return builder.body(Full::new(body)).unwrap();
+    }
+
+    // The body depends on Accept-Encoding even when sent uncompressed
+    builder = add_vary(builder, "Accept-Encoding");
 
-    let (final_body, is_gzipped) = if should_compress {
-        match gzip_compress(&body, gzip_config.level) {
-            Ok(compressed) if compressed.len() < body.len() => (Bytes::from(compressed), true),
-            _ => (body, false), // Fallback to uncompressed if compression fails or increases size
+    // Keep the compressed body only if compression succeeded and saved space
+    let compressed = compression
+        .negotiate(accept_encoding, body.len())
+        .and_then(|encoding| match compression.compress(encoding, &body) {
+            Ok(compressed) if compressed.len() < body.len() => Some((encoding, compressed)),
+            _ => None,
+        });
+
+    match compressed {
+        Some((encoding, compressed)) => weaken_etag(builder)
+            .header("Content-Encoding", encoding.as_str())
+            .body(Full::new(Bytes::from(compressed)))
+            .unwrap(),
+        None => builder.body(Full::new(body)).unwrap(),
+    }
+}
+
+/// Marks a strong `ETag` weak: the compressed body is not byte-identical to
+/// the representation it was computed for.
+fn weaken_etag(mut builder: Builder) -> Builder {
+    if let Some(headers) = builder.headers_mut() {
+        let weak = headers
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| v.starts_with('"'))
+            .and_then(|v| HeaderValue::from_str(&format!("W/{}", v)).ok());
+        if let Some(weak) = weak {
+            headers.insert(ETAG, weak);
         }
-    } else {
-        (body, false)
-    };
+    }
+    builder
+}
 
-    // Add Content-Encoding header if compressed
-    if is_gzipped {
-        builder = builder.header("Content-Encoding", "gzip");
+/// Adds a `Vary` header unless one already covers `name`.
+fn add_vary(builder: Builder, name: &str) -> Builder {
+    let covered = builder.headers_ref().is_some_and(|h| {
+        h.get_all(VARY)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim() == "*" || v.trim().eq_ignore_ascii_case(name))
+    });
+    if covered {
+        builder
+    } else {
+        builder.header(VARY, name)
     }
+}
 
-    builder.body(Full::new(final_body)).unwrap()
+/// Returns the URL-decoded value of a query string parameter.
+pub(crate) fn query_param(query: Option<&str>, key: &str) -> Option<String> {
+    query?.split('&').find_map(|pair| {
+        let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+        (k == key).then(|| {
+            urlencoding::decode(&v.replace('+', " "))
+                .map(|v| v.into_owned())
+                .unwrap_or_else(|_| v.to_string())
+        })
+    })
 }
 
-/// Gzip compress bytes with specified compression level.
-fn gzip_compress(data: &[u8], level: u32) -> std::io::Result<Vec<u8>> {
-    let compression = Compression::new(level);
-    let mut encoder = GzEncoder::new(Vec::new(), compression);
-    encoder.write_all(data)?;
-    encoder.finish()
+/// Returns every value of a repeatable query parameter such as `match[]`.
+///
+/// Keys are compared after percent-decoding, so `match%5B%5D` works too.
+pub(crate) fn query_values(query: Option<&str>, key: &str) -> Vec<String> {
+    let decode = |s: &str| {
+        urlencoding::decode(&s.replace('+', " "))
+            .map(|s| s.into_owned())
+            .unwrap_or_else(|_| s.to_string())
+    };
+    query
+        .into_iter()
+        .flat_map(|q| q.split('&'))
+        .filter_map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(k) == key).then(|| decode(v))
+        })
+        .collect()
 }
 
 /// 404 Not Found handler.
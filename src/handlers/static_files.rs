@@ -1,16 +1,223 @@
-//! Static file serving handler.
+//! Static file serving handler (dashboard UI).
+//!
+//! Files come from `static_dir` (default `/static`), read asynchronously on
+//! every request, or — when built with the `embedded-dashboard` feature and no
+//! `static_dir` is configured — from the Next.js static export compiled into
+//! the binary.
+//!
+//! - Hashed assets under `/_next/static/` are `immutable` for a year; other
+//!   files (including the `index.html` fallback for any path) are
+//!   revalidated (`no-cache`) so a new build shows up at once
+//! - `ETag` / `Last-Modified` with `If-None-Match` / `If-Modified-Since` → `304`
+//! - Single byte ranges (`Range: bytes=...`, honoring `If-Range`) → `206`,
+//!   unsatisfiable ranges → `416`; multiple ranges get the whole file
+//! - Directories serve their `index.html`, and paths without an extension fall
+//!   back to the root `index.html` for client-side routing
 
 use super::HandlerResponse;
+use chrono::{DateTime, Utc};
 use hyper::body::Bytes;
+use hyper::header::HeaderMap;
 use hyper::{Response, StatusCode};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Default static files directory (inside container).
+#[cfg(not(feature = "embedded-dashboard"))]
 const STATIC_DIR: &str = "/static";
 
-/// Serve a static file from the static directory.
+/// Content-hashed Next.js build output.
+const IMMUTABLE_PREFIX: &str = "_next/static/";
+
+#[cfg(feature = "embedded-dashboard")]
+#[derive(rust_embed::Embed)]
+#[folder = "dashboard/out/"]
+struct Dashboard;
+
+/// Where static files are served from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaticFiles {
+    /// Files under a directory.
+    Dir(PathBuf),
+    /// Dashboard export compiled into the binary.
+    #[cfg(feature = "embedded-dashboard")]
+    Embedded,
+}
+
+impl StaticFiles {
+    /// `static_dir` if configured, otherwise the embedded dashboard (if
+    /// built in) or `/static`.
+    pub fn new(static_dir: Option<String>) -> Self {
+        match static_dir {
+            Some(dir) => StaticFiles::Dir(PathBuf::from(dir)),
+            None => Self::default(),
+        }
+    }
+
+    /// Returns false if the directory does not exist.
+    async fn available(&self) -> bool {
+        match self {
+            StaticFiles::Dir(dir) => tokio::fs::metadata(dir).await.is_ok_and(|m| m.is_dir()),
+            #[cfg(feature = "embedded-dashboard")]
+            StaticFiles::Embedded => true,
+        }
+    }
+
+    /// Loads a file by its path relative to the root (directories resolve
+    /// to their `index.html`).
+    async fn load(&self, path: &str) -> Option<Asset> {
+        match self {
+            StaticFiles::Dir(dir) => {
+                let mut file = dir.join(path);
+                let mut meta = tokio::fs::metadata(&file).await.ok()?;
+                if meta.is_dir() {
+                    file = file.join("index.html");
+                    meta = tokio::fs::metadata(&file).await.ok()?;
+                }
+                if !meta.is_file() {
+                    return None;
+                }
+                let path = file.strip_prefix(dir).ok()?.to_string_lossy().into_owned();
+                let body = tokio::fs::read(&file).await.ok()?;
+                let modified = meta.modified().ok().map(DateTime::<Utc>::from);
+                let etag = format!(
+                    "\"{:x}-{:x}\"",
+                    modified.map_or(0, |m| m.timestamp()),
+                    body.len()
+                );
+                Some(Asset {
+                    content_type: mime_type(&file),
+                    path,
+                    body: Bytes::from(body),
+                    etag,
+                    modified,
+                })
+            }
+            #[cfg(feature = "embedded-dashboard")]
+            StaticFiles::Embedded => {
+                let index = format!("{}/index.html", path.trim_end_matches('/'));
+                let (name, file) = [path, index.trim_start_matches('/')]
+                    .into_iter()
+                    .find_map(|name| Some((name, Dashboard::get(name)?)))?;
+                let hash = file.metadata.sha256_hash();
+                let etag: String = hash[..8].iter().map(|b| format!("{:02x}", b)).collect();
+                Some(Asset {
+                    content_type: mime_type(Path::new(name)),
+                    path: name.to_string(),
+                    body: match file.data {
+                        std::borrow::Cow::Borrowed(data) => Bytes::from_static(data),
+                        std::borrow::Cow::Owned(data) => Bytes::from(data),
+                    },
+                    etag: format!("\"{}\"", etag),
+                    modified: file
+                        .metadata
+                        .last_modified()
+                        .and_then(|secs| DateTime::from_timestamp(secs as i64, 0)),
+                })
+            }
+        }
+    }
+}
+
+impl Default for StaticFiles {
+    #[cfg(feature = "embedded-dashboard")]
+    fn default() -> Self {
+        StaticFiles::Embedded
+    }
+
+    #[cfg(not(feature = "embedded-dashboard"))]
+    fn default() -> Self {
+        StaticFiles::Dir(PathBuf::from(STATIC_DIR))
+    }
+}
+
+/// A loaded file with its validators.
+struct Asset {
+    content_type: &'static str,
+    /// Path of the file actually loaded, relative to the root.
+    path: String,
+    body: Bytes,
+    etag: String,
+    modified: Option<DateTime<Utc>>,
+}
+
+impl Asset {
+    fn last_modified(&self) -> Option<String> {
+        self.modified
+            .map(|m| m.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+    }
+
+    /// Returns true if the client's validators still match.
+    ///
+    /// `If-None-Match` takes precedence over `If-Modified-Since`.
+    fn not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(tags) = header(headers, "If-None-Match") {
+            return tags
+                .split(',')
+                .map(|t| t.trim().trim_start_matches("W/"))
+                .any(|t| t == "*" || t == self.etag);
+        }
+        let since =
+            header(headers, "If-Modified-Since").and_then(|v| DateTime::parse_from_rfc2822(v).ok());
+        matches!((self.modified, since), (Some(m), Some(s)) if m.timestamp() <= s.timestamp())
+    }
+
+    /// Returns true if a `Range` may be applied (`If-Range` absent or still
+    /// matching, compared strongly).
+    fn range_applies(&self, headers: &HeaderMap) -> bool {
+        match header(headers, "If-Range") {
+            None => true,
+            Some(tag) if tag.starts_with('"') => tag == self.etag,
+            Some(date) => self.last_modified().is_some_and(|m| m == date),
+        }
+    }
+}
+
+/// Result of parsing a `Range` header against a body length.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ByteRange {
+    /// Inclusive start and end offsets.
+    Satisfiable(u64, u64),
+    Unsatisfiable,
+}
+
+/// Parses a single `bytes=` range. Returns None for anything the whole body
+/// should be sent for instead (other units, multiple ranges, bad syntax).
+pub(crate) fn parse_range(value: &str, len: u64) -> Option<ByteRange> {
+    let spec = value.trim().strip_prefix("bytes=")?.trim();
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = if start.is_empty() {
+        // Suffix range: last n bytes
+        let n: u64 = end.parse().ok()?;
+        (n > 0 && len > 0).then(|| (len.saturating_sub(n), len - 1))
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = match end {
+            "" => u64::MAX,
+            end => end.parse().ok()?,
+        };
+        if end < start {
+            return None;
+        }
+        (start < len).then(|| (start, end.min(len - 1)))
+    };
+    Some(match range {
+        Some((start, end)) => ByteRange::Satisfiable(start, end),
+        None => ByteRange::Unsatisfiable,
+    })
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Serve a static file.
 /// Falls back to index.html for SPA routing if file not found.
-pub fn handle(path: &str) -> HandlerResponse {
+pub async fn handle(files: &StaticFiles, path: &str, headers: &HeaderMap) -> HandlerResponse {
     // Security: prevent directory traversal
     let clean_path = path.trim_start_matches('/');
     if clean_path.contains("..") {
@@ -21,52 +228,69 @@ pub fn handle(path: &str) -> HandlerResponse {
     }
 
     // Check if static directory exists
-    let static_dir = Path::new(STATIC_DIR);
-    if !static_dir.exists() {
+    if !files.available().await {
         return (
             Response::builder().status(StatusCode::NOT_FOUND),
             Bytes::from("Static files not available"),
         );
     }
 
-    // Build full path
-    let file_path = if clean_path.is_empty() {
-        static_dir.join("index.html")
+    let asset = match files.load(clean_path).await {
+        Some(asset) => asset,
+        // SPA fallback: try index.html for non-file paths
+        None if !clean_path.contains('.') => match files.load("").await {
+            Some(asset) => asset,
+            None => return not_found(),
+        },
+        None => return not_found(),
+    };
+
+    // Decided by the file served, not the request: a fallback index.html for
+    // a missing hashed asset must not be cached forever
+    let cache_control = if asset.path.starts_with(IMMUTABLE_PREFIX) {
+        "public, max-age=31536000, immutable"
     } else {
-        static_dir.join(clean_path)
+        "no-cache"
     };
+    let mut builder = Response::builder()
+        .header("ETag", &asset.etag)
+        .header("Cache-Control", cache_control)
+        .header("Accept-Ranges", "bytes");
+    if let Some(modified) = asset.last_modified() {
+        builder = builder.header("Last-Modified", modified);
+    }
 
-    // Try to read the file
-    match std::fs::read(&file_path) {
-        Ok(contents) => {
-            let content_type = mime_type(&file_path);
-            (
-                Response::builder()
-                    .status(StatusCode::OK)
-                    .header("Content-Type", content_type)
-                    .header("Cache-Control", "public, max-age=3600"),
-                Bytes::from(contents),
-            )
-        }
-        Err(_) => {
-            // SPA fallback: try index.html for non-file paths
-            if !clean_path.contains('.') {
-                let index_path = static_dir.join("index.html");
-                if let Ok(contents) = std::fs::read(&index_path) {
-                    return (
-                        Response::builder()
-                            .status(StatusCode::OK)
-                            .header("Content-Type", "text/html; charset=utf-8"),
-                        Bytes::from(contents),
-                    );
-                }
-            }
-            (
-                Response::builder().status(StatusCode::NOT_FOUND),
-                Bytes::from("Not Found"),
-            )
-        }
+    if asset.not_modified(headers) {
+        return (builder.status(StatusCode::NOT_MODIFIED), Bytes::new());
     }
+
+    builder = builder.header("Content-Type", asset.content_type);
+    let len = asset.body.len() as u64;
+    let range = header(headers, "Range")
+        .filter(|_| asset.range_applies(headers))
+        .and_then(|value| parse_range(value, len));
+    match range {
+        Some(ByteRange::Satisfiable(start, end)) => (
+            builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header("Content-Range", format!("bytes {}-{}/{}", start, end, len)),
+            asset.body.slice(start as usize..=end as usize),
+        ),
+        Some(ByteRange::Unsatisfiable) => (
+            builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header("Content-Range", format!("bytes */{}", len)),
+            Bytes::new(),
+        ),
+        None => (builder.status(StatusCode::OK), asset.body),
+    }
+}
+
+fn not_found() -> HandlerResponse {
+    (
+        Response::builder().status(StatusCode::NOT_FOUND),
+        Bytes::from("Not Found"),
+    )
 }
 
 /// Determine MIME type from file extension.
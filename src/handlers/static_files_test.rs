@@ -1,9 +1,37 @@
 //! Tests for static file serving handler.
 
-use super::static_files::{get_mime_type, handle};
+use super::static_files::{get_mime_type, handle, parse_range, ByteRange, StaticFiles};
+use super::HandlerResponse;
 use hyper::body::Bytes;
+use hyper::header::HeaderMap;
 use hyper::http::StatusCode;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Serves a path from `/static` without request headers.
+async fn serve(path: &str) -> HandlerResponse {
+    let files = StaticFiles::Dir(PathBuf::from("/static"));
+    handle(&files, path, &HeaderMap::new()).await
+}
+
+/// Creates a static export layout in a fresh temp directory.
+fn export_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("exporter-static-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("_next/static/chunks")).unwrap();
+    std::fs::create_dir_all(dir.join("settings")).unwrap();
+    std::fs::write(dir.join("index.html"), "<html>dashboard</html>").unwrap();
+    std::fs::write(dir.join("settings/index.html"), "<html>settings</html>").unwrap();
+    std::fs::write(dir.join("_next/static/chunks/app-3f2a.js"), "0123456789").unwrap();
+    dir
+}
+
+fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    for (name, value) in pairs {
+        map.insert(*name, value.parse().unwrap());
+    }
+    map
+}
 
 // =============================================================================
 // MIME Type Tests
@@ -96,26 +124,26 @@ fn test_mime_type_unknown() {
 // Handler Security Tests
 // =============================================================================
 
-#[test]
-fn test_handle_blocks_directory_traversal() {
-    let (builder, body) = handle("../../../etc/passwd");
+#[tokio::test]
+async fn test_handle_blocks_directory_traversal() {
+    let (builder, body) = serve("../../../etc/passwd").await;
     let response = builder.body(body.clone()).unwrap();
 
     assert_eq!(response.status(), StatusCode::FORBIDDEN);
     assert_eq!(body, Bytes::from("Forbidden"));
 }
 
-#[test]
-fn test_handle_blocks_traversal_in_middle() {
-    let (builder, body) = handle("/some/../path");
+#[tokio::test]
+async fn test_handle_blocks_traversal_in_middle() {
+    let (builder, body) = serve("/some/../path").await;
     let response = builder.body(body.clone()).unwrap();
 
     assert_eq!(response.status(), StatusCode::FORBIDDEN);
     assert_eq!(body, Bytes::from("Forbidden"));
 }
 
-#[test]
-fn test_handle_blocks_double_dot_anywhere() {
+#[tokio::test]
+async fn test_handle_blocks_double_dot_anywhere() {
     let paths = [
         "../secret",
         "foo/../bar",
@@ -125,7 +153,7 @@ fn test_handle_blocks_double_dot_anywhere() {
 
     for path in &paths {
         if path.contains("..") {
-            let (builder, body) = handle(path);
+            let (builder, body) = serve(path).await;
             let response = builder.body(body.clone()).unwrap();
             assert_eq!(
                 response.status(),
@@ -141,10 +169,10 @@ fn test_handle_blocks_double_dot_anywhere() {
 // Handler Behavior Tests
 // =============================================================================
 
-#[test]
-fn test_handle_static_dir_not_exists() {
+#[tokio::test]
+async fn test_handle_static_dir_not_exists() {
     // On dev machine, /static doesn't exist
-    let (builder, body) = handle("/");
+    let (builder, body) = serve("/").await;
     let response = builder.body(body.clone()).unwrap();
 
     // Without /static directory, should return 404 with specific message
@@ -154,10 +182,10 @@ fn test_handle_static_dir_not_exists() {
     }
 }
 
-#[test]
-fn test_handle_strips_leading_slashes() {
+#[tokio::test]
+async fn test_handle_strips_leading_slashes() {
     // Multiple leading slashes should be handled
-    let (builder, body) = handle("///index.html");
+    let (builder, body) = serve("///index.html").await;
     let response = builder.body(body.clone()).unwrap();
 
     // Should not crash, return appropriate response
@@ -168,9 +196,9 @@ fn test_handle_strips_leading_slashes() {
     );
 }
 
-#[test]
-fn test_handle_empty_path_serves_index() {
-    let (builder, body) = handle("");
+#[tokio::test]
+async fn test_handle_empty_path_serves_index() {
+    let (builder, body) = serve("").await;
     let response = builder.body(body.clone()).unwrap();
 
     // Empty path should try to serve index.html
@@ -181,9 +209,9 @@ fn test_handle_empty_path_serves_index() {
     );
 }
 
-#[test]
-fn test_handle_root_path_serves_index() {
-    let (builder, body) = handle("/");
+#[tokio::test]
+async fn test_handle_root_path_serves_index() {
+    let (builder, body) = serve("/").await;
     let response = builder.body(body.clone()).unwrap();
 
     // Root path should try to serve index.html
@@ -193,3 +221,207 @@ fn test_handle_root_path_serves_index() {
         response.status()
     );
 }
+
+// =============================================================================
+// Directory Serving Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_handle_serves_from_static_dir() {
+    let dir = export_dir("serve");
+    let files = StaticFiles::Dir(dir.clone());
+    let none = HeaderMap::new();
+
+    let (builder, body) = handle(&files, "/", &none).await;
+    let response = builder.body(()).unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body, Bytes::from("<html>dashboard</html>"));
+    assert_eq!(response.headers()["Cache-Control"], "no-cache");
+    assert_eq!(response.headers()["Accept-Ranges"], "bytes");
+    assert!(response.headers().contains_key("ETag"));
+    assert!(response.headers().contains_key("Last-Modified"));
+
+    // Directories serve their own index.html, with or without trailing slash
+    for path in ["/settings/", "/settings"] {
+        let (_builder, body) = handle(&files, path, &none).await;
+        assert_eq!(body, Bytes::from("<html>settings</html>"));
+    }
+
+    // Client-side routes fall back to the root index.html, missing files 404
+    let (_builder, body) = handle(&files, "/services/web", &none).await;
+    assert_eq!(body, Bytes::from("<html>dashboard</html>"));
+    let (builder, _body) = handle(&files, "/missing.js", &none).await;
+    assert_eq!(builder.body(()).unwrap().status(), StatusCode::NOT_FOUND);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_handle_hashed_assets_immutable() {
+    let dir = export_dir("immutable");
+    let files = StaticFiles::Dir(dir.clone());
+
+    let (builder, _body) = handle(
+        &files,
+        "/_next/static/chunks/app-3f2a.js",
+        &HeaderMap::new(),
+    )
+    .await;
+    let response = builder.body(()).unwrap();
+    assert_eq!(
+        response.headers()["Cache-Control"],
+        "public, max-age=31536000, immutable"
+    );
+    assert_eq!(
+        response.headers()["Content-Type"],
+        "application/javascript; charset=utf-8"
+    );
+
+    // A missing hashed asset falls back to index.html, which is revalidated
+    let (builder, body) = handle(&files, "/_next/static/chunks/app-9e1b", &HeaderMap::new()).await;
+    let response = builder.body(()).unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body, Bytes::from("<html>dashboard</html>"));
+    assert_eq!(response.headers()["Cache-Control"], "no-cache");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_handle_conditional_requests() {
+    let dir = export_dir("conditional");
+    let files = StaticFiles::Dir(dir.clone());
+
+    let (builder, _body) = handle(&files, "/", &HeaderMap::new()).await;
+    let response = builder.body(()).unwrap();
+    let etag = response.headers()["ETag"].to_str().unwrap().to_string();
+    let last_modified = response.headers()["Last-Modified"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let (builder, body) = handle(&files, "/", &headers(&[("If-None-Match", &etag)])).await;
+    assert_eq!(builder.body(()).unwrap().status(), StatusCode::NOT_MODIFIED);
+    assert!(body.is_empty());
+
+    // Weak comparison: a compressed response's W/ tag still matches
+    let weak = format!("W/{}", etag);
+    let (builder, _body) = handle(&files, "/", &headers(&[("If-None-Match", &weak)])).await;
+    assert_eq!(builder.body(()).unwrap().status(), StatusCode::NOT_MODIFIED);
+
+    let (builder, _body) = handle(
+        &files,
+        "/",
+        &headers(&[("If-Modified-Since", &last_modified)]),
+    )
+    .await;
+    assert_eq!(builder.body(()).unwrap().status(), StatusCode::NOT_MODIFIED);
+
+    // If-None-Match wins over If-Modified-Since
+    let request = headers(&[
+        ("If-None-Match", "\"stale\""),
+        ("If-Modified-Since", &last_modified),
+    ]);
+    let (builder, _body) = handle(&files, "/", &request).await;
+    assert_eq!(builder.body(()).unwrap().status(), StatusCode::OK);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// =============================================================================
+// Range Tests
+// =============================================================================
+
+#[test]
+fn test_parse_range() {
+    assert_eq!(
+        parse_range("bytes=0-3", 10),
+        Some(ByteRange::Satisfiable(0, 3))
+    );
+    assert_eq!(
+        parse_range("bytes=4-", 10),
+        Some(ByteRange::Satisfiable(4, 9))
+    );
+    assert_eq!(
+        parse_range("bytes=-3", 10),
+        Some(ByteRange::Satisfiable(7, 9))
+    );
+    assert_eq!(
+        parse_range("bytes=5-100", 10),
+        Some(ByteRange::Satisfiable(5, 9))
+    );
+    assert_eq!(
+        parse_range("bytes=-20", 10),
+        Some(ByteRange::Satisfiable(0, 9))
+    );
+
+    assert_eq!(parse_range("bytes=10-", 10), Some(ByteRange::Unsatisfiable));
+    assert_eq!(parse_range("bytes=-0", 10), Some(ByteRange::Unsatisfiable));
+    assert_eq!(parse_range("bytes=0-", 0), Some(ByteRange::Unsatisfiable));
+
+    // Ignored: whole body is sent
+    assert_eq!(parse_range("bytes=0-1, 4-5", 10), None);
+    assert_eq!(parse_range("items=0-1", 10), None);
+    assert_eq!(parse_range("bytes=5-2", 10), None);
+    assert_eq!(parse_range("bytes=a-b", 10), None);
+}
+
+#[tokio::test]
+async fn test_handle_range_requests() {
+    let dir = export_dir("range");
+    let files = StaticFiles::Dir(dir.clone());
+    let path = "/_next/static/chunks/app-3f2a.js";
+
+    let (builder, body) = handle(&files, path, &headers(&[("Range", "bytes=2-5")])).await;
+    let response = builder.body(()).unwrap();
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()["Content-Range"], "bytes 2-5/10");
+    assert_eq!(body, Bytes::from("2345"));
+    let etag = response.headers()["ETag"].to_str().unwrap().to_string();
+
+    let (builder, body) = handle(&files, path, &headers(&[("Range", "bytes=20-")])).await;
+    let response = builder.body(()).unwrap();
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(response.headers()["Content-Range"], "bytes */10");
+    assert!(body.is_empty());
+
+    // If-Range: the range only applies while the validator matches
+    let request = headers(&[("Range", "bytes=-2"), ("If-Range", &etag)]);
+    let (builder, body) = handle(&files, path, &request).await;
+    assert_eq!(
+        builder.body(()).unwrap().status(),
+        StatusCode::PARTIAL_CONTENT
+    );
+    assert_eq!(body, Bytes::from("89"));
+
+    let request = headers(&[("Range", "bytes=-2"), ("If-Range", "\"old\"")]);
+    let (builder, body) = handle(&files, path, &request).await;
+    assert_eq!(builder.body(()).unwrap().status(), StatusCode::OK);
+    assert_eq!(body, Bytes::from("0123456789"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// =============================================================================
+// Embedded Dashboard Tests
+// =============================================================================
+
+#[cfg(feature = "embedded-dashboard")]
+#[tokio::test]
+async fn test_handle_embedded_dashboard() {
+    let files = StaticFiles::default();
+    assert_eq!(files, StaticFiles::Embedded);
+
+    let (builder, body) = handle(&files, "/", &HeaderMap::new()).await;
+    let response = builder.body(()).unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["Content-Type"],
+        "text/html; charset=utf-8"
+    );
+    assert!(!body.is_empty());
+
+    let etag = response.headers()["ETag"].to_str().unwrap().to_string();
+    let (builder, _body) = handle(&files, "/", &headers(&[("If-None-Match", &etag)])).await;
+    assert_eq!(builder.body(()).unwrap().status(), StatusCode::NOT_MODIFIED);
+}
@@ -3,7 +3,10 @@
 use super::HandlerResponse;
 use crate::config::IconMode;
 use crate::state::AppState;
-use crate::types::{ApiStatus, ConfigStatus, EndpointStatus, IconCacheStatusConfig, ServerStatus};
+use crate::types::{
+    ApiStatus, ConfigStatus, EndpointStatus, IconCacheStatusConfig, ServerStatus,
+    ServiceGroupAssignment,
+};
 use hyper::body::Bytes;
 use hyper::Response;
 
@@ -15,6 +18,7 @@ use hyper::Response;
 /// - config (plan, scrape_interval, groups list)
 /// - process (CPU, memory from ProcessInfoProvider in AppState)
 /// - api (last success/error, scrape counts)
+/// - service_groups (rule that matched each service)
 /// - icon_cache statistics (only in base64 mode)
 pub async fn handle(state: &AppState) -> HandlerResponse {
     let api_status = state.api_status.read().await;
@@ -26,10 +30,31 @@ pub async fn handle(state: &AppState) -> HandlerResponse {
         json: true,       // Always enabled
         websocket: state.config.websocket_enabled,
         health: true, // Always enabled
+        chargeback: !state.config.cost_centers.is_empty(),
     };
 
-    // Get group names from config
-    let service_groups: Vec<String> = state.config.service_groups.keys().cloned().collect();
+    // Get group names from config (rule order)
+    let service_groups: Vec<String> = state.config.service_groups.group_names();
+
+    // Report the groups and rules recorded for each service at the last
+    // scrape (names may have been relabeled since, so don't classify again)
+    let assignments: Vec<ServiceGroupAssignment> = state
+        .metrics_json
+        .read()
+        .await
+        .as_ref()
+        .map(|m| {
+            m.services
+                .iter()
+                .map(|s| ServiceGroupAssignment {
+                    service: s.name.clone(),
+                    group: s.group.clone(),
+                    groups: s.groups.clone(),
+                    matches: s.group_matches.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
 
     // Get icon cache statistics (both modes store icons on server)
     let icon_cache = Some(state.icon_cache.stats().await);
@@ -68,7 +93,9 @@ pub async fn handle(state: &AppState) -> HandlerResponse {
             api_url: state.config.api_url.clone(),
             service_groups,
             prices: state.config.pricing_values.clone(),
-            gzip: state.config.gzip.clone(),
+            gzip: state.config.compression.gzip.clone(),
+            brotli: state.config.compression.brotli.clone(),
+            zstd: state.config.compression.zstd.clone(),
             icon_cache: icon_cache_config,
         },
         process,
@@ -78,6 +105,7 @@ pub async fn handle(state: &AppState) -> HandlerResponse {
             total_scrapes: api_status.total_scrapes,
             failed_scrapes: api_status.failed_scrapes,
         },
+        service_groups: assignments,
         icon_cache,
     };
 
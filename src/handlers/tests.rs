@@ -1,11 +1,16 @@
 //! Handler tests for Railway Exporter.
 
 use super::{
-    finalize, health, metrics_json, metrics_prometheus, not_found, status, HandlerResponse,
+    chargeback, finalize, health, metrics_influx, metrics_json, metrics_prometheus, not_found,
+    status, CachedBody, HandlerResponse,
 };
+use crate::compression::Compression;
 use crate::config::{Config, GzipConfig, Plan};
+use crate::cors::CorsPolicy;
 use crate::state::AppState;
+use http_body_util::BodyExt;
 use hyper::body::Bytes;
+use hyper::header::HeaderMap;
 use hyper::http::StatusCode;
 use hyper::Response;
 use std::sync::Arc;
@@ -22,6 +27,14 @@ fn create_test_state() -> Arc<AppState> {
     Arc::new(AppState::new(create_test_config()))
 }
 
+fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    for (name, value) in pairs {
+        map.insert(*name, value.parse().unwrap());
+    }
+    map
+}
+
 // =============================================================================
 // Health Handler Tests
 // =============================================================================
@@ -67,9 +80,9 @@ fn test_finalize_without_cors() {
         Response::builder().header("Content-Type", "text/plain"),
         Bytes::from("test"),
     );
-    let gzip = GzipConfig::default();
+    let compression = Compression::default();
 
-    let result = finalize(response, false, false, &gzip);
+    let result = finalize(response, None, None, None, &compression);
 
     assert!(result
         .headers()
@@ -83,9 +96,15 @@ fn test_finalize_with_cors() {
         Response::builder().header("Content-Type", "text/plain"),
         Bytes::from("test"),
     );
-    let gzip = GzipConfig::default();
-
-    let result = finalize(response, true, false, &gzip);
+    let compression = Compression::default();
+
+    let result = finalize(
+        response,
+        Some(&CorsPolicy::default()),
+        None,
+        None,
+        &compression,
+    );
 
     let cors = result.headers().get("Access-Control-Allow-Origin").unwrap();
     assert_eq!(cors, "*");
@@ -103,8 +122,12 @@ fn test_finalize_gzip_disabled() {
         min_size: 256,
         level: 1,
     };
+    let compression = Compression {
+        gzip,
+        ..Default::default()
+    };
 
-    let result = finalize(response, false, true, &gzip);
+    let result = finalize(response, None, None, Some("gzip"), &compression);
 
     assert!(result.headers().get("Content-Encoding").is_none());
 }
@@ -116,10 +139,10 @@ fn test_finalize_gzip_not_requested() {
         Response::builder().header("Content-Type", "text/plain"),
         Bytes::from(large_body),
     );
-    let gzip = GzipConfig::default();
+    let compression = Compression::default();
 
-    // gzip_requested = false
-    let result = finalize(response, false, false, &gzip);
+    // No Accept-Encoding
+    let result = finalize(response, None, None, None, &compression);
 
     assert!(result.headers().get("Content-Encoding").is_none());
 }
@@ -131,9 +154,9 @@ fn test_finalize_gzip_body_too_small() {
         Response::builder().header("Content-Type", "text/plain"),
         Bytes::from(small_body),
     );
-    let gzip = GzipConfig::default();
+    let compression = Compression::default();
 
-    let result = finalize(response, false, true, &gzip);
+    let result = finalize(response, None, None, Some("gzip"), &compression);
 
     assert!(result.headers().get("Content-Encoding").is_none());
 }
@@ -145,9 +168,9 @@ fn test_finalize_gzip_compresses_large_body() {
         Response::builder().header("Content-Type", "text/plain"),
         Bytes::from(large_body),
     );
-    let gzip = GzipConfig::default();
+    let compression = Compression::default();
 
-    let result = finalize(response, false, true, &gzip);
+    let result = finalize(response, None, None, Some("gzip"), &compression);
 
     let encoding = result.headers().get("Content-Encoding");
     assert_eq!(encoding.unwrap(), "gzip");
@@ -165,12 +188,100 @@ fn test_finalize_gzip_custom_level() {
         min_size: 256,
         level: 9, // Max compression
     };
+    let compression = Compression {
+        gzip,
+        ..Default::default()
+    };
 
-    let result = finalize(response, false, true, &gzip);
+    let result = finalize(response, None, None, Some("gzip"), &compression);
 
     assert_eq!(result.headers().get("Content-Encoding").unwrap(), "gzip");
 }
 
+#[test]
+fn test_finalize_negotiates_encoding() {
+    let response = || -> HandlerResponse {
+        (
+            Response::builder().header("Content-Type", "text/plain"),
+            Bytes::from("x".repeat(1000)),
+        )
+    };
+    let compression = Compression::default();
+    let encoding = |accept: &str| {
+        let result = finalize(response(), None, None, Some(accept), &compression);
+        result
+            .headers()
+            .get("Content-Encoding")
+            .map(|v| v.to_str().unwrap().to_string())
+    };
+
+    assert_eq!(encoding("gzip, deflate, br, zstd").as_deref(), Some("br"));
+    assert_eq!(encoding("br;q=0.5, gzip").as_deref(), Some("gzip"));
+    assert_eq!(encoding("zstd, gzip;q=0.9").as_deref(), Some("zstd"));
+    assert_eq!(encoding("gzip;q=0"), None);
+    assert_eq!(encoding("identity"), None);
+}
+
+#[test]
+fn test_finalize_vary_accept_encoding() {
+    let compression = Compression::default();
+
+    // Compressible bodies vary on Accept-Encoding, even when sent plain
+    let response: HandlerResponse = (Response::builder(), Bytes::from("x".repeat(1000)));
+    let result = finalize(response, None, None, None, &compression);
+    assert_eq!(result.headers()["Vary"], "Accept-Encoding");
+
+    // Bodies below every min_size do not
+    let response: HandlerResponse = (Response::builder(), Bytes::from("x".repeat(100)));
+    let result = finalize(response, None, None, Some("br"), &compression);
+    assert!(result.headers().get("Vary").is_none());
+
+    // Added next to the CORS Vary, not over an existing one
+    let cors = CorsPolicy::new(
+        serde_yaml::from_str("allowed_origins: [\"https://dash.example.com\"]").unwrap(),
+    )
+    .unwrap();
+    let response: HandlerResponse = (
+        Response::builder().header("Vary", "Accept"),
+        Bytes::from("x".repeat(1000)),
+    );
+    let result = finalize(
+        response,
+        Some(&cors),
+        Some("https://dash.example.com"),
+        Some("br"),
+        &compression,
+    );
+    let vary: Vec<_> = result.headers().get_all("Vary").iter().collect();
+    assert_eq!(vary, ["Accept", "Origin", "Accept-Encoding"]);
+}
+
+#[test]
+fn test_finalize_etag_and_ranges() {
+    let compression = Compression::default();
+
+    // Compressed bodies get a weak ETag
+    let response: HandlerResponse = (
+        Response::builder().header("ETag", "\"abc\""),
+        Bytes::from("x".repeat(1000)),
+    );
+    let result = finalize(response, None, None, Some("gzip"), &compression);
+    assert_eq!(result.headers()["Content-Encoding"], "gzip");
+    assert_eq!(result.headers()["ETag"], "W/\"abc\"");
+
+    // Partial content is never compressed
+    let response: HandlerResponse = (
+        Response::builder()
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header("ETag", "\"abc\"")
+            .header("Content-Range", "bytes 0-999/5000"),
+        Bytes::from("x".repeat(1000)),
+    );
+    let result = finalize(response, None, None, Some("gzip"), &compression);
+    assert!(result.headers().get("Content-Encoding").is_none());
+    assert_eq!(result.headers()["ETag"], "\"abc\"");
+}
+
 // =============================================================================
 // Metrics Prometheus Handler Tests
 // =============================================================================
@@ -178,23 +289,320 @@ fn test_finalize_gzip_custom_level() {
 #[test]
 fn test_metrics_prometheus_content_type() {
     let state = create_test_state();
-    let (builder, _body) = metrics_prometheus(&state);
+    let (builder, _body) = metrics_prometheus(&state, &HeaderMap::new(), None);
     let response = builder.body(Bytes::new()).unwrap();
 
     let content_type = response.headers().get("Content-Type").unwrap();
     assert_eq!(content_type, "text/plain; version=0.0.4; charset=utf-8");
 }
 
+#[test]
+fn test_metrics_prometheus_openmetrics() {
+    let state = create_test_state();
+    let (builder, body) = metrics_prometheus(
+        &state,
+        &headers(&[("Accept", "application/openmetrics-text")]),
+        None,
+    );
+    let response = builder.body(Bytes::new()).unwrap();
+
+    let content_type = response.headers().get("Content-Type").unwrap();
+    assert_eq!(
+        content_type,
+        "application/openmetrics-text; version=1.0.0; charset=utf-8"
+    );
+    assert!(String::from_utf8_lossy(&body).ends_with("# EOF\n"));
+}
+
+#[test]
+fn test_metrics_prometheus_protobuf() {
+    let state = create_test_state();
+    state.metrics.api_up.with_label_values(&["p"]).set(1.0);
+    let accept = "application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;encoding=delimited";
+    let (builder, body) = metrics_prometheus(&state, &headers(&[("Accept", accept)]), None);
+    let response = builder.body(Bytes::new()).unwrap();
+
+    assert_eq!(
+        response.headers()["Content-Type"],
+        "application/vnd.google.protobuf; proto=io.prometheus.client.MetricFamily; encoding=delimited"
+    );
+    assert!(!body.is_empty());
+    assert!(!body.starts_with(b"# "));
+}
+
 #[test]
 fn test_metrics_prometheus_returns_data() {
     let state = create_test_state();
-    let (_builder, body) = metrics_prometheus(&state);
+    let (_builder, body) = metrics_prometheus(&state, &HeaderMap::new(), None);
 
     // Body should contain Prometheus metrics format
     let body_str = String::from_utf8_lossy(&body);
     assert!(body_str.contains("# HELP") || body_str.contains("# TYPE") || !body_str.is_empty());
 }
 
+#[test]
+fn test_metrics_prometheus_filter() {
+    let state = create_test_state();
+    state.metrics.api_up.with_label_values(&["p"]).set(1.0);
+
+    let (_builder, body) =
+        metrics_prometheus(&state, &HeaderMap::new(), Some("name[]=railway_api_up"));
+    let body_str = String::from_utf8_lossy(&body);
+    assert!(body_str.contains("railway_api_up{project=\"p\"} 1"));
+    assert!(!body_str.contains("railway_exporter_memory_bytes"));
+
+    let (builder, _body) =
+        metrics_prometheus(&state, &HeaderMap::new(), Some("match[]={project=~\"(\""));
+    let response = builder.body(Bytes::new()).unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+// =============================================================================
+// Cached Metrics Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_metrics_served_from_cache() {
+    let state = create_test_state();
+    state.metrics.api_up.with_label_values(&["p"]).set(1.0);
+    state.refresh_metrics_cache().await;
+
+    // Changes after the collection are not visible until the next refresh
+    state.metrics.api_up.with_label_values(&["p"]).set(0.0);
+
+    let (builder, body) = metrics_prometheus(&state, &HeaderMap::new(), None);
+    let response = builder.body(Bytes::new()).unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("ETag"));
+    assert!(response.headers().contains_key("Last-Modified"));
+    assert_eq!(response.headers()["Vary"], "Accept, Accept-Encoding");
+    assert!(String::from_utf8_lossy(&body).contains("railway_api_up{project=\"p\"} 1"));
+
+    // Filtered requests are rendered on demand
+    let (_builder, body) =
+        metrics_prometheus(&state, &HeaderMap::new(), Some("name[]=railway_api_up"));
+    assert!(String::from_utf8_lossy(&body).contains("railway_api_up{project=\"p\"} 0"));
+}
+
+#[tokio::test]
+async fn test_metrics_repeat_scrape_not_modified() {
+    let state = create_test_state();
+    state.refresh_metrics_cache().await;
+
+    let (builder, _body) = metrics_prometheus(&state, &HeaderMap::new(), None);
+    let response = builder.body(Bytes::new()).unwrap();
+    let etag = response.headers()["ETag"].to_str().unwrap().to_string();
+
+    // Requests in between don't change the body until the next refresh
+    state.metrics.observe_request("/metrics", 200, None, 0.01);
+    let (builder, body) = metrics_prometheus(&state, &headers(&[("If-None-Match", &etag)]), None);
+    let response = builder.body(Bytes::new()).unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert!(body.is_empty());
+
+    // Each format has its own body
+    let request = headers(&[
+        ("Accept", "application/openmetrics-text"),
+        ("If-None-Match", &etag),
+    ]);
+    let (builder, _body) = metrics_prometheus(&state, &request, None);
+    assert_eq!(builder.body(Bytes::new()).unwrap().status(), StatusCode::OK);
+
+    // A self-metrics refresh produces a new body
+    state.refresh_self_metrics();
+    let (builder, _body) = metrics_prometheus(&state, &headers(&[("If-None-Match", &etag)]), None);
+    assert_eq!(builder.body(Bytes::new()).unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_metrics_self_metrics_refreshed() {
+    let state = create_test_state();
+    state.refresh_metrics_cache().await;
+
+    let sample =
+        "railway_exporter_http_requests_total{encoding=\"identity\",route=\"/metrics\",status=\"200\"}";
+    state.metrics.observe_request("/metrics", 200, None, 0.01);
+    state.refresh_self_metrics();
+    let (_builder, body) = metrics_prometheus(&state, &HeaderMap::new(), None);
+    assert!(String::from_utf8_lossy(&body).contains(&format!("{} 1", sample)));
+
+    // Self-metrics advance between collections
+    state.metrics.observe_request("/metrics", 200, None, 0.01);
+    state.refresh_self_metrics();
+    let (_builder, body) = metrics_prometheus(&state, &HeaderMap::new(), None);
+    let text = String::from_utf8_lossy(&body);
+    assert!(text.contains(&format!("{} 2", sample)));
+    assert!(text.contains("railway_exporter_memory_bytes"));
+    assert_eq!(
+        text.matches("# TYPE railway_exporter_http_requests_total")
+            .count(),
+        1
+    );
+
+    // OpenMetrics still ends with a single EOF after the appended part
+    let request = headers(&[("Accept", "application/openmetrics-text")]);
+    let (_builder, body) = metrics_prometheus(&state, &request, None);
+    let text = String::from_utf8_lossy(&body);
+    assert!(text.ends_with("# EOF\n"));
+    assert_eq!(text.matches("# EOF").count(), 1);
+    assert!(text.contains("railway_exporter_http_requests_total"));
+}
+
+#[tokio::test]
+async fn test_metrics_served_precompressed() {
+    let state = create_test_state();
+    state.refresh_metrics_cache().await;
+
+    let accept = "gzip, deflate, br, zstd";
+    let request = headers(&[("Accept-Encoding", accept)]);
+    let (builder, body) = metrics_prometheus(&state, &request, None);
+    let response = finalize(
+        (builder, body),
+        None,
+        None,
+        Some(accept),
+        &Compression::default(),
+    );
+    assert_eq!(response.headers()["Content-Encoding"], "br");
+    assert!(response.headers()["ETag"]
+        .to_str()
+        .unwrap()
+        .ends_with("-br\""));
+    let vary: Vec<_> = response.headers().get_all("Vary").iter().collect();
+    assert_eq!(vary, ["Accept, Accept-Encoding"]);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let mut text = String::new();
+    std::io::Read::read_to_string(&mut brotli::Decompressor::new(&body[..], 4096), &mut text)
+        .unwrap();
+    assert!(text.contains("# TYPE"));
+}
+
+// =============================================================================
+// Cached Body Tests
+// =============================================================================
+
+fn cached_json() -> CachedBody {
+    let body = format!("[{}]", r#"{"service":"web","cost":1.5}"#.repeat(100));
+    CachedBody::new(
+        "application/json",
+        Bytes::from(body),
+        chrono::Utc::now(),
+        &Compression::default(),
+    )
+}
+
+#[test]
+fn test_cached_body_if_none_match() {
+    let cached = cached_json();
+
+    let response = cached
+        .response(&HeaderMap::new())
+        .0
+        .body(Bytes::new())
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["Vary"], "Accept, Accept-Encoding");
+    let etag = response.headers()["ETag"].to_str().unwrap().to_string();
+    let last_modified = response.headers()["Last-Modified"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(etag, cached.etag());
+
+    for request in [
+        headers(&[("If-None-Match", &etag)]),
+        headers(&[("If-None-Match", &format!("\"other\", W/{}", etag))]),
+        headers(&[("If-Modified-Since", &last_modified)]),
+    ] {
+        let (builder, body) = cached.response(&request);
+        let response = builder.body(Bytes::new()).unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(body.is_empty());
+    }
+
+    // If-None-Match wins over If-Modified-Since
+    let request = headers(&[
+        ("If-None-Match", "\"stale\""),
+        ("If-Modified-Since", &last_modified),
+    ]);
+    let (builder, _body) = cached.response(&request);
+    assert_eq!(builder.body(Bytes::new()).unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_cached_body_gzip() {
+    let cached = cached_json();
+
+    let request = headers(&[("Accept-Encoding", "gzip")]);
+    let response = finalize(
+        cached.response(&request),
+        None,
+        None,
+        Some("gzip"),
+        &Compression::default(),
+    );
+    assert_eq!(response.headers()["Content-Encoding"], "gzip");
+    let etag = response.headers()["ETag"].to_str().unwrap().to_string();
+    assert!(etag.ends_with("-gzip\""));
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let mut decoder = flate2::read::GzDecoder::new(&body[..]);
+    let mut text = String::new();
+    std::io::Read::read_to_string(&mut decoder, &mut text).unwrap();
+    assert!(text.contains("\"service\":\"web\""));
+
+    let request = headers(&[("Accept-Encoding", "gzip"), ("If-None-Match", &etag)]);
+    let (builder, _body) = cached.response(&request);
+    assert_eq!(
+        builder.body(Bytes::new()).unwrap().status(),
+        StatusCode::NOT_MODIFIED
+    );
+}
+
+#[tokio::test]
+async fn test_cached_body_brotli_and_zstd() {
+    let cached = cached_json();
+
+    let accept = "gzip, deflate, br, zstd";
+    let response = finalize(
+        cached.response(&headers(&[("Accept-Encoding", accept)])),
+        None,
+        None,
+        Some(accept),
+        &Compression::default(),
+    );
+    assert_eq!(response.headers()["Content-Encoding"], "br");
+    assert!(response.headers()["ETag"]
+        .to_str()
+        .unwrap()
+        .ends_with("-br\""));
+    assert_eq!(response.headers().get_all("Vary").iter().count(), 1);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let mut text = String::new();
+    std::io::Read::read_to_string(&mut brotli::Decompressor::new(&body[..], 4096), &mut text)
+        .unwrap();
+    assert!(text.contains("\"service\":\"web\""));
+
+    let (builder, body) = cached.response(&headers(&[("Accept-Encoding", "br;q=0.5, zstd")]));
+    let response = builder.body(body).unwrap();
+    assert_eq!(response.headers()["Content-Encoding"], "zstd");
+    let text = zstd::decode_all(&response.body()[..]).unwrap();
+    assert!(String::from_utf8_lossy(&text).contains("\"service\":\"web\""));
+
+    let (builder, _body) = cached.response(&headers(&[("Accept-Encoding", "*;q=0")]));
+    let response = builder.body(Bytes::new()).unwrap();
+    assert!(response.headers().get("Content-Encoding").is_none());
+}
+
+#[tokio::test]
+async fn test_metrics_json_cached_after_collection() {
+    let state = create_test_state();
+    state.refresh_metrics_cache().await;
+    // No snapshot yet: falls back to the live handler
+    let (_builder, body) = metrics_json(&state, &HeaderMap::new()).await;
+    assert!(String::from_utf8_lossy(&body).contains("No data yet"));
+}
+
 // =============================================================================
 // Metrics JSON Handler Tests
 // =============================================================================
@@ -202,7 +610,7 @@ fn test_metrics_prometheus_returns_data() {
 #[tokio::test]
 async fn test_metrics_json_no_data() {
     let state = create_test_state();
-    let (builder, body) = metrics_json(&state).await;
+    let (builder, body) = metrics_json(&state, &HeaderMap::new()).await;
     let response = builder.body(Bytes::new()).unwrap();
 
     let content_type = response.headers().get("Content-Type").unwrap();
@@ -212,6 +620,14 @@ async fn test_metrics_json_no_data() {
     assert!(body_str.contains("No data yet"));
 }
 
+#[tokio::test]
+async fn test_metrics_influx_no_data() {
+    let state = create_test_state();
+    let (builder, _) = metrics_influx(&state).await;
+    let response = builder.body(Bytes::new()).unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
 // =============================================================================
 // Status Handler Tests
 // =============================================================================
@@ -283,3 +699,109 @@ async fn test_status_handler_api_status() {
     assert!(parsed["api"]["last_success"].is_null());
     assert!(parsed["api"]["last_error"].is_null());
 }
+
+// =============================================================================
+// Chargeback Handler Tests
+// =============================================================================
+
+fn create_chargeback_state() -> Arc<AppState> {
+    use crate::chargeback::{CostCenterRule, CostCenters};
+    let mut config = create_test_config();
+    config.cost_centers = CostCenters::new(vec![CostCenterRule {
+        name: "platform".to_string(),
+        dimension: "team".to_string(),
+        kind: crate::grouping::MatchKind::Contains,
+        patterns: vec!["postgres".to_string()],
+        case_sensitive: false,
+    }])
+    .unwrap();
+    Arc::new(AppState::new(config))
+}
+
+#[tokio::test]
+async fn test_chargeback_not_configured() {
+    let state = create_test_state();
+    let (builder, _body) = chargeback(&state, None).await;
+    let response = builder.body(Bytes::new()).unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_chargeback_json_and_csv() {
+    use crate::chargeback::ChargebackPeriod;
+    let state = create_chargeback_state();
+    state.chargeback.write().await.record(
+        ChargebackPeriod {
+            period: "2026-10".to_string(),
+            project: "p".to_string(),
+            closed: false,
+            updated_at: 0,
+            rows: vec![],
+        },
+        12,
+    );
+
+    let (_builder, body) = chargeback(&state, None).await;
+    let parsed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(parsed["dimensions"][0], "team");
+    assert_eq!(parsed["periods"][0]["period"], "2026-10");
+
+    let (builder, body) = chargeback(&state, Some("format=csv&period=2026-10")).await;
+    let response = builder.body(Bytes::new()).unwrap();
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    assert_eq!(
+        response.headers().get("Content-Disposition").unwrap(),
+        "attachment; filename=\"chargeback-2026-10.csv\""
+    );
+    assert!(String::from_utf8_lossy(&body).starts_with("period,status"));
+
+    let (builder, _body) = chargeback(&state, Some("format=xml")).await;
+    let response = builder.body(Bytes::new()).unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_chargeback_rejects_invalid_period() {
+    let state = create_chargeback_state();
+    for query in [
+        "format=csv&period=%0d%0a",
+        "format=csv&period=2026-10%22%3B%20x",
+        "period=2026-13",
+        "period=26-10",
+    ] {
+        let (builder, body) = chargeback(&state, Some(query)).await;
+        let response = finalize((builder, body), None, None, None, &Compression::default());
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+        assert!(response.headers().get("Content-Disposition").is_none());
+    }
+}
+
+#[test]
+fn test_query_param() {
+    use super::query_param;
+    assert_eq!(
+        query_param(Some("a=1&format=csv"), "format").as_deref(),
+        Some("csv")
+    );
+    assert_eq!(
+        query_param(Some("name=a%20b+c"), "name").as_deref(),
+        Some("a b c")
+    );
+    assert_eq!(query_param(Some("flag"), "flag").as_deref(), Some(""));
+    assert_eq!(query_param(Some("a=1"), "b"), None);
+    assert_eq!(query_param(None, "a"), None);
+}
+
+#[test]
+fn test_query_values() {
+    use super::query_values;
+    assert_eq!(
+        query_values(Some("match[]=a&x=1&match%5B%5D=b%20c"), "match[]"),
+        vec!["a", "b c"]
+    );
+    assert!(query_values(Some("match=a"), "match[]").is_empty());
+    assert!(query_values(None, "match[]").is_empty());
+}
@@ -0,0 +1,178 @@
+//! InfluxDB line protocol.
+//!
+//! Renders the JSON snapshot (`MetricsJson`) as line protocol, served on
+//! `/metrics/influx` and written by the `influxdb` sink. Every point carries
+//! the snapshot's `scrape_timestamp` (second precision).
+//!
+//! | Measurement | Tags | Fields |
+//! |-------------|------|--------|
+//! | `railway_project` | `project` | `current_usage_usd`, `estimated_monthly_usd`, `daily_average_usd`, `days_elapsed`, `days_remaining` |
+//! | `railway_service` | `project`, `service`, `service_id`, `group`, `deleted` | `cpu_usage`, `memory_usage`, `disk_usage`, `network_tx`, `cost_usd`, `estimated_monthly_usd` |
+//! | `railway_group` | `project`, `group` | `services`, usage fields, `cost_usd`, `estimated_monthly_usd`, `cost_share` |
+//! | `railway_group_allocated` | `project`, `group` | `direct_cost_usd`, `received_usd`, `distributed_usd`, `allocated_cost_usd`, `allocated_estimated_monthly_usd` |
+//! | `railway_scrape` | `project` | `duration_seconds` |
+//!
+//! ```text
+//! railway_service,project=my-project,service=api,service_id=abc,group=backend,deleted=false cpu_usage=1.5,...,cost_usd=2.5 1700000000
+//! ```
+
+use crate::types::MetricsJson;
+use std::fmt::Write;
+
+/// Content type of line protocol responses.
+pub const CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// Field value of a point.
+enum Field {
+    Float(f64),
+    Int(i64),
+}
+
+/// One line protocol point being built.
+struct Point<'a> {
+    measurement: &'a str,
+    tags: Vec<(&'a str, &'a str)>,
+    fields: Vec<(&'a str, Field)>,
+}
+
+impl<'a> Point<'a> {
+    fn new(measurement: &'a str, project: &'a str) -> Self {
+        Self {
+            measurement,
+            tags: vec![("project", project)],
+            fields: Vec::new(),
+        }
+    }
+
+    fn tag(mut self, key: &'a str, value: &'a str) -> Self {
+        self.tags.push((key, value));
+        self
+    }
+
+    fn float(mut self, key: &'a str, value: f64) -> Self {
+        self.fields.push((key, Field::Float(value)));
+        self
+    }
+
+    fn int(mut self, key: &'a str, value: i64) -> Self {
+        self.fields.push((key, Field::Int(value)));
+        self
+    }
+
+    /// Appends the point to `out`. Points without a finite field are skipped.
+    fn write(&self, out: &mut String, timestamp: i64) {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .filter_map(|(key, value)| match value {
+                Field::Float(v) if v.is_finite() => Some(format!("{}={}", escape_key(key), v)),
+                Field::Float(_) => None,
+                Field::Int(v) => Some(format!("{}={}i", escape_key(key), v)),
+            })
+            .collect();
+        if fields.is_empty() {
+            return;
+        }
+
+        out.push_str(&escape_measurement(self.measurement));
+        for (key, value) in &self.tags {
+            // Empty tag values are invalid in line protocol
+            if !value.is_empty() {
+                let _ = write!(out, ",{}={}", escape_key(key), escape_key(value));
+            }
+        }
+        let _ = writeln!(out, " {} {}", fields.join(","), timestamp);
+    }
+}
+
+/// Renders a snapshot as line protocol (one point per line).
+pub fn encode(json: &MetricsJson) -> String {
+    let project = json.project.name.as_str();
+    let ts = json.scrape_timestamp;
+    let mut out = String::new();
+
+    let p = &json.project;
+    Point::new("railway_project", project)
+        .float("current_usage_usd", p.current_usage_usd)
+        .float("estimated_monthly_usd", p.estimated_monthly_usd)
+        .float("daily_average_usd", p.daily_average_usd)
+        .int("days_elapsed", p.days_elapsed as i64)
+        .int("days_remaining", p.days_remaining as i64)
+        .write(&mut out, ts);
+
+    for s in &json.services {
+        Point::new("railway_service", project)
+            .tag("service", &s.name)
+            .tag("service_id", &s.id)
+            .tag("group", &s.group)
+            .tag("deleted", if s.is_deleted { "true" } else { "false" })
+            .float("cpu_usage", s.cpu_usage)
+            .float("memory_usage", s.memory_usage)
+            .float("disk_usage", s.disk_usage)
+            .float("network_tx", s.network_tx)
+            .float("cost_usd", s.cost_usd)
+            .float("estimated_monthly_usd", s.estimated_monthly_usd)
+            .write(&mut out, ts);
+    }
+
+    for g in &json.groups {
+        Point::new("railway_group", project)
+            .tag("group", &g.name)
+            .int("services", g.service_count as i64)
+            .float("cpu_usage", g.cpu_usage)
+            .float("memory_usage", g.memory_usage)
+            .float("disk_usage", g.disk_usage)
+            .float("network_tx", g.network_tx)
+            .float("cost_usd", g.cost_usd)
+            .float("estimated_monthly_usd", g.estimated_monthly_usd)
+            .float("cost_share", g.cost_share)
+            .write(&mut out, ts);
+    }
+
+    for a in &json.allocated {
+        Point::new("railway_group_allocated", project)
+            .tag("group", &a.name)
+            .float("direct_cost_usd", a.direct_cost_usd)
+            .float("received_usd", a.received_usd)
+            .float("distributed_usd", a.distributed_usd)
+            .float("allocated_cost_usd", a.allocated_cost_usd)
+            .float(
+                "allocated_estimated_monthly_usd",
+                a.allocated_estimated_monthly_usd,
+            )
+            .write(&mut out, ts);
+    }
+
+    Point::new("railway_scrape", project)
+        .float("duration_seconds", json.scrape_duration_seconds)
+        .write(&mut out, ts);
+
+    out
+}
+
+/// Escapes a measurement name (commas and spaces).
+fn escape_measurement(value: &str) -> String {
+    escape(value, &[',', ' '])
+}
+
+/// Escapes a tag key, tag value or field key (commas, equals signs and spaces).
+fn escape_key(value: &str) -> String {
+    escape(value, &[',', '=', ' '])
+}
+
+fn escape(value: &str, special: &[char]) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            // Line breaks would end the point
+            '\n' | '\r' => out.push_str("\\ "),
+            '\\' => out.push_str("\\\\"),
+            c if special.contains(&c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,105 @@
+//! Tests for InfluxDB line protocol encoding.
+
+use crate::influx::encode;
+use crate::test_support::service;
+use crate::types::{AllocatedGroup, GroupSummary, MetricsJson, ProjectSummary, ServiceData};
+
+fn sample_json() -> MetricsJson {
+    MetricsJson {
+        project: ProjectSummary {
+            name: "my project".to_string(),
+            current_usage_usd: 10.5,
+            estimated_monthly_usd: 31.0,
+            daily_average_usd: 1.05,
+            days_elapsed: 10,
+            days_remaining: 20,
+        },
+        services: vec![ServiceData {
+            id: "svc-1".to_string(),
+            cpu_usage: 1.5,
+            memory_usage: 2.0,
+            network_tx: 0.25,
+            ..service("api,v2", &["backend"], 2.5)
+        }],
+        groups: vec![GroupSummary {
+            name: "backend".to_string(),
+            service_count: 1,
+            cpu_usage: 1.5,
+            memory_usage: 2.0,
+            disk_usage: 0.0,
+            network_tx: 0.25,
+            cost_usd: 2.5,
+            estimated_monthly_usd: 7.5,
+            cost_share: 0.25,
+        }],
+        allocated: vec![],
+        scrape_timestamp: 1700000000,
+        scrape_duration_seconds: 0.5,
+    }
+}
+
+// =============================================================================
+// Encoding Tests
+// =============================================================================
+
+#[test]
+fn test_encode_points() {
+    let lines: Vec<String> = encode(&sample_json()).lines().map(String::from).collect();
+
+    assert_eq!(
+        lines,
+        vec![
+            "railway_project,project=my\\ project current_usage_usd=10.5,estimated_monthly_usd=31,daily_average_usd=1.05,days_elapsed=10i,days_remaining=20i 1700000000",
+            "railway_service,project=my\\ project,service=api\\,v2,service_id=svc-1,group=backend,deleted=false cpu_usage=1.5,memory_usage=2,disk_usage=0,network_tx=0.25,cost_usd=2.5,estimated_monthly_usd=7.5 1700000000",
+            "railway_group,project=my\\ project,group=backend services=1i,cpu_usage=1.5,memory_usage=2,disk_usage=0,network_tx=0.25,cost_usd=2.5,estimated_monthly_usd=7.5,cost_share=0.25 1700000000",
+            "railway_scrape,project=my\\ project duration_seconds=0.5 1700000000",
+        ]
+    );
+}
+
+#[test]
+fn test_encode_allocated_groups() {
+    let mut json = sample_json();
+    json.allocated = vec![AllocatedGroup {
+        name: "backend".to_string(),
+        direct_cost_usd: 2.5,
+        received_usd: 1.0,
+        distributed_usd: 0.0,
+        allocated_cost_usd: 3.5,
+        allocated_estimated_monthly_usd: 10.5,
+    }];
+
+    let out = encode(&json);
+    assert!(out.contains(
+        "railway_group_allocated,project=my\\ project,group=backend direct_cost_usd=2.5,received_usd=1,distributed_usd=0,allocated_cost_usd=3.5,allocated_estimated_monthly_usd=10.5 1700000000\n"
+    ));
+}
+
+#[test]
+fn test_encode_skips_non_finite_fields_and_empty_tags() {
+    let mut json = sample_json();
+    json.services[0].group = String::new();
+    json.services[0].cost_usd = f64::NAN;
+    json.scrape_duration_seconds = f64::INFINITY;
+
+    let out = encode(&json);
+    let service = out
+        .lines()
+        .find(|l| l.starts_with("railway_service"))
+        .unwrap();
+    assert!(!service.contains("group="));
+    assert!(!service.contains("cost_usd=NaN"));
+    assert!(service.contains("estimated_monthly_usd=7.5"));
+    // No finite field left: point is dropped
+    assert!(!out.contains("railway_scrape"));
+}
+
+#[test]
+fn test_encode_escapes_special_characters() {
+    let mut json = sample_json();
+    json.services[0].name = "a=b c\nd\\".to_string();
+
+    let out = encode(&json);
+    assert!(out.contains(",service=a\\=b\\ c\\ d\\\\,"));
+    assert_eq!(out.lines().count(), 4);
+}
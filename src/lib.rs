@@ -11,22 +11,54 @@
 //! ## Architecture
 //!
 //! - `config` - YAML configuration loading
+//! - `access_log` - JSON / Common Log Format request logging
+//! - `cors` - Cross-origin policy and preflight handling
+//! - `allocation` - Shared-cost redistribution between groups
+//! - `auth` - Bearer, basic and trusted-proxy authentication per route
 //! - `metrics` - Prometheus metrics definitions
+//! - `compression` - Brotli, zstd and gzip response compression
+//! - `exposition` - Prometheus text / OpenMetrics encoding and negotiation
+//! - `influx` - InfluxDB line protocol encoding
+//! - `relabel` - Label drop/rename/replace rules and series limit
+//! - `selector` - Federation-style `name[]` / `match[]` filtering
 //! - `pricing` - Railway pricing calculations
 //! - `client` - Railway GraphQL API client
 //! - `types` - Shared data types
 //! - `state` - Application state management
+//! - `chargeback` - Cost centers and monthly chargeback ledger
 //! - `collector` - Metrics collection logic
+//! - `sinks` - Push sinks (remote_write, OTLP, Pushgateway, StatsD, InfluxDB)
+//! - `grouping` - Service grouping rules
+//! - `filter` - Service include/exclude filters
 //! - `server` - HTTP server and handlers
+//! - `listen` - TCP, Unix and systemd-activated listening sockets
+//! - `limits` - Rate limits, connection limits and timeouts
+//! - `tls` - Native TLS termination with certificate reload
 
+pub mod access_log;
+pub mod allocation;
+pub mod auth;
+pub mod chargeback;
 pub mod client;
 pub mod collector;
+pub mod compression;
 pub mod config;
+pub mod cors;
+pub mod exposition;
+pub mod filter;
+pub mod grouping;
 pub mod handlers;
+pub mod influx;
+pub mod limits;
+pub mod listen;
 pub mod metrics;
 pub mod pricing;
+pub mod relabel;
+pub mod selector;
 pub mod server;
+pub mod sinks;
 pub mod state;
+pub mod tls;
 pub mod types;
 pub mod utils;
 
@@ -34,10 +66,22 @@ pub use config::{Config, Plan};
 pub use metrics::Metrics;
 pub use state::AppState;
 
+#[cfg(test)]
+#[path = "test_support.rs"]
+mod test_support;
+
 #[cfg(test)]
 #[path = "config_test.rs"]
 mod config_test;
 
+#[cfg(test)]
+#[path = "compression_test.rs"]
+mod compression_test;
+
+#[cfg(test)]
+#[path = "cors_test.rs"]
+mod cors_test;
+
 #[cfg(test)]
 #[path = "types_test.rs"]
 mod types_test;
@@ -65,3 +109,55 @@ mod metrics_test;
 #[cfg(test)]
 #[path = "pricing_test.rs"]
 mod pricing_test;
+
+#[cfg(test)]
+#[path = "grouping_test.rs"]
+mod grouping_test;
+
+#[cfg(test)]
+#[path = "access_log_test.rs"]
+mod access_log_test;
+
+#[cfg(test)]
+#[path = "allocation_test.rs"]
+mod allocation_test;
+
+#[cfg(test)]
+#[path = "auth_test.rs"]
+mod auth_test;
+
+#[cfg(test)]
+#[path = "chargeback_test.rs"]
+mod chargeback_test;
+
+#[cfg(test)]
+#[path = "filter_test.rs"]
+mod filter_test;
+
+#[cfg(test)]
+#[path = "exposition_test.rs"]
+mod exposition_test;
+
+#[cfg(test)]
+#[path = "influx_test.rs"]
+mod influx_test;
+
+#[cfg(test)]
+#[path = "limits_test.rs"]
+mod limits_test;
+
+#[cfg(test)]
+#[path = "listen_test.rs"]
+mod listen_test;
+
+#[cfg(test)]
+#[path = "relabel_test.rs"]
+mod relabel_test;
+
+#[cfg(test)]
+#[path = "selector_test.rs"]
+mod selector_test;
+
+#[cfg(test)]
+#[path = "tls_test.rs"]
+mod tls_test;
@@ -0,0 +1,450 @@
+//! Rate limits, connection limits and timeouts.
+//!
+//! - `rate_limit` — token buckets per client address: `requests_per_second`
+//!   refills the bucket, `burst` is its size. Each `routes` entry (exact path
+//!   or `/prefix/*`, first match wins) gets its own bucket per client; other
+//!   paths share the default bucket (unlimited if no default rate is set).
+//!   Requests over the limit get `429` with `Retry-After`.
+//! - `max_connections` — open HTTP connections; further connections get `503`
+//!   and are closed.
+//! - `max_ws_clients` — connected `/ws` clients; further handshakes get `503`.
+//! - `header_read_timeout` — seconds a client has to send complete request
+//!   headers, which also bounds how long a keep-alive connection waits for
+//!   its next request and how long a TLS handshake may take.
+//! - `idle_timeout` — seconds without any data read or written (including
+//!   upgraded WebSocket connections) before a connection is closed.
+//!
+//! Behind a reverse proxy every request comes from the proxy, so the client
+//! address is taken from `X-Forwarded-For` when the peer is one of the
+//! top-level `trusted_proxies` (see `auth::TrustedProxies`). IPv6 clients are keyed
+//! by their /64 network, since one host usually controls the whole prefix.
+//! Unix socket connections have no address and share one bucket per route.
+//! At most 10 000 buckets are kept; full (idle) ones are dropped first, then
+//! the least recently used.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! limits:
+//!   max_connections: 1024
+//!   max_ws_clients: 100
+//!   header_read_timeout: 30   # seconds (default)
+//!   idle_timeout: 300         # seconds (default), 0 = none
+//!   rate_limit:
+//!     requests_per_second: 5
+//!     burst: 20
+//!     routes:
+//!       - path: /status
+//!         requests_per_second: 0.5
+//!         burst: 5
+//! ```
+
+use crate::auth::path_matches;
+use crate::config::ConfigError;
+use crate::handlers::HandlerResponse;
+use hyper::body::Bytes;
+use hyper::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv6Addr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::Sleep;
+
+/// Buckets kept before idle (full) ones are dropped.
+pub(crate) const MAX_BUCKETS: usize = 10_000;
+
+/// Limits section as written in YAML.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LimitsConfig {
+    /// Maximum open HTTP connections (unlimited if unset).
+    #[serde(default)]
+    pub max_connections: Option<usize>,
+    /// Maximum connected WebSocket clients (unlimited if unset).
+    #[serde(default)]
+    pub max_ws_clients: Option<u32>,
+    /// Seconds to receive request headers (0 = no limit).
+    #[serde(default = "default_header_read_timeout")]
+    pub header_read_timeout: u64,
+    /// Seconds without traffic before closing a connection (0 = no limit).
+    #[serde(default = "default_idle_timeout")]
+    pub idle_timeout: u64,
+    /// Per-client request rates.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+}
+
+fn default_header_read_timeout() -> u64 {
+    30
+}
+
+fn default_idle_timeout() -> u64 {
+    300
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: None,
+            max_ws_clients: None,
+            header_read_timeout: default_header_read_timeout(),
+            idle_timeout: default_idle_timeout(),
+            rate_limit: None,
+        }
+    }
+}
+
+/// Rate limit section.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RateLimitConfig {
+    /// Default rate for paths without a route rule (unlimited if unset).
+    #[serde(default)]
+    pub requests_per_second: Option<f64>,
+    /// Default bucket size (default: `requests_per_second`, at least 1).
+    #[serde(default)]
+    pub burst: Option<u32>,
+    /// Per-route rates.
+    #[serde(default)]
+    pub routes: Vec<RouteRateLimit>,
+}
+
+/// Rate for one route.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RouteRateLimit {
+    /// Exact path or prefix (`/icons/*`).
+    pub path: String,
+    pub requests_per_second: f64,
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+/// Token refill rate and bucket size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rate {
+    per_second: f64,
+    burst: f64,
+}
+
+impl Rate {
+    fn new(per_second: f64, burst: Option<u32>) -> Result<Self, String> {
+        if !(per_second.is_finite() && per_second > 0.0) {
+            return Err(format!(
+                "requests_per_second must be greater than 0, got {}",
+                per_second
+            ));
+        }
+        let burst = burst.unwrap_or_else(|| per_second.ceil() as u32).max(1);
+        Ok(Self {
+            per_second,
+            burst: burst as f64,
+        })
+    }
+}
+
+/// Tokens left at a point in time.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn tokens_at(&self, rate: Rate, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        (self.tokens + elapsed * rate.per_second).min(rate.burst)
+    }
+}
+
+/// A request over its rate limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    /// Time until the next token.
+    pub retry_after: Duration,
+}
+
+impl RateLimited {
+    /// `429 Too Many Requests` with `Retry-After` in whole seconds.
+    pub fn response(&self) -> HandlerResponse {
+        let seconds = self.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        (
+            Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header("Retry-After", seconds.to_string()),
+            Bytes::from("Too many requests"),
+        )
+    }
+}
+
+/// `503 Service Unavailable` for requests refused because of a limit.
+pub fn unavailable(message: &'static str) -> HandlerResponse {
+    (
+        Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header("Retry-After", "1"),
+        Bytes::from(message),
+    )
+}
+
+/// Client and route index (`routes.len()` for the default rate).
+type BucketKey = (Option<IpAddr>, usize);
+
+/// Token buckets per client and route.
+#[derive(Debug, Clone)]
+struct RateLimiter {
+    default: Option<Rate>,
+    routes: Vec<(String, Rate)>,
+    buckets: Arc<Mutex<HashMap<BucketKey, Bucket>>>,
+}
+
+impl RateLimiter {
+    fn new(config: RateLimitConfig) -> Result<Self, String> {
+        let default = config
+            .requests_per_second
+            .map(|rps| Rate::new(rps, config.burst))
+            .transpose()?;
+        let routes = config
+            .routes
+            .into_iter()
+            .enumerate()
+            .map(|(i, route)| {
+                if !route.path.starts_with('/') {
+                    return Err(format!(
+                        "routes[{}]: path '{}' must start with '/'",
+                        i, route.path
+                    ));
+                }
+                let rate = Rate::new(route.requests_per_second, route.burst)
+                    .map_err(|e| format!("routes[{}]: {}", i, e))?;
+                Ok((route.path, rate))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if default.is_none() && routes.is_empty() {
+            return Err("set requests_per_second or at least one route".to_string());
+        }
+        Ok(Self {
+            default,
+            routes,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    fn check(&self, path: &str, client: Option<IpAddr>, now: Instant) -> Result<(), RateLimited> {
+        let (index, rate) = match self.routes.iter().position(|(p, _)| path_matches(p, path)) {
+            Some(i) => (i, self.routes[i].1),
+            None => match self.default {
+                Some(rate) => (self.routes.len(), rate),
+                None => return Ok(()),
+            },
+        };
+        let key = (client.map(client_key), index);
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&key) {
+            // Full buckets behave exactly like missing ones
+            let routes = &self.routes;
+            let default = self.default;
+            buckets.retain(|(_, i), bucket| {
+                let rate = routes.get(*i).map(|(_, r)| *r).or(default);
+                rate.is_some_and(|rate| bucket.tokens_at(rate, now) < rate.burst)
+            });
+            if buckets.len() >= MAX_BUCKETS {
+                // Still full of active clients: drop the least recently used tenth
+                let mut updated: Vec<Instant> = buckets.values().map(|b| b.updated).collect();
+                let cutoff = *updated.select_nth_unstable(MAX_BUCKETS / 10).1;
+                buckets.retain(|_, bucket| bucket.updated > cutoff);
+            }
+        }
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: rate.burst,
+            updated: now,
+        });
+        let tokens = bucket.tokens_at(rate, now);
+        if tokens >= 1.0 {
+            *bucket = Bucket {
+                tokens: tokens - 1.0,
+                updated: now,
+            };
+            Ok(())
+        } else {
+            Err(RateLimited {
+                retry_after: Duration::from_secs_f64((1.0 - tokens) / rate.per_second),
+            })
+        }
+    }
+}
+
+/// Bucket key of a client address (IPv6 masked to its /64).
+fn client_key(ip: IpAddr) -> IpAddr {
+    match ip.to_canonical() {
+        IpAddr::V6(v6) => IpAddr::V6(Ipv6Addr::from(u128::from(v6) & !(u128::MAX >> 64))),
+        v4 => v4,
+    }
+}
+
+/// Compiled limits.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Maximum open HTTP connections.
+    pub max_connections: Option<usize>,
+    /// Maximum connected WebSocket clients.
+    pub max_ws_clients: Option<u32>,
+    /// Time to receive request headers.
+    pub header_read_timeout: Option<Duration>,
+    /// Time without traffic before closing a connection.
+    pub idle_timeout: Option<Duration>,
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl Default for Limits {
+    /// No rate or connection limits, default timeouts.
+    fn default() -> Self {
+        Self::new(LimitsConfig::default()).expect("default limits are valid")
+    }
+}
+
+impl Limits {
+    /// Validates the section and compiles it.
+    pub fn new(config: LimitsConfig) -> Result<Self, ConfigError> {
+        let err = |msg: String| ConfigError::InvalidValue(format!("limits: {}", msg));
+
+        if config.max_connections == Some(0) {
+            return Err(err("max_connections must be greater than 0".to_string()));
+        }
+        if config.max_ws_clients == Some(0) {
+            return Err(err(
+                "max_ws_clients must be greater than 0 (use websocket_enabled: false)".to_string(),
+            ));
+        }
+        let rate_limiter = config
+            .rate_limit
+            .map(RateLimiter::new)
+            .transpose()
+            .map_err(|e| err(format!("rate_limit: {}", e)))?;
+        let seconds = |s: u64| (s > 0).then(|| Duration::from_secs(s));
+
+        Ok(Self {
+            max_connections: config.max_connections,
+            max_ws_clients: config.max_ws_clients,
+            header_read_timeout: seconds(config.header_read_timeout),
+            idle_timeout: seconds(config.idle_timeout),
+            rate_limiter,
+        })
+    }
+
+    /// Takes a token for a request from `client` (resolved through the
+    /// trusted proxies), or returns how long to wait.
+    pub fn check_rate(&self, path: &str, client: Option<IpAddr>) -> Result<(), RateLimited> {
+        self.check_rate_at(path, client, Instant::now())
+    }
+
+    /// `check_rate` at a given time.
+    pub fn check_rate_at(
+        &self,
+        path: &str,
+        client: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<(), RateLimited> {
+        match &self.rate_limiter {
+            Some(limiter) => limiter.check(path, client, now),
+            None => Ok(()),
+        }
+    }
+
+    /// Number of rate limit buckets held.
+    #[cfg(test)]
+    pub(crate) fn rate_buckets(&self) -> usize {
+        self.rate_limiter
+            .as_ref()
+            .map_or(0, |l| l.buckets.lock().unwrap().len())
+    }
+}
+
+/// Closes a connection after a period without reads or writes.
+///
+/// The timer is reset whenever data moves in either direction; once it
+/// expires, pending reads and writes fail with `TimedOut`.
+pub struct IdleTimeout<I> {
+    inner: I,
+    /// Timeout and its timer (None never times out).
+    timer: Option<(Duration, Pin<Box<Sleep>>)>,
+}
+
+impl<I> IdleTimeout<I> {
+    /// Wraps a stream (`None` never times out).
+    pub fn new(inner: I, timeout: Option<Duration>) -> Self {
+        Self {
+            inner,
+            timer: timeout.map(|t| (t, Box::pin(tokio::time::sleep(t)))),
+        }
+    }
+
+    fn touch(&mut self) {
+        if let Some((timeout, sleep)) = &mut self.timer {
+            sleep.as_mut().reset(tokio::time::Instant::now() + *timeout);
+        }
+    }
+
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<io::Error> {
+        match &mut self.timer {
+            Some((_, sleep)) => sleep
+                .as_mut()
+                .poll(cx)
+                .map(|()| io::Error::new(io::ErrorKind::TimedOut, "connection idle timeout")),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<I: AsyncRead + Unpin> AsyncRead for IdleTimeout<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        match Pin::new(&mut this.inner).poll_read(cx, buf) {
+            Poll::Ready(Ok(())) => {
+                if buf.filled().len() > before {
+                    this.touch();
+                }
+                Poll::Ready(Ok(()))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => this.poll_expired(cx).map(Err),
+        }
+    }
+}
+
+impl<I: AsyncWrite + Unpin> AsyncWrite for IdleTimeout<I> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.inner).poll_write(cx, buf) {
+            Poll::Ready(Ok(n)) => {
+                if n > 0 {
+                    this.touch();
+                }
+                Poll::Ready(Ok(n))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => this.poll_expired(cx).map(Err),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,334 @@
+//! Tests for rate limits, connection limits and timeouts.
+
+use crate::auth::TrustedProxies;
+use crate::config::Plan;
+use crate::limits::{IdleTimeout, Limits, LimitsConfig, RateLimited, MAX_BUCKETS};
+use crate::state::AppState;
+use crate::test_support::{serve_state, start_test_server};
+use crate::Config;
+use std::io;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn limits(yaml: &str) -> Result<Limits, crate::config::ConfigError> {
+    Limits::new(serde_yaml::from_str::<LimitsConfig>(yaml).unwrap())
+}
+
+fn ip(s: &str) -> Option<IpAddr> {
+    Some(s.parse().unwrap())
+}
+
+/// Starts the real connection handler with the given limits on a random port.
+async fn start_server(yaml: &str) -> (Arc<AppState>, String) {
+    let mut config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
+    config.limits = limits(yaml).unwrap();
+    let state = Arc::new(AppState::new(config));
+    let addr = serve_state(state.clone()).await;
+    (state, addr)
+}
+
+// =============================================================================
+// Config Tests
+// =============================================================================
+
+#[test]
+fn test_validation() {
+    let invalid = [
+        "max_connections: 0",
+        "max_ws_clients: 0",
+        "rate_limit: {}",
+        "rate_limit:\n  requests_per_second: 0",
+        "rate_limit:\n  requests_per_second: -1",
+        "rate_limit:\n  requests_per_second: .inf",
+        "rate_limit:\n  routes:\n    - path: status\n      requests_per_second: 1",
+    ];
+    for yaml in invalid {
+        assert!(limits(yaml).is_err(), "{}", yaml);
+    }
+
+    let defaults = Limits::default();
+    assert_eq!(defaults.max_connections, None);
+    assert_eq!(defaults.header_read_timeout, Some(Duration::from_secs(30)));
+    assert_eq!(defaults.idle_timeout, Some(Duration::from_secs(300)));
+
+    let disabled = limits("header_read_timeout: 0\nidle_timeout: 0").unwrap();
+    assert_eq!(disabled.header_read_timeout, None);
+    assert_eq!(disabled.idle_timeout, None);
+}
+
+// =============================================================================
+// Rate Limit Tests
+// =============================================================================
+
+#[test]
+fn test_token_bucket() {
+    let limits = limits("rate_limit:\n  requests_per_second: 2\n  burst: 3").unwrap();
+    let client = ip("192.0.2.1");
+    let start = Instant::now();
+
+    // Full bucket allows a burst, then one request per 500ms
+    for _ in 0..3 {
+        assert!(limits.check_rate_at("/status", client, start).is_ok());
+    }
+    let limited = limits.check_rate_at("/status", client, start).unwrap_err();
+    assert_eq!(limited.retry_after, Duration::from_millis(500));
+
+    let later = start + Duration::from_millis(500);
+    assert!(limits.check_rate_at("/status", client, later).is_ok());
+    assert!(limits.check_rate_at("/status", client, later).is_err());
+
+    // Other clients have their own bucket
+    assert!(limits
+        .check_rate_at("/status", ip("192.0.2.2"), later)
+        .is_ok());
+}
+
+#[test]
+fn test_route_buckets() {
+    let yaml = r#"
+rate_limit:
+  routes:
+    - path: /status
+      requests_per_second: 1
+    - path: /icons/*
+      requests_per_second: 1
+      burst: 2
+"#;
+    let limits = limits(yaml).unwrap();
+    let client = ip("192.0.2.1");
+    let now = Instant::now();
+
+    assert!(limits.check_rate_at("/status", client, now).is_ok());
+    assert!(limits.check_rate_at("/status", client, now).is_err());
+
+    // Separate bucket per route, shared by paths under a prefix
+    assert!(limits
+        .check_rate_at("/icons/services/a", client, now)
+        .is_ok());
+    assert!(limits
+        .check_rate_at("/icons/services/b", client, now)
+        .is_ok());
+    assert!(limits
+        .check_rate_at("/icons/services/c", client, now)
+        .is_err());
+
+    // No default rate: other paths are unlimited
+    for _ in 0..100 {
+        assert!(limits.check_rate_at("/metrics", client, now).is_ok());
+    }
+}
+
+#[test]
+fn test_ipv6_clients_keyed_by_prefix() {
+    let limits = limits("rate_limit:\n  requests_per_second: 1").unwrap();
+    let now = Instant::now();
+
+    // Addresses within one /64 share a bucket
+    assert!(limits
+        .check_rate_at("/status", ip("2001:db8:1:2::1"), now)
+        .is_ok());
+    assert!(limits
+        .check_rate_at("/status", ip("2001:db8:1:2:ffff::9"), now)
+        .is_err());
+    assert!(limits
+        .check_rate_at("/status", ip("2001:db8:1:3::1"), now)
+        .is_ok());
+
+    // IPv4-mapped addresses count as the IPv4 client
+    assert!(limits
+        .check_rate_at("/status", ip("192.0.2.1"), now)
+        .is_ok());
+    assert!(limits
+        .check_rate_at("/status", ip("::ffff:192.0.2.1"), now)
+        .is_err());
+}
+
+#[test]
+fn test_bucket_cap_evicts_least_recently_used() {
+    let limits = limits("rate_limit:\n  requests_per_second: 1\n  burst: 2").unwrap();
+    let start = Instant::now();
+
+    // Every client keeps a partly drained bucket, so none is idle
+    let client = |i: usize| Some(IpAddr::from([10, (i >> 16) as u8, (i >> 8) as u8, i as u8]));
+    for i in 0..=MAX_BUCKETS {
+        let now = start + Duration::from_micros(i as u64);
+        assert!(limits.check_rate_at("/status", client(i), now).is_ok());
+        assert!(limits.rate_buckets() <= MAX_BUCKETS);
+    }
+
+    // The oldest clients were dropped, recent ones are still tracked
+    let now = start + Duration::from_micros(MAX_BUCKETS as u64 + 1);
+    assert!(limits
+        .check_rate_at("/status", client(MAX_BUCKETS), now)
+        .is_ok());
+    assert!(limits
+        .check_rate_at("/status", client(MAX_BUCKETS), now)
+        .is_err());
+    assert!(limits.check_rate_at("/status", client(0), now).is_ok());
+    assert!(limits.check_rate_at("/status", client(0), now).is_ok());
+}
+
+#[test]
+fn test_rate_limited_response() {
+    let limited = RateLimited {
+        retry_after: Duration::from_millis(1500),
+    };
+    let response = limited.response().0.body(()).unwrap();
+    assert_eq!(response.status(), 429);
+    assert_eq!(response.headers()["Retry-After"], "2");
+
+    let limited = RateLimited {
+        retry_after: Duration::from_millis(10),
+    };
+    let response = limited.response().0.body(()).unwrap();
+    assert_eq!(response.headers()["Retry-After"], "1");
+}
+
+// =============================================================================
+// Idle Timeout Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_idle_timeout() {
+    let (client, server) = tokio::io::duplex(64);
+    let mut server = IdleTimeout::new(server, Some(Duration::from_millis(100)));
+    let mut client = client;
+
+    // Traffic keeps the connection open past the timeout
+    for _ in 0..3 {
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        client.write_all(b"x").await.unwrap();
+        let mut buf = [0u8; 1];
+        server.read_exact(&mut buf).await.unwrap();
+    }
+
+    let started = Instant::now();
+    let mut buf = [0u8; 1];
+    let err = server.read(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    assert!(started.elapsed() >= Duration::from_millis(90));
+}
+
+// =============================================================================
+// Server Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_server_rate_limit() {
+    let (_state, addr) = start_server("rate_limit:\n  requests_per_second: 1\n  burst: 2").await;
+    let client = reqwest::Client::new();
+    let mut statuses = Vec::new();
+    for _ in 0..3 {
+        let response = client
+            .get(format!("http://{}/health", addr))
+            .send()
+            .await
+            .unwrap();
+        statuses.push(response.status().as_u16());
+        if response.status() == 429 {
+            assert_eq!(response.headers()["Retry-After"], "1");
+            // Rejections still carry CORS headers for the dashboard
+            assert!(response
+                .headers()
+                .contains_key("Access-Control-Allow-Origin"));
+        }
+    }
+    assert_eq!(statuses, [200, 200, 429]);
+}
+
+#[tokio::test]
+async fn test_server_rate_limit_behind_proxy() {
+    let mut config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
+    config.limits = limits("rate_limit:\n  requests_per_second: 1").unwrap();
+    config.trusted_proxies = TrustedProxies::new(&["127.0.0.1".to_string()]).unwrap();
+    let addr = start_test_server(config).await;
+    let client = reqwest::Client::new();
+    let status = |forwarded: &'static str| {
+        let request = client
+            .get(format!("http://{}/health", addr))
+            .header("X-Forwarded-For", forwarded);
+        async move { request.send().await.unwrap().status().as_u16() }
+    };
+
+    // Clients behind the same proxy are limited separately
+    assert_eq!(status("203.0.113.1").await, 200);
+    assert_eq!(status("203.0.113.1").await, 429);
+    assert_eq!(status("203.0.113.2").await, 200);
+}
+
+#[tokio::test]
+async fn test_server_max_connections() {
+    let (state, addr) = start_server("max_connections: 1").await;
+
+    // Hold the only slot with an idle keep-alive connection
+    let mut held = tokio::net::TcpStream::connect(&addr).await.unwrap();
+    held.write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let mut buf = [0u8; 12];
+    held.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"HTTP/1.1 200");
+
+    let response = reqwest::get(format!("http://{}/health", addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 503);
+    assert_eq!(response.headers()["Connection"], "close");
+
+    // Slot is released when the connection closes
+    drop(held);
+    for _ in 0..50 {
+        if state.connections.load(std::sync::atomic::Ordering::SeqCst) == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let response = reqwest::get(format!("http://{}/health", addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_server_max_ws_clients() {
+    use tokio_tungstenite::tungstenite::Error;
+
+    let (state, addr) = start_server("max_ws_clients: 1").await;
+    let url = format!("ws://{}/ws", addr);
+
+    let (first, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+    match tokio_tungstenite::connect_async(&url).await {
+        Err(Error::Http(response)) => assert_eq!(response.status(), 503),
+        other => panic!("expected 503, got {:?}", other.map(|(_, r)| r.status())),
+    }
+    assert_eq!(state.ws_client_count(), 1);
+
+    drop(first);
+    for _ in 0..50 {
+        if state.ws_client_count() == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(tokio_tungstenite::connect_async(&url).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_server_header_read_timeout() {
+    let (_state, addr) = start_server("header_read_timeout: 1").await;
+
+    // Incomplete request headers
+    let mut stream = tokio::net::TcpStream::connect(&addr).await.unwrap();
+    stream
+        .write_all(b"GET /health HTTP/1.1\r\nHost: loc")
+        .await
+        .unwrap();
+    let started = Instant::now();
+    let mut rest = Vec::new();
+    let _ = tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut rest))
+        .await
+        .expect("connection closed by the server");
+    assert!(started.elapsed() >= Duration::from_millis(900));
+}
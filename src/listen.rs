@@ -0,0 +1,362 @@
+//! Listening sockets.
+//!
+//! By default the server listens on `0.0.0.0:<port>`. The `listen` list
+//! replaces that with any number of sockets:
+//!
+//! - `IP:PORT` — a specific IPv4 or IPv6 address (`[::1]:9090`); IPv6 sockets
+//!   are IPv6-only, so `0.0.0.0:9090` and `[::]:9090` can be combined
+//! - `IP` — an address on `port`
+//! - `unix:PATH` — a Unix domain socket (a stale socket file is replaced)
+//! - `systemd` — every socket passed by systemd socket activation
+//!   (`LISTEN_FDS` / `LISTEN_PID`)
+//!
+//! Unix sockets have no client address, so `proxy` authentication never
+//! matches connections on them.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! listen:
+//!   - 127.0.0.1:9090
+//!   - "[::1]:9090"
+//!   - unix:/run/railway-exporter/http.sock
+//! ```
+
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::pin::Pin;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+
+/// Pending connection queue length.
+const BACKLOG: i32 = 1024;
+
+/// First file descriptor passed by systemd (`SD_LISTEN_FDS_START`).
+#[cfg(unix)]
+const SD_LISTEN_FDS_START: i32 = 3;
+
+/// Set once the systemd sockets have been wrapped in listeners.
+#[cfg(unix)]
+static SYSTEMD_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// A configured listen address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    /// TCP on a specific address.
+    Tcp(SocketAddr),
+    /// Unix domain socket at a path.
+    Unix(PathBuf),
+    /// Sockets inherited from systemd.
+    Systemd,
+}
+
+impl ListenAddr {
+    /// The default `0.0.0.0:<port>`.
+    pub fn any(port: u16) -> Self {
+        ListenAddr::Tcp(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))
+    }
+
+    /// Parses a `listen` entry; bare IP addresses use `port`.
+    pub fn parse(s: &str, port: u16) -> Result<Self, String> {
+        let s = s.trim();
+        if s == "systemd" {
+            return Ok(ListenAddr::Systemd);
+        }
+        if let Some(path) = s.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err("unix: needs a socket path".to_string());
+            }
+            return Ok(ListenAddr::Unix(PathBuf::from(path)));
+        }
+        if let Ok(addr) = s.parse::<SocketAddr>() {
+            return Ok(ListenAddr::Tcp(addr));
+        }
+        let ip = s.trim_start_matches('[').trim_end_matches(']');
+        ip.parse::<IpAddr>()
+            .map(|ip| ListenAddr::Tcp(SocketAddr::new(ip, port)))
+            .map_err(|_| {
+                format!(
+                    "'{}' is not an address (IP:PORT, IP, unix:PATH or systemd)",
+                    s
+                )
+            })
+    }
+
+    /// Parses every `listen` entry, rejecting duplicates: binding an address
+    /// twice fails, and a second `systemd` would take the inherited sockets twice.
+    pub fn parse_all(entries: &[String], port: u16) -> Result<Vec<Self>, String> {
+        let mut addrs = Vec::new();
+        for entry in entries {
+            let addr = Self::parse(entry, port)?;
+            if addrs.contains(&addr) {
+                return Err(format!("'{}' is listed twice", entry));
+            }
+            addrs.push(addr);
+        }
+        Ok(addrs)
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddr::Tcp(addr) => write!(f, "{}", addr),
+            ListenAddr::Unix(path) => write!(f, "unix:{}", path.display()),
+            ListenAddr::Systemd => write!(f, "systemd"),
+        }
+    }
+}
+
+/// A bound listening socket.
+#[derive(Debug)]
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    /// Accepts a connection, with the client address for TCP.
+    pub async fn accept(&self) -> io::Result<(Stream, Option<IpAddr>)> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, peer) = listener.accept().await?;
+                Ok((Stream::Tcp(stream), Some(peer.ip())))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok((Stream::Unix(stream), None))
+            }
+        }
+    }
+
+    /// Address actually bound (resolves port 0).
+    pub fn local_addr(&self) -> io::Result<ListenAddr> {
+        match self {
+            Listener::Tcp(listener) => listener.local_addr().map(ListenAddr::Tcp),
+            #[cfg(unix)]
+            Listener::Unix(listener) => Ok(ListenAddr::Unix(
+                listener
+                    .local_addr()?
+                    .as_pathname()
+                    .map(PathBuf::from)
+                    .unwrap_or_default(),
+            )),
+        }
+    }
+}
+
+/// An accepted connection.
+#[derive(Debug)]
+pub enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Tcp(s) => Pin::new(s).poll_read(cx, buf),
+            #[cfg(unix)]
+            Stream::Unix(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Stream::Tcp(s) => Pin::new(s).poll_write(cx, buf),
+            #[cfg(unix)]
+            Stream::Unix(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Tcp(s) => Pin::new(s).poll_flush(cx),
+            #[cfg(unix)]
+            Stream::Unix(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Tcp(s) => Pin::new(s).poll_shutdown(cx),
+            #[cfg(unix)]
+            Stream::Unix(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Binds every configured address.
+pub fn bind(addrs: &[ListenAddr]) -> io::Result<Vec<Listener>> {
+    let mut listeners = Vec::new();
+    for addr in addrs {
+        let context = |e: io::Error| io::Error::new(e.kind(), format!("{}: {}", addr, e));
+        match addr {
+            ListenAddr::Tcp(addr) => listeners.push(bind_tcp(*addr).map_err(context)?),
+            #[cfg(unix)]
+            ListenAddr::Unix(path) => listeners.push(bind_unix(path).map_err(context)?),
+            #[cfg(unix)]
+            ListenAddr::Systemd => listeners.extend(systemd_listeners().map_err(context)?),
+            #[cfg(not(unix))]
+            _ => {
+                return Err(context(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "only TCP addresses are supported on this platform",
+                )))
+            }
+        }
+    }
+    Ok(listeners)
+}
+
+fn bind_tcp(addr: SocketAddr) -> io::Result<Listener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_reuse_address(true)?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.bind(&SockAddr::from(addr))?;
+    socket.listen(BACKLOG)?;
+    socket.set_nonblocking(true)?;
+    TcpListener::from_std(socket.into()).map(Listener::Tcp)
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &std::path::Path) -> io::Result<Listener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // Replace a socket left behind by a previous run, but not a live one
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "path exists and is not a socket",
+            ));
+        }
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                "another process is listening on this socket",
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    UnixListener::bind(path).map(Listener::Unix)
+}
+
+/// Takes the listening sockets passed by systemd.
+#[cfg(unix)]
+fn systemd_listeners() -> io::Result<Vec<Listener>> {
+    use std::os::unix::io::FromRawFd;
+
+    let count = systemd_fd_count(
+        std::env::var("LISTEN_PID").ok().as_deref(),
+        std::env::var("LISTEN_FDS").ok().as_deref(),
+    )
+    .ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "no sockets passed (LISTEN_FDS/LISTEN_PID not set for this process)",
+        )
+    })?;
+
+    // Each descriptor must have a single owner, or it is closed twice
+    if SYSTEMD_TAKEN.swap(true, Ordering::SeqCst) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "sockets already taken by an earlier systemd entry",
+        ));
+    }
+
+    (SD_LISTEN_FDS_START..SD_LISTEN_FDS_START + count)
+        .map(|fd| {
+            // SAFETY: systemd hands these descriptors to this process, and
+            // `SYSTEMD_TAKEN` ensures they are only taken once.
+            let socket = unsafe { Socket::from_raw_fd(fd) };
+            socket.set_nonblocking(true)?;
+            if socket.local_addr()?.is_unix() {
+                UnixListener::from_std(socket.into()).map(Listener::Unix)
+            } else {
+                TcpListener::from_std(socket.into()).map(Listener::Tcp)
+            }
+        })
+        .collect()
+}
+
+/// Number of sockets passed to this process by systemd, if any.
+#[cfg(unix)]
+pub(crate) fn systemd_fd_count(listen_pid: Option<&str>, listen_fds: Option<&str>) -> Option<i32> {
+    let pid: u32 = listen_pid?.trim().parse().ok()?;
+    if pid != std::process::id() {
+        return None;
+    }
+    listen_fds?.trim().parse().ok().filter(|n| *n > 0)
+}
+
+/// Returns true for errors that only affect the connection being accepted.
+pub fn is_connection_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+    )
+}
+
+/// Exponential delay between failing `accept` calls (e.g. `EMFILE`).
+///
+/// Retrying immediately would spin on a full descriptor table; waiting lets
+/// open connections finish and release descriptors.
+#[derive(Debug, Clone)]
+pub struct AcceptBackoff {
+    next: Duration,
+}
+
+impl AcceptBackoff {
+    const MIN: Duration = Duration::from_millis(5);
+    const MAX: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        Self { next: Self::MIN }
+    }
+
+    /// Returns the delay before the next attempt and doubles it (up to 1s).
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(Self::MAX);
+        delay
+    }
+
+    /// Resets after a successful accept.
+    pub fn reset(&mut self) {
+        self.next = Self::MIN;
+    }
+}
+
+impl Default for AcceptBackoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,216 @@
+//! Tests for listening sockets.
+
+use crate::config::Plan;
+use crate::listen::{self, AcceptBackoff, ListenAddr, Listener};
+use crate::server::accept_loop;
+use crate::state::AppState;
+use crate::Config;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn state() -> Arc<AppState> {
+    let config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
+    Arc::new(AppState::new(config))
+}
+
+/// Binds a single address and serves it in the background.
+fn serve(addr: ListenAddr) -> ListenAddr {
+    let listener = listen::bind(&[addr]).unwrap().pop().unwrap();
+    let bound = listener.local_addr().unwrap();
+    tokio::spawn(accept_loop(listener, None, state()));
+    bound
+}
+
+fn socket_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("exporter-{}-{}.sock", name, std::process::id()))
+}
+
+const HEALTH_REQUEST: &[u8] =
+    b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+
+// =============================================================================
+// Parsing Tests
+// =============================================================================
+
+#[test]
+fn test_parse() {
+    let cases = [
+        ("127.0.0.1:8080", "127.0.0.1:8080"),
+        ("[::1]:8080", "[::1]:8080"),
+        // Bare addresses use the configured port
+        ("10.0.0.1", "10.0.0.1:9090"),
+        ("::", "[::]:9090"),
+        ("[::1]", "[::1]:9090"),
+        ("unix:/run/exporter.sock", "unix:/run/exporter.sock"),
+        ("systemd", "systemd"),
+    ];
+    for (input, expected) in cases {
+        let addr = ListenAddr::parse(input, 9090).unwrap();
+        assert_eq!(addr.to_string(), expected, "{}", input);
+    }
+    assert_eq!(ListenAddr::any(9090).to_string(), "0.0.0.0:9090");
+
+    for invalid in ["localhost:9090", "unix:", "127.0.0.1:99999", ""] {
+        assert!(ListenAddr::parse(invalid, 9090).is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_parse_all_rejects_duplicates() {
+    let entries = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let addrs = ListenAddr::parse_all(&entries(&["127.0.0.1", "systemd"]), 9090).unwrap();
+    assert_eq!(addrs.len(), 2);
+
+    for duplicate in [
+        &["systemd", " systemd"][..],
+        &["127.0.0.1:9090", "127.0.0.1"],
+        &["unix:/run/a.sock", "unix:/run/a.sock"],
+    ] {
+        let err = ListenAddr::parse_all(&entries(duplicate), 9090).unwrap_err();
+        assert!(err.contains("listed twice"), "{:?}", duplicate);
+    }
+}
+
+#[test]
+fn test_systemd_fd_count() {
+    use crate::listen::systemd_fd_count;
+
+    let pid = std::process::id().to_string();
+    assert_eq!(systemd_fd_count(Some(&pid), Some("2")), Some(2));
+    // Variables meant for another process (e.g. our parent)
+    assert_eq!(systemd_fd_count(Some("1"), Some("2")), None);
+    assert_eq!(systemd_fd_count(Some(&pid), Some("0")), None);
+    assert_eq!(systemd_fd_count(None, Some("2")), None);
+    assert_eq!(systemd_fd_count(Some(&pid), None), None);
+}
+
+#[test]
+fn test_accept_backoff() {
+    let mut backoff = AcceptBackoff::new();
+    let delays: Vec<_> = (0..10).map(|_| backoff.next_delay()).collect();
+    assert_eq!(delays[0], Duration::from_millis(5));
+    assert_eq!(delays[1], Duration::from_millis(10));
+    assert_eq!(delays[9], Duration::from_secs(1));
+    assert!(delays.windows(2).all(|w| w[0] <= w[1]));
+
+    backoff.reset();
+    assert_eq!(backoff.next_delay(), Duration::from_millis(5));
+}
+
+#[test]
+fn test_is_connection_error() {
+    assert!(listen::is_connection_error(&io::Error::from(
+        io::ErrorKind::ConnectionAborted
+    )));
+    // EMFILE
+    assert!(!listen::is_connection_error(&io::Error::from_raw_os_error(
+        24
+    )));
+}
+
+// =============================================================================
+// TCP Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_tcp_listener() {
+    let ListenAddr::Tcp(addr) = serve(ListenAddr::parse("127.0.0.1:0", 0).unwrap()) else {
+        panic!("expected TCP listener");
+    };
+    let response = reqwest::get(format!("http://{}/health", addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_ipv4_and_ipv6_on_same_port() {
+    // Pick a free port, then bind both families to it
+    let probe = listen::bind(&[ListenAddr::parse("0.0.0.0:0", 0).unwrap()]).unwrap();
+    let ListenAddr::Tcp(probe_addr) = probe[0].local_addr().unwrap() else {
+        unreachable!()
+    };
+    drop(probe);
+
+    let port = probe_addr.port();
+    let addrs = [
+        ListenAddr::Tcp(SocketAddr::from(([0, 0, 0, 0], port))),
+        ListenAddr::Tcp(SocketAddr::from(([0u16; 8], port))),
+    ];
+    let listeners = listen::bind(&addrs).expect("IPv6 socket is IPv6-only");
+    assert_eq!(listeners.len(), 2);
+    for listener in listeners {
+        tokio::spawn(accept_loop(listener, None, state()));
+    }
+
+    for host in ["127.0.0.1", "[::1]"] {
+        let response = reqwest::get(format!("http://{}:{}/health", host, port))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200, "{}", host);
+    }
+}
+
+#[tokio::test]
+async fn test_bind_error_names_address() {
+    let listener = listen::bind(&[ListenAddr::parse("127.0.0.1:0", 0).unwrap()]).unwrap();
+    let taken = listener[0].local_addr().unwrap();
+
+    let err = listen::bind(std::slice::from_ref(&taken)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    assert!(err.to_string().starts_with(&taken.to_string()));
+}
+
+// =============================================================================
+// Unix Socket Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_unix_listener() {
+    let path = socket_path("serve");
+    let bound = serve(ListenAddr::Unix(path.clone()));
+    assert_eq!(bound, ListenAddr::Unix(path.clone()));
+
+    let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+    stream.write_all(HEALTH_REQUEST).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_unix_socket_replacement() {
+    let path = socket_path("stale");
+    let addr = ListenAddr::Unix(path.clone());
+
+    // Live socket is not taken over
+    let live = listen::bind(std::slice::from_ref(&addr)).unwrap();
+    let err = listen::bind(std::slice::from_ref(&addr)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+    // Socket file left behind after the listener is gone is replaced
+    drop(live);
+    assert!(path.exists());
+    let listeners: Vec<Listener> = listen::bind(std::slice::from_ref(&addr)).unwrap();
+    assert_eq!(listeners.len(), 1);
+    drop(listeners);
+    std::fs::remove_file(&path).unwrap();
+
+    // Regular files are never removed
+    std::fs::write(&path, "data").unwrap();
+    assert!(listen::bind(&[addr]).is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "data");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_systemd_without_sockets() {
+    // The test runner is not socket-activated
+    assert!(listen::bind(&[ListenAddr::Systemd]).is_err());
+}
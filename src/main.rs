@@ -3,9 +3,14 @@
 //! Endpoints:
 //! - GET /metrics - Prometheus format
 //! - GET /metrics.json - JSON format
+//! - GET /metrics/influx - InfluxDB line protocol
 //! - GET /status - Server status
 //! - GET /health - Health check
+//! - GET /chargeback - Cost center report (JSON/CSV)
 //! - GET /ws - WebSocket (real-time updates)
+//!
+//! Run with `--once` to collect a single snapshot, push it to the configured
+//! `sinks:` and exit (for cron jobs feeding a Pushgateway or StatsD).
 
 // === Memory Allocator Configuration ===
 //
@@ -40,6 +45,7 @@ fn configure_allocator() {
 }
 
 use railway_exporter::client::Client;
+use railway_exporter::sinks::Sinks;
 use railway_exporter::{collector, server, AppState, Config};
 use std::sync::Arc;
 use tokio::time::{interval, Duration};
@@ -66,12 +72,19 @@ async fn main() {
     info!("API URL: {}", config.api_url);
     info!("Scrape interval: {}s", config.scrape_interval);
     info!(
-        "Service groups: {:?}",
-        config.service_groups.keys().collect::<Vec<_>>()
+        "Service groups: {:?} (multi-group: {})",
+        config.service_groups.group_names(),
+        config.service_groups.multi_group()
     );
+    let compression = &config.compression;
     info!(
-        "Gzip: enabled={}, min_size={}, level={}",
-        config.gzip.enabled, config.gzip.min_size, config.gzip.level
+        "Compression: gzip={} (level {}), brotli={} (level {}), zstd={} (level {})",
+        compression.gzip.enabled,
+        compression.gzip.level,
+        compression.brotli.enabled,
+        compression.brotli.level,
+        compression.zstd.enabled,
+        compression.zstd.level
     );
     info!(
         "Icon cache: enabled={}, max_count={}",
@@ -81,13 +94,28 @@ async fn main() {
     // Create application state
     let state = Arc::new(AppState::new(config.clone()));
 
+    // Push sinks, fed after every collection
+    let sinks = Sinks::spawn(&config.sinks);
+
     // Create Railway API client
     let client = Client::new(&config.api_token, Some(&config.api_url));
 
     // Initial collection
-    if let Err(e) = collector::collect_metrics(&client, &state).await {
+    let initial = collector::collect_metrics(&client, &state).await;
+    if let Err(ref e) = initial {
         error!("Initial collection failed: {}", e);
     }
+    sinks.push(&state).await;
+
+    // --once: collect, push to sinks and exit (cron-style runs)
+    if std::env::args().any(|arg| arg == "--once") {
+        if sinks.is_empty() {
+            error!("--once needs at least one entry under `sinks:`");
+            std::process::exit(2);
+        }
+        sinks.close().await;
+        std::process::exit(if initial.is_ok() { 0 } else { 1 });
+    }
 
     // Background collection loop
     let state_bg = state.clone();
@@ -102,6 +130,7 @@ async fn main() {
         loop {
             ticker.tick().await;
             let _ = collector::collect_metrics(&client, &state_bg).await;
+            sinks.push(&state_bg).await;
         }
     });
 
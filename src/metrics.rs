@@ -1,6 +1,19 @@
 //! Prometheus metrics definitions and management.
 //!
 //! This module provides the metrics registry for Railway usage data.
+//! Output formats (Prometheus text, OpenMetrics) live in `crate::exposition`.
+//!
+//! Names below use the default `railway` prefix. The `metrics:` config section
+//! changes the prefix and adds constant labels to every family, so several
+//! exporters can feed one Prometheus without relabeling:
+//!
+//! ```yaml
+//! metrics:
+//!   prefix: acme_railway      # default: railway ("" = no prefix)
+//!   const_labels:
+//!     env: prod
+//!     account: acme
+//! ```
 //!
 //! ## Metrics Exposed
 //!
@@ -15,6 +28,30 @@
 //! | `railway_service_cost_usd` | Current cost in USD |
 //! | `railway_service_estimated_monthly_usd` | Estimated monthly cost |
 //!
+//! ### Per-Group Metrics (labels: group, project)
+//!
+//! A service in several groups (`service_groups_multi`) counts towards each of them.
+//!
+//! | Metric | Description |
+//! |--------|-------------|
+//! | `railway_group_cpu_usage_vcpu_minutes` | CPU usage in vCPU-minutes |
+//! | `railway_group_memory_usage_gb_minutes` | Memory usage in GB-minutes |
+//! | `railway_group_disk_usage_gb_minutes` | Disk usage in GB-minutes |
+//! | `railway_group_network_tx_gb` | Network egress in GB |
+//! | `railway_group_cost_usd` | Current cost in USD |
+//! | `railway_group_estimated_monthly_usd` | Estimated monthly cost |
+//! | `railway_group_services` | Number of services in the group |
+//! | `railway_group_allocated_cost_usd` | Cost after shared-cost redistribution |
+//! | `railway_group_allocated_estimated_monthly_usd` | Estimated monthly cost after redistribution |
+//!
+//! ### Per-Cost-Center Metrics (labels: dimension, cost_center, project)
+//!
+//! | Metric | Description |
+//! |--------|-------------|
+//! | `railway_cost_center_cost_usd` | Current billing period cost |
+//! | `railway_cost_center_estimated_monthly_usd` | Estimated monthly cost |
+//! | `railway_cost_center_closed_cost_usd` | Frozen cost of the last closed period (extra label: period) |
+//!
 //! ### Per-Project Metrics (labels: project)
 //!
 //! | Metric | Description |
@@ -34,10 +71,132 @@
 //! |--------|-------------|
 //! | `railway_exporter_memory_bytes` | Exporter memory usage |
 //! | `railway_exporter_cpu_percent` | Exporter CPU usage |
+//!
+//! ### Exporter Self-Observability
+//!
+//! | Metric | Type | Labels | Description |
+//! |--------|------|--------|-------------|
+//! | `railway_exporter_build_info` | gauge | version, git_sha | Always 1 |
+//! | `railway_exporter_http_requests_total` | counter | route, status, encoding | HTTP requests served (`encoding`: br, zstd, gzip or identity) |
+//! | `railway_exporter_http_request_duration_seconds` | histogram | route | HTTP request latency |
+//! | `railway_exporter_ws_connections_total` | counter | — | WebSocket clients connected |
+//! | `railway_exporter_ws_disconnections_total` | counter | — | WebSocket clients disconnected |
+//! | `railway_exporter_ws_messages_total` | counter | direction | WebSocket messages sent/received |
+//! | `railway_exporter_scrapes_total` | counter | result | Railway API collections (success/failure) |
+//! | `railway_exporter_collection_duration_seconds` | histogram | — | Railway API collection duration |
 
-use prometheus::{Encoder, GaugeVec, Opts, Registry, TextEncoder};
+use crate::config::ConfigError;
+use crate::exposition::{self, ExpositionFormat};
+use crate::relabel::{RelabelRule, Relabeler};
+use prometheus::proto::MetricFamily;
+use prometheus::{
+    Encoder, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+    IntGaugeVec, Opts, ProtobufEncoder, Registry, TextEncoder,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
 use sysinfo::System;
 
+/// Label names set by the exporter itself (can't be used as constant labels).
+pub(crate) const RESERVED_LABELS: &[&str] = &[
+    "service",
+    "project",
+    "icon",
+    "group",
+    "dimension",
+    "cost_center",
+    "period",
+    "route",
+    "status",
+    "encoding",
+    "direction",
+    "result",
+    "version",
+    "git_sha",
+    "le",
+    "quantile",
+];
+
+/// Collection duration buckets (seconds); API calls take from ~1s to a minute.
+const COLLECTION_BUCKETS: &[f64] = &[0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// `metrics:` section: metric name prefix, constant labels and relabeling.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MetricsConfig {
+    /// Prefix of every metric name (joined with `_`; empty = no prefix).
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// Labels added to every metric family.
+    #[serde(default)]
+    pub const_labels: BTreeMap<String, String>,
+    /// Label drop/rename/replace rules (see `crate::relabel`).
+    #[serde(default)]
+    pub relabel: Vec<RelabelRule>,
+    /// Maximum number of per-service series (the rest goes to `other`).
+    #[serde(default)]
+    pub max_series: Option<usize>,
+}
+
+fn default_prefix() -> String {
+    "railway".to_string()
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            prefix: default_prefix(),
+            const_labels: BTreeMap::new(),
+            relabel: Vec::new(),
+            max_series: None,
+        }
+    }
+}
+
+impl MetricsConfig {
+    /// Checks prefix, label names and relabeling rules.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let relabel = self.relabeler()?;
+        if !self.prefix.is_empty() && !is_valid_name(&self.prefix, true) {
+            return Err(ConfigError::InvalidValue(format!(
+                "metrics.prefix '{}' is not a valid metric name prefix",
+                self.prefix
+            )));
+        }
+        for name in self.const_labels.keys() {
+            if !is_valid_name(name, false) || name.starts_with("__") {
+                return Err(ConfigError::InvalidValue(format!(
+                    "metrics.const_labels: '{}' is not a valid label name",
+                    name
+                )));
+            }
+            if RESERVED_LABELS.contains(&name.as_str())
+                || relabel.target_labels().any(|t| t == name)
+            {
+                return Err(ConfigError::InvalidValue(format!(
+                    "metrics.const_labels: '{}' is already set by the exporter",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compiles the relabeling rules.
+    pub fn relabeler(&self) -> Result<Relabeler, ConfigError> {
+        Relabeler::new(&self.relabel, self.max_series)
+    }
+}
+
+/// Prometheus name rules: `[a-zA-Z_][a-zA-Z0-9_]*`, plus `:` in metric names.
+pub(crate) fn is_valid_name(name: &str, allow_colon: bool) -> bool {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || (allow_colon && c == ':');
+    match name.chars().next() {
+        Some(first) if !first.is_ascii_digit() => name.chars().all(valid),
+        _ => false,
+    }
+}
+
 /// Prometheus metrics registry for Railway data.
 pub struct Metrics {
     // Per-service metrics (labels: service, project, icon, group)
@@ -54,6 +213,34 @@ pub struct Metrics {
     /// Estimated monthly cost in USD per service.
     pub service_estimated_monthly: GaugeVec,
 
+    // Per-group metrics (labels: group, project)
+    /// CPU usage in vCPU-minutes per group.
+    pub group_cpu_usage: GaugeVec,
+    /// Memory usage in GB-minutes per group.
+    pub group_memory_usage: GaugeVec,
+    /// Disk usage in GB-minutes per group.
+    pub group_disk_usage: GaugeVec,
+    /// Network egress in GB per group.
+    pub group_network_tx: GaugeVec,
+    /// Current cost in USD per group.
+    pub group_cost: GaugeVec,
+    /// Estimated monthly cost in USD per group.
+    pub group_estimated_monthly: GaugeVec,
+    /// Number of services per group.
+    pub group_services: GaugeVec,
+    /// Cost in USD per group after shared-cost redistribution.
+    pub group_allocated_cost: GaugeVec,
+    /// Estimated monthly cost in USD per group after redistribution.
+    pub group_allocated_estimated_monthly: GaugeVec,
+
+    // Per-cost-center metrics (labels: dimension, cost_center, project)
+    /// Current billing period cost in USD per cost center.
+    pub cost_center_cost: GaugeVec,
+    /// Estimated monthly cost in USD per cost center.
+    pub cost_center_estimated_monthly: GaugeVec,
+    /// Frozen cost of the last closed billing period (extra label: period).
+    pub cost_center_closed_cost: GaugeVec,
+
     // Per-project metrics (labels: project)
     /// Total current usage in USD per project.
     pub current_usage: GaugeVec,
@@ -78,8 +265,34 @@ pub struct Metrics {
     /// CPU usage percentage of exporter process.
     pub exporter_cpu_percent: GaugeVec,
 
-    /// The Prometheus registry holding all metrics.
+    // Exporter self-observability
+    /// Build version and git commit (value is always 1).
+    pub build_info: IntGaugeVec,
+    /// HTTP requests served (labels: route, status, encoding).
+    pub http_requests: IntCounterVec,
+    /// HTTP request latency (labels: route).
+    pub http_request_duration: HistogramVec,
+    /// WebSocket clients connected.
+    pub ws_connections: IntCounter,
+    /// WebSocket clients disconnected.
+    pub ws_disconnections: IntCounter,
+    /// WebSocket messages (labels: direction = sent|received).
+    pub ws_messages: IntCounterVec,
+    /// Railway API collections (labels: result = success|failure).
+    pub scrapes: IntCounterVec,
+    /// Railway API collection duration.
+    pub collection_duration: Histogram,
+
+    /// The Prometheus registry holding the Railway metrics (they only change
+    /// once per collection).
     pub registry: Registry,
+    /// Exporter process and self-observability metrics (change on every
+    /// request, so `/metrics` gathers them live).
+    pub self_registry: Registry,
+    /// Label drop/rename/replace rules and series limit.
+    pub relabel: Relabeler,
+    /// Unix time (seconds) the registry was created, exported as OpenMetrics `_created`.
+    pub created: f64,
 }
 
 impl Default for Metrics {
@@ -91,99 +304,214 @@ impl Default for Metrics {
 impl Metrics {
     /// Creates a new metrics registry with all Railway metrics registered.
     pub fn new() -> Self {
+        Self::with_config(&MetricsConfig::default())
+    }
+
+    /// Creates a registry with a custom name prefix, constant labels and
+    /// relabeling rules.
+    ///
+    /// The config must have passed `MetricsConfig::validate`.
+    pub fn with_config(config: &MetricsConfig) -> Self {
         let registry = Registry::new();
+        let self_registry = Registry::new();
+        let relabel = config.relabeler().unwrap();
+        let const_labels: HashMap<String, String> = config
+            .const_labels
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let opts = |name: &str, help: &str| {
+            Opts::new(name, help)
+                .namespace(config.prefix.clone())
+                .const_labels(const_labels.clone())
+        };
 
         // Label sets
         let service_labels = &["service", "project", "icon", "group"];
+        let group_labels = &["group", "project"];
+        let cost_center_labels = &["dimension", "cost_center", "project"];
+        let cost_center_period_labels = &["dimension", "cost_center", "project", "period"];
         let project_labels = &["project"];
         let no_labels: &[&str] = &[];
 
         // Per-service metrics
         let cpu_usage = GaugeVec::new(
-            Opts::new(
-                "railway_cpu_usage_vcpu_minutes",
-                "CPU usage in vCPU-minutes",
-            ),
+            opts("cpu_usage_vcpu_minutes", "CPU usage in vCPU-minutes"),
             service_labels,
         )
         .unwrap();
 
         let memory_usage = GaugeVec::new(
-            Opts::new(
-                "railway_memory_usage_gb_minutes",
-                "Memory usage in GB-minutes",
-            ),
+            opts("memory_usage_gb_minutes", "Memory usage in GB-minutes"),
             service_labels,
         )
         .unwrap();
 
         let disk_usage = GaugeVec::new(
-            Opts::new("railway_disk_usage_gb_minutes", "Disk usage in GB-minutes"),
+            opts("disk_usage_gb_minutes", "Disk usage in GB-minutes"),
             service_labels,
         )
         .unwrap();
 
         let network_tx = GaugeVec::new(
-            Opts::new("railway_network_tx_gb", "Network egress in GB"),
+            opts("network_tx_gb", "Network egress in GB"),
             service_labels,
         )
         .unwrap();
 
         let service_cost = GaugeVec::new(
-            Opts::new("railway_service_cost_usd", "Current service cost in USD"),
+            opts("service_cost_usd", "Current service cost in USD"),
             service_labels,
         )
         .unwrap();
 
         let service_estimated_monthly = GaugeVec::new(
-            Opts::new(
-                "railway_service_estimated_monthly_usd",
+            opts(
+                "service_estimated_monthly_usd",
                 "Estimated monthly service cost in USD",
             ),
             service_labels,
         )
         .unwrap();
 
+        // Per-group metrics
+        let group_cpu_usage = GaugeVec::new(
+            opts(
+                "group_cpu_usage_vcpu_minutes",
+                "CPU usage in vCPU-minutes per service group",
+            ),
+            group_labels,
+        )
+        .unwrap();
+
+        let group_memory_usage = GaugeVec::new(
+            opts(
+                "group_memory_usage_gb_minutes",
+                "Memory usage in GB-minutes per service group",
+            ),
+            group_labels,
+        )
+        .unwrap();
+
+        let group_disk_usage = GaugeVec::new(
+            opts(
+                "group_disk_usage_gb_minutes",
+                "Disk usage in GB-minutes per service group",
+            ),
+            group_labels,
+        )
+        .unwrap();
+
+        let group_network_tx = GaugeVec::new(
+            opts(
+                "group_network_tx_gb",
+                "Network egress in GB per service group",
+            ),
+            group_labels,
+        )
+        .unwrap();
+
+        let group_cost = GaugeVec::new(
+            opts("group_cost_usd", "Current service group cost in USD"),
+            group_labels,
+        )
+        .unwrap();
+
+        let group_estimated_monthly = GaugeVec::new(
+            opts(
+                "group_estimated_monthly_usd",
+                "Estimated monthly service group cost in USD",
+            ),
+            group_labels,
+        )
+        .unwrap();
+
+        let group_services = GaugeVec::new(
+            opts("group_services", "Number of services in the group"),
+            group_labels,
+        )
+        .unwrap();
+
+        let group_allocated_cost = GaugeVec::new(
+            opts(
+                "group_allocated_cost_usd",
+                "Service group cost in USD after shared-cost redistribution",
+            ),
+            group_labels,
+        )
+        .unwrap();
+
+        let group_allocated_estimated_monthly = GaugeVec::new(
+            opts(
+                "group_allocated_estimated_monthly_usd",
+                "Estimated monthly service group cost in USD after shared-cost redistribution",
+            ),
+            group_labels,
+        )
+        .unwrap();
+
+        // Per-cost-center metrics
+        let cost_center_cost = GaugeVec::new(
+            opts(
+                "cost_center_cost_usd",
+                "Current billing period cost in USD per cost center",
+            ),
+            cost_center_labels,
+        )
+        .unwrap();
+
+        let cost_center_estimated_monthly = GaugeVec::new(
+            opts(
+                "cost_center_estimated_monthly_usd",
+                "Estimated monthly cost in USD per cost center",
+            ),
+            cost_center_labels,
+        )
+        .unwrap();
+
+        let cost_center_closed_cost = GaugeVec::new(
+            opts(
+                "cost_center_closed_cost_usd",
+                "Frozen cost in USD per cost center for the last closed billing period",
+            ),
+            cost_center_period_labels,
+        )
+        .unwrap();
+
         // Per-project metrics
         let current_usage = GaugeVec::new(
-            Opts::new("railway_current_usage_usd", "Total current usage in USD"),
+            opts("current_usage_usd", "Total current usage in USD"),
             project_labels,
         )
         .unwrap();
 
         let estimated_monthly = GaugeVec::new(
-            Opts::new(
-                "railway_estimated_monthly_usd",
-                "Estimated monthly total in USD",
-            ),
+            opts("estimated_monthly_usd", "Estimated monthly total in USD"),
             project_labels,
         )
         .unwrap();
 
         let daily_average = GaugeVec::new(
-            Opts::new("railway_daily_average_usd", "Average daily spending in USD"),
+            opts("daily_average_usd", "Average daily spending in USD"),
             project_labels,
         )
         .unwrap();
 
         let days_in_billing_period = GaugeVec::new(
-            Opts::new(
-                "railway_days_in_billing_period",
-                "Days elapsed in billing period",
-            ),
+            opts("days_in_billing_period", "Days elapsed in billing period"),
             project_labels,
         )
         .unwrap();
 
         let days_remaining_in_month = GaugeVec::new(
-            Opts::new("railway_days_remaining_in_month", "Days remaining in month"),
+            opts("days_remaining_in_month", "Days remaining in month"),
             project_labels,
         )
         .unwrap();
 
         let last_scrape_timestamp = GaugeVec::new(
-            Opts::new(
-                "railway_exporter_last_scrape_timestamp",
+            opts(
+                "exporter_last_scrape_timestamp",
                 "Unix timestamp of last successful scrape",
             ),
             project_labels,
@@ -191,8 +519,8 @@ impl Metrics {
         .unwrap();
 
         let scrape_duration_seconds = GaugeVec::new(
-            Opts::new(
-                "railway_exporter_scrape_duration_seconds",
+            opts(
+                "exporter_scrape_duration_seconds",
                 "Duration of API scrape in seconds",
             ),
             project_labels,
@@ -200,18 +528,15 @@ impl Metrics {
         .unwrap();
 
         let api_up = GaugeVec::new(
-            Opts::new(
-                "railway_api_up",
-                "Whether Railway API is reachable (1=up, 0=down)",
-            ),
+            opts("api_up", "Whether Railway API is reachable (1=up, 0=down)"),
             project_labels,
         )
         .unwrap();
 
         // Exporter process metrics
         let exporter_memory_bytes = GaugeVec::new(
-            Opts::new(
-                "railway_exporter_memory_bytes",
+            opts(
+                "exporter_memory_bytes",
                 "Memory usage of exporter process in bytes",
             ),
             no_labels,
@@ -219,14 +544,78 @@ impl Metrics {
         .unwrap();
 
         let exporter_cpu_percent = GaugeVec::new(
-            Opts::new(
-                "railway_exporter_cpu_percent",
+            opts(
+                "exporter_cpu_percent",
                 "CPU usage percentage of exporter process",
             ),
             no_labels,
         )
         .unwrap();
 
+        // Exporter self-observability
+        let build_info = IntGaugeVec::new(
+            opts(
+                "exporter_build_info",
+                "Exporter build information (always 1)",
+            ),
+            &["version", "git_sha"],
+        )
+        .unwrap();
+        build_info
+            .with_label_values(&[env!("CARGO_PKG_VERSION"), env!("GIT_SHA")])
+            .set(1);
+
+        let http_requests = IntCounterVec::new(
+            opts("exporter_http_requests_total", "HTTP requests served"),
+            &["route", "status", "encoding"],
+        )
+        .unwrap();
+
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::from(opts(
+                "exporter_http_request_duration_seconds",
+                "HTTP request latency in seconds",
+            )),
+            &["route"],
+        )
+        .unwrap();
+
+        let ws_connections = IntCounter::with_opts(opts(
+            "exporter_ws_connections_total",
+            "WebSocket clients connected",
+        ))
+        .unwrap();
+
+        let ws_disconnections = IntCounter::with_opts(opts(
+            "exporter_ws_disconnections_total",
+            "WebSocket clients disconnected",
+        ))
+        .unwrap();
+
+        let ws_messages = IntCounterVec::new(
+            opts("exporter_ws_messages_total", "WebSocket messages"),
+            &["direction"],
+        )
+        .unwrap();
+
+        let scrapes = IntCounterVec::new(
+            opts(
+                "exporter_scrapes_total",
+                "Railway API collections by result",
+            ),
+            &["result"],
+        )
+        .unwrap();
+
+        let collection_duration = Histogram::with_opts(
+            HistogramOpts::from(opts(
+                "exporter_collection_duration_seconds",
+                "Railway API collection duration in seconds",
+            ))
+            .buckets(COLLECTION_BUCKETS.to_vec()),
+        )
+        .unwrap();
+
         // Register all metrics
         registry.register(Box::new(cpu_usage.clone())).unwrap();
         registry.register(Box::new(memory_usage.clone())).unwrap();
@@ -236,6 +625,38 @@ impl Metrics {
         registry
             .register(Box::new(service_estimated_monthly.clone()))
             .unwrap();
+        registry
+            .register(Box::new(group_cpu_usage.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(group_memory_usage.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(group_disk_usage.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(group_network_tx.clone()))
+            .unwrap();
+        registry.register(Box::new(group_cost.clone())).unwrap();
+        registry
+            .register(Box::new(group_estimated_monthly.clone()))
+            .unwrap();
+        registry.register(Box::new(group_services.clone())).unwrap();
+        registry
+            .register(Box::new(group_allocated_cost.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(group_allocated_estimated_monthly.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(cost_center_cost.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(cost_center_estimated_monthly.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(cost_center_closed_cost.clone()))
+            .unwrap();
         registry.register(Box::new(current_usage.clone())).unwrap();
         registry
             .register(Box::new(estimated_monthly.clone()))
@@ -254,12 +675,35 @@ impl Metrics {
             .register(Box::new(scrape_duration_seconds.clone()))
             .unwrap();
         registry.register(Box::new(api_up.clone())).unwrap();
-        registry
+        // Self-metrics
+        self_registry
             .register(Box::new(exporter_memory_bytes.clone()))
             .unwrap();
-        registry
+        self_registry
             .register(Box::new(exporter_cpu_percent.clone()))
             .unwrap();
+        self_registry
+            .register(Box::new(build_info.clone()))
+            .unwrap();
+        self_registry
+            .register(Box::new(http_requests.clone()))
+            .unwrap();
+        self_registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        self_registry
+            .register(Box::new(ws_connections.clone()))
+            .unwrap();
+        self_registry
+            .register(Box::new(ws_disconnections.clone()))
+            .unwrap();
+        self_registry
+            .register(Box::new(ws_messages.clone()))
+            .unwrap();
+        self_registry.register(Box::new(scrapes.clone())).unwrap();
+        self_registry
+            .register(Box::new(collection_duration.clone()))
+            .unwrap();
 
         Self {
             cpu_usage,
@@ -268,6 +712,18 @@ impl Metrics {
             network_tx,
             service_cost,
             service_estimated_monthly,
+            group_cpu_usage,
+            group_memory_usage,
+            group_disk_usage,
+            group_network_tx,
+            group_cost,
+            group_estimated_monthly,
+            group_services,
+            group_allocated_cost,
+            group_allocated_estimated_monthly,
+            cost_center_cost,
+            cost_center_estimated_monthly,
+            cost_center_closed_cost,
             current_usage,
             estimated_monthly,
             daily_average,
@@ -278,18 +734,77 @@ impl Metrics {
             api_up,
             exporter_memory_bytes,
             exporter_cpu_percent,
+            build_info,
+            http_requests,
+            http_request_duration,
+            ws_connections,
+            ws_disconnections,
+            ws_messages,
+            scrapes,
+            collection_duration,
             registry,
+            self_registry,
+            relabel,
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or(0.0),
         }
     }
 
+    /// Gathers all metric families with relabeling applied.
+    ///
+    /// Use this instead of `registry.gather()` for anything leaving the process.
+    pub fn gather(&self) -> Vec<MetricFamily> {
+        let mut families = self.registry.gather();
+        families.extend(self.self_registry.gather());
+        families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+        self.relabel.relabel_families(&mut families);
+        families
+    }
+
+    /// Gathers the Railway metric families (cached per collection).
+    pub fn gather_railway(&self) -> Vec<MetricFamily> {
+        let mut families = self.registry.gather();
+        self.relabel.relabel_families(&mut families);
+        families
+    }
+
+    /// Gathers the exporter self-metrics (rendered on every scrape).
+    pub fn gather_self(&self) -> Vec<MetricFamily> {
+        let mut families = self.self_registry.gather();
+        self.relabel.relabel_families(&mut families);
+        families
+    }
+
     /// Encodes all metrics in Prometheus text format.
     pub fn encode(&self) -> String {
-        let encoder = TextEncoder::new();
+        String::from_utf8(self.encode_as(ExpositionFormat::Text)).unwrap()
+    }
+
+    /// Encodes all metrics in OpenMetrics 1.0 text format.
+    pub fn encode_openmetrics(&self) -> String {
+        String::from_utf8(self.encode_as(ExpositionFormat::OpenMetrics)).unwrap()
+    }
+
+    /// Encodes all metrics in the given exposition format.
+    pub fn encode_as(&self, format: ExpositionFormat) -> Vec<u8> {
+        self.encode_families(&self.gather(), format)
+    }
+
+    /// Encodes already gathered (e.g. filtered) families in the given format.
+    pub fn encode_families(&self, families: &[MetricFamily], format: ExpositionFormat) -> Vec<u8> {
         let mut buffer = Vec::new();
-        encoder
-            .encode(&self.registry.gather(), &mut buffer)
-            .unwrap();
-        String::from_utf8(buffer).unwrap()
+        match format {
+            ExpositionFormat::Text => TextEncoder::new().encode(families, &mut buffer).unwrap(),
+            ExpositionFormat::OpenMetrics => {
+                buffer = exposition::encode_openmetrics(families, self.created).into_bytes()
+            }
+            ExpositionFormat::Protobuf => ProtobufEncoder::new()
+                .encode(families, &mut buffer)
+                .unwrap(),
+        }
+        buffer
     }
 
     /// Updates exporter process metrics (CPU, memory).
@@ -308,14 +823,55 @@ impl Metrics {
         }
     }
 
-    /// Resets all metric values.
-    pub fn reset(&self) {
+    /// Resets per-group metric values (before re-exporting group rollups).
+    pub fn reset_groups(&self) {
+        self.group_cpu_usage.reset();
+        self.group_memory_usage.reset();
+        self.group_disk_usage.reset();
+        self.group_network_tx.reset();
+        self.group_cost.reset();
+        self.group_estimated_monthly.reset();
+        self.group_services.reset();
+        self.group_allocated_cost.reset();
+        self.group_allocated_estimated_monthly.reset();
+    }
+
+    /// Records a served HTTP request with its `Content-Encoding` (None for
+    /// uncompressed bodies).
+    pub fn observe_request(&self, route: &str, status: u16, encoding: Option<&str>, seconds: f64) {
+        self.http_requests
+            .with_label_values(&[route, &status.to_string(), encoding.unwrap_or("identity")])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[route])
+            .observe(seconds);
+    }
+
+    /// Records a Railway API collection attempt.
+    pub fn observe_collection(&self, success: bool, seconds: f64) {
+        self.scrapes
+            .with_label_values(&[if success { "success" } else { "failure" }])
+            .inc();
+        self.collection_duration.observe(seconds);
+    }
+
+    /// Resets per-service metric values (before re-exporting service rows).
+    pub fn reset_services(&self) {
         self.cpu_usage.reset();
         self.memory_usage.reset();
         self.disk_usage.reset();
         self.network_tx.reset();
         self.service_cost.reset();
         self.service_estimated_monthly.reset();
+    }
+
+    /// Resets all gauge values (counters, histograms and build info are kept).
+    pub fn reset(&self) {
+        self.reset_services();
+        self.reset_groups();
+        self.cost_center_cost.reset();
+        self.cost_center_estimated_monthly.reset();
+        self.cost_center_closed_cost.reset();
         self.current_usage.reset();
         self.estimated_monthly.reset();
         self.daily_average.reset();
@@ -1,6 +1,6 @@
 //! Unit tests for Railway Exporter metrics.
 
-use crate::metrics::Metrics;
+use crate::metrics::{Metrics, MetricsConfig};
 
 // =============================================================================
 // Metrics Creation Tests
@@ -182,3 +182,125 @@ fn test_encode_prometheus_format() {
     assert!(output.contains("railway_cpu_usage_vcpu_minutes{"));
     assert!(output.contains("} 42"));
 }
+
+// =============================================================================
+// Prefix and Constant Label Tests
+// =============================================================================
+
+#[test]
+fn test_metrics_custom_prefix_and_const_labels() {
+    let mut config = MetricsConfig {
+        prefix: "acme".to_string(),
+        ..Default::default()
+    };
+    config
+        .const_labels
+        .insert("env".to_string(), "prod".to_string());
+    let metrics = Metrics::with_config(&config);
+    metrics
+        .cpu_usage
+        .with_label_values(&["web", "proj", "", "frontend"])
+        .set(1.0);
+    metrics
+        .exporter_memory_bytes
+        .with_label_values(&[])
+        .set(2.0);
+
+    let output = metrics.encode();
+    assert!(!output.contains("railway_"));
+    assert!(output.contains(
+        "acme_cpu_usage_vcpu_minutes{env=\"prod\",group=\"frontend\",icon=\"\",project=\"proj\",service=\"web\"} 1"
+    ));
+    assert!(output.contains("acme_exporter_memory_bytes{env=\"prod\"} 2"));
+}
+
+#[test]
+fn test_metrics_empty_prefix() {
+    let config = MetricsConfig {
+        prefix: String::new(),
+        ..Default::default()
+    };
+    let metrics = Metrics::with_config(&config);
+    metrics.api_up.with_label_values(&["proj"]).set(1.0);
+    assert!(metrics.encode().contains("\napi_up{project=\"proj\"} 1"));
+}
+
+#[test]
+fn test_metrics_config_validate() {
+    assert!(MetricsConfig::default().validate().is_ok());
+
+    let invalid_prefixes = ["1abc", "my-prefix", "a b"];
+    for prefix in invalid_prefixes {
+        let config = MetricsConfig {
+            prefix: prefix.to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err(), "prefix {:?}", prefix);
+    }
+
+    for label in ["service", "project", "__name__", "bad-label", ""] {
+        let mut config = MetricsConfig::default();
+        config
+            .const_labels
+            .insert(label.to_string(), "x".to_string());
+        assert!(config.validate().is_err(), "label {:?}", label);
+    }
+}
+
+// =============================================================================
+// Self-Observability Tests
+// =============================================================================
+
+#[test]
+fn test_build_info() {
+    let output = Metrics::new().encode();
+    assert!(output.contains(&format!(
+        "railway_exporter_build_info{{git_sha=\"{}\",version=\"{}\"}} 1",
+        env!("GIT_SHA"),
+        env!("CARGO_PKG_VERSION")
+    )));
+}
+
+#[test]
+fn test_observe_request() {
+    let metrics = Metrics::new();
+    metrics.observe_request("/metrics", 200, Some("br"), 0.002);
+    metrics.observe_request("/metrics", 200, Some("br"), 0.004);
+    metrics.observe_request("/metrics", 200, Some("zstd"), 0.003);
+    metrics.observe_request("/health", 404, None, 0.001);
+
+    let output = metrics.encode();
+    assert!(output.contains(
+        "railway_exporter_http_requests_total{encoding=\"br\",route=\"/metrics\",status=\"200\"} 2"
+    ));
+    assert!(output.contains(
+        "railway_exporter_http_requests_total{encoding=\"zstd\",route=\"/metrics\",status=\"200\"} 1"
+    ));
+    assert!(output.contains(
+        "railway_exporter_http_requests_total{encoding=\"identity\",route=\"/health\",status=\"404\"} 1"
+    ));
+    assert!(output
+        .contains("railway_exporter_http_request_duration_seconds_count{route=\"/metrics\"} 3"));
+}
+
+#[test]
+fn test_observe_collection() {
+    let metrics = Metrics::new();
+    metrics.observe_collection(true, 1.5);
+    metrics.observe_collection(false, 0.2);
+
+    let output = metrics.encode();
+    assert!(output.contains("railway_exporter_scrapes_total{result=\"success\"} 1"));
+    assert!(output.contains("railway_exporter_scrapes_total{result=\"failure\"} 1"));
+    assert!(output.contains("railway_exporter_collection_duration_seconds_bucket{le=\"2.5\"} 2"));
+    assert!(output.contains("railway_exporter_collection_duration_seconds_count 2"));
+}
+
+#[test]
+fn test_reset_keeps_counters() {
+    let metrics = Metrics::new();
+    metrics.observe_collection(true, 1.0);
+    metrics.reset();
+    assert_eq!(metrics.scrapes.with_label_values(&["success"]).get(), 1);
+    assert!(metrics.encode().contains("railway_exporter_build_info"));
+}
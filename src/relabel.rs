@@ -0,0 +1,329 @@
+//! Label cardinality controls.
+//!
+//! Rules under `metrics.relabel` run on every collection and apply the same
+//! way to the Prometheus gauges (and every sink fed from the registry) and to
+//! the JSON output:
+//!
+//! | Action | Labels | Effect |
+//! |--------|--------|--------|
+//! | `drop` | `icon`, `project` | Label removed from every family; a dropped `icon` is also cleared in JSON |
+//! | `rename` | any label set by the exporter | Label name changed (JSON field names stay the same) |
+//! | `replace` | `service`, `group` | Value rewritten by regex (fully anchored, like Prometheus relabeling) |
+//!
+//! Services whose rewritten `service`/`group` values coincide are merged into
+//! one series (usage and cost summed). Group rollups and cost allocation see
+//! the rewritten values; cost centers still match the original Railway names.
+//!
+//! `max_series` caps the number of per-service series: the cheapest services
+//! are collapsed into the `other` bucket (see `service_filter.other_name`).
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! metrics:
+//!   relabel:
+//!     - label: icon
+//!       action: drop
+//!     - label: service
+//!       action: replace
+//!       regex: "(.*)-prod"
+//!       replacement: "$1"      # default: $1
+//!     - label: group
+//!       action: rename
+//!       target_label: team
+//!   max_series: 50
+//! ```
+
+use crate::config::ConfigError;
+use crate::grouping::UNGROUPED;
+use crate::metrics::{is_valid_name, RESERVED_LABELS};
+use crate::types::{ServiceData, OTHER_ID};
+use prometheus::proto::MetricFamily;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Labels set by the exporter (can be renamed).
+const EXPORTER_LABELS: &[&str] = &[
+    "service",
+    "project",
+    "icon",
+    "group",
+    "dimension",
+    "cost_center",
+    "period",
+];
+
+/// Labels that don't identify a series (can be dropped).
+const DROPPABLE_LABELS: &[&str] = &["icon", "project"];
+
+/// Labels whose values can be rewritten.
+const REPLACEABLE_LABELS: &[&str] = &["service", "group"];
+
+/// What a rule does to its label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelabelAction {
+    /// Remove the label.
+    Drop,
+    /// Change the label name to `target_label`.
+    Rename,
+    /// Rewrite values matching `regex` to `replacement`.
+    Replace,
+}
+
+/// A single rule as written in YAML.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RelabelRule {
+    /// Label the rule applies to.
+    pub label: String,
+    pub action: RelabelAction,
+    /// New label name (`rename`).
+    #[serde(default)]
+    pub target_label: Option<String>,
+    /// Value pattern (`replace`).
+    #[serde(default)]
+    pub regex: Option<String>,
+    /// Replacement with `$1`-style capture references (`replace`).
+    #[serde(default = "default_replacement")]
+    pub replacement: String,
+}
+
+fn default_replacement() -> String {
+    "$1".to_string()
+}
+
+/// Compiled relabeling rules and series limit.
+#[derive(Debug, Clone, Default)]
+pub struct Relabeler {
+    drop: Vec<String>,
+    rename: Vec<(String, String)>,
+    replace: Vec<(String, Regex, String)>,
+    max_series: Option<usize>,
+}
+
+impl Relabeler {
+    /// Validates and compiles rules.
+    pub fn new(rules: &[RelabelRule], max_series: Option<usize>) -> Result<Self, ConfigError> {
+        let mut relabeler = Self {
+            max_series,
+            ..Default::default()
+        };
+        if max_series.is_some_and(|max| max < 2) {
+            return Err(ConfigError::InvalidValue(
+                "metrics.max_series must be at least 2".to_string(),
+            ));
+        }
+
+        for (i, rule) in rules.iter().enumerate() {
+            let err =
+                |msg: String| ConfigError::InvalidValue(format!("metrics.relabel[{}]: {}", i, msg));
+            let label = rule.label.as_str();
+            if !EXPORTER_LABELS.contains(&label) {
+                return Err(err(format!(
+                    "unknown label '{}' (expected one of {})",
+                    label,
+                    EXPORTER_LABELS.join(", ")
+                )));
+            }
+            if relabeler.drop.iter().any(|d| d == label) {
+                return Err(err(format!("label '{}' is already dropped", label)));
+            }
+
+            match rule.action {
+                RelabelAction::Drop => {
+                    if !DROPPABLE_LABELS.contains(&label) {
+                        return Err(err(format!(
+                            "only {} can be dropped",
+                            DROPPABLE_LABELS.join(" and ")
+                        )));
+                    }
+                    if relabeler.rename.iter().any(|(from, _)| from == label) {
+                        return Err(err(format!("label '{}' is already renamed", label)));
+                    }
+                    relabeler.drop.push(label.to_string());
+                }
+                RelabelAction::Rename => {
+                    let target = rule
+                        .target_label
+                        .as_deref()
+                        .ok_or_else(|| err("rename needs target_label".to_string()))?;
+                    if !is_valid_name(target, false) || target.starts_with("__") {
+                        return Err(err(format!("'{}' is not a valid label name", target)));
+                    }
+                    if RESERVED_LABELS.contains(&target) {
+                        return Err(err(format!(
+                            "target_label '{}' is already set by the exporter",
+                            target
+                        )));
+                    }
+                    if relabeler
+                        .rename
+                        .iter()
+                        .any(|(from, to)| from == label || to == target)
+                    {
+                        return Err(err(format!(
+                            "label '{}' or target '{}' is already renamed",
+                            label, target
+                        )));
+                    }
+                    relabeler
+                        .rename
+                        .push((label.to_string(), target.to_string()));
+                }
+                RelabelAction::Replace => {
+                    if !REPLACEABLE_LABELS.contains(&label) {
+                        return Err(err(format!(
+                            "only {} values can be replaced",
+                            REPLACEABLE_LABELS.join(" and ")
+                        )));
+                    }
+                    let pattern = rule
+                        .regex
+                        .as_deref()
+                        .ok_or_else(|| err("replace needs regex".to_string()))?;
+                    let regex = Regex::new(&format!("^(?:{})$", pattern))
+                        .map_err(|e| err(format!("invalid regex '{}': {}", pattern, e)))?;
+                    relabeler
+                        .replace
+                        .push((label.to_string(), regex, rule.replacement.clone()));
+                }
+            }
+        }
+        Ok(relabeler)
+    }
+
+    /// Returns true if no rule or limit is configured.
+    pub fn is_empty(&self) -> bool {
+        self.drop.is_empty()
+            && self.rename.is_empty()
+            && self.replace.is_empty()
+            && self.max_series.is_none()
+    }
+
+    /// Label names produced by `rename` rules.
+    pub fn target_labels(&self) -> impl Iterator<Item = &str> {
+        self.rename.iter().map(|(_, to)| to.as_str())
+    }
+
+    /// Returns true if the label is dropped.
+    pub fn drops(&self, label: &str) -> bool {
+        self.drop.iter().any(|d| d == label)
+    }
+
+    /// Applies `replace` rules for a label to a value.
+    pub fn rewrite(&self, label: &str, value: &str) -> String {
+        let mut value = value.to_string();
+        for (_, regex, replacement) in self.replace.iter().filter(|(l, ..)| l == label) {
+            if let Some(caps) = regex.captures(&value) {
+                let mut out = String::new();
+                caps.expand(replacement, &mut out);
+                value = out;
+            }
+        }
+        value
+    }
+
+    /// Rewrites service rows, merges rows that end up identical and applies
+    /// `max_series` (cheapest services go to the `other_name` bucket).
+    pub fn apply(&self, rows: Vec<ServiceData>, other_name: &str) -> Vec<ServiceData> {
+        if self.replace.is_empty() && !self.drops("icon") && self.max_series.is_none() {
+            return rows;
+        }
+
+        let mut merged: Vec<ServiceData> = Vec::with_capacity(rows.len());
+        for mut row in rows {
+            row.name = self.rewrite("service", &row.name);
+            row.group = self.rewrite("group", &row.group);
+            let mut groups: Vec<String> = Vec::with_capacity(row.groups.len());
+            for group in &row.groups {
+                let group = self.rewrite("group", group);
+                if !groups.contains(&group) {
+                    groups.push(group);
+                }
+            }
+            row.groups = groups;
+            if self.drops("icon") {
+                row.icon.clear();
+            }
+
+            match merged.iter_mut().find(|r| {
+                r.name == row.name
+                    && r.group == row.group
+                    && (r.id == OTHER_ID) == (row.id == OTHER_ID)
+            }) {
+                Some(existing) => merge_into(existing, row),
+                None => merged.push(row),
+            }
+        }
+
+        match self.max_series {
+            Some(max) if merged.len() > max => collapse(merged, max, other_name),
+            _ => merged,
+        }
+    }
+
+    /// Drops and renames labels in gathered metric families.
+    pub fn relabel_families(&self, families: &mut [MetricFamily]) {
+        if self.drop.is_empty() && self.rename.is_empty() {
+            return;
+        }
+        for family in families.iter_mut() {
+            for metric in family.mut_metric().iter_mut() {
+                let mut labels: Vec<_> = metric.take_label().into();
+                labels.retain(|l| !self.drops(l.get_name()));
+                for label in labels.iter_mut() {
+                    if let Some((_, to)) = self
+                        .rename
+                        .iter()
+                        .find(|(from, _)| from == label.get_name())
+                    {
+                        label.set_name(to.clone());
+                    }
+                }
+                // Keep the canonical (sorted) label order
+                labels.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+                metric.set_label(labels.into());
+            }
+        }
+    }
+}
+
+/// Adds a row's usage and cost to another row.
+fn merge_into(target: &mut ServiceData, row: ServiceData) {
+    target.cpu_usage += row.cpu_usage;
+    target.memory_usage += row.memory_usage;
+    target.disk_usage += row.disk_usage;
+    target.network_tx += row.network_tx;
+    target.cost_usd += row.cost_usd;
+    target.estimated_monthly_usd += row.estimated_monthly_usd;
+    target.is_deleted &= row.is_deleted;
+    for group in row.groups {
+        if !target.groups.contains(&group) {
+            target.groups.push(group);
+        }
+    }
+    for m in row.group_matches {
+        if !target.group_matches.iter().any(|t| t.group == m.group) {
+            target.group_matches.push(m);
+        }
+    }
+}
+
+/// Keeps the `max - 1` most expensive rows and folds the rest into `other`.
+fn collapse(rows: Vec<ServiceData>, max: usize, other_name: &str) -> Vec<ServiceData> {
+    let (existing, mut rows): (Vec<_>, Vec<_>) = rows.into_iter().partition(|r| r.id == OTHER_ID);
+    let mut other = ServiceData::other(other_name);
+    for row in existing {
+        merge_into(&mut other, row);
+    }
+
+    rows.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+    for row in rows.split_off(max - 1) {
+        merge_into(&mut other, row);
+    }
+    // Collapsed rows' groups would double-count them in group rollups
+    other.groups = vec![UNGROUPED.to_string()];
+    other.group_matches.clear();
+    rows.push(other);
+    rows
+}
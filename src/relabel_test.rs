@@ -0,0 +1,195 @@
+//! Tests for label relabeling and the series limit.
+
+use crate::metrics::{Metrics, MetricsConfig};
+use crate::relabel::{RelabelAction, RelabelRule, Relabeler};
+use crate::test_support::service;
+use crate::types::{ServiceData, OTHER_ID};
+
+fn rule(label: &str, action: RelabelAction) -> RelabelRule {
+    RelabelRule {
+        label: label.to_string(),
+        action,
+        target_label: None,
+        regex: None,
+        replacement: "$1".to_string(),
+    }
+}
+
+fn replace(label: &str, regex: &str, replacement: &str) -> RelabelRule {
+    RelabelRule {
+        regex: Some(regex.to_string()),
+        replacement: replacement.to_string(),
+        ..rule(label, RelabelAction::Replace)
+    }
+}
+
+fn rename(label: &str, target: &str) -> RelabelRule {
+    RelabelRule {
+        target_label: Some(target.to_string()),
+        ..rule(label, RelabelAction::Rename)
+    }
+}
+
+// =============================================================================
+// Validation Tests
+// =============================================================================
+
+#[test]
+fn test_new_validates_rules() {
+    assert!(Relabeler::new(&[], None).unwrap().is_empty());
+    assert!(Relabeler::new(&[rule("icon", RelabelAction::Drop)], None).is_ok());
+
+    let invalid = vec![
+        vec![rule("unknown", RelabelAction::Drop)],
+        vec![rule("service", RelabelAction::Drop)],
+        vec![rule("icon", RelabelAction::Rename)],
+        vec![rename("service", "group")],
+        vec![rename("service", "bad-name")],
+        vec![rename("service", "app"), rename("group", "app")],
+        vec![rule("service", RelabelAction::Replace)],
+        vec![replace("icon", ".*", "")],
+        vec![replace("service", "(", "")],
+        vec![rule("icon", RelabelAction::Drop), rename("icon", "emoji")],
+    ];
+    for rules in invalid {
+        assert!(Relabeler::new(&rules, None).is_err(), "{:?}", rules);
+    }
+
+    assert!(Relabeler::new(&[], Some(1)).is_err());
+    assert!(Relabeler::new(&[], Some(2)).is_ok());
+}
+
+#[test]
+fn test_const_label_clashes_with_rename_target() {
+    let mut config = MetricsConfig {
+        relabel: vec![rename("group", "team")],
+        ..Default::default()
+    };
+    assert!(config.validate().is_ok());
+    config
+        .const_labels
+        .insert("team".to_string(), "platform".to_string());
+    assert!(config.validate().is_err());
+}
+
+// =============================================================================
+// Row Rewriting Tests
+// =============================================================================
+
+#[test]
+fn test_rewrite_is_anchored() {
+    let relabel = Relabeler::new(&[replace("service", "(.*)-prod", "$1")], None).unwrap();
+    assert_eq!(relabel.rewrite("service", "api-prod"), "api");
+    assert_eq!(relabel.rewrite("service", "api-prod-old"), "api-prod-old");
+    assert_eq!(relabel.rewrite("group", "x-prod"), "x-prod");
+}
+
+#[test]
+fn test_apply_merges_rewritten_rows() {
+    let relabel = Relabeler::new(
+        &[
+            replace("service", "(.*)-(prod|staging)", "$1"),
+            rule("icon", RelabelAction::Drop),
+        ],
+        None,
+    )
+    .unwrap();
+    let rows = relabel.apply(
+        vec![
+            service("api-prod", &["backend"], 2.0),
+            service("api-staging", &["backend"], 1.0),
+            service("web", &["frontend"], 0.5),
+        ],
+        "other",
+    );
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].name, "api");
+    assert_eq!(rows[0].id, "id-api-prod");
+    assert_eq!(rows[0].cost_usd, 3.0);
+    assert_eq!(rows[0].cpu_usage, 30.0);
+    assert_eq!(rows[0].estimated_monthly_usd, 9.0);
+    assert!(rows.iter().all(|r| r.icon.is_empty()));
+}
+
+#[test]
+fn test_apply_without_rules_keeps_rows() {
+    let relabel = Relabeler::default();
+    let rows = relabel.apply(vec![service("api", &["backend"], 1.0)], "other");
+    assert_eq!(rows[0].icon, "📦");
+}
+
+#[test]
+fn test_max_series_collapses_cheapest_into_other() {
+    let relabel = Relabeler::new(&[], Some(3)).unwrap();
+    let mut filtered = ServiceData::other("other");
+    filtered.cost_usd = 0.1;
+    let rows = relabel.apply(
+        vec![
+            service("a", &["g"], 1.0),
+            service("b", &["g"], 5.0),
+            service("c", &["g"], 0.5),
+            service("d", &["g"], 3.0),
+            filtered,
+        ],
+        "other",
+    );
+
+    let names: Vec<&str> = rows.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, vec!["b", "d", "other"]);
+    let other = &rows[2];
+    assert!((other.cost_usd - 1.6).abs() < 1e-9);
+    assert_eq!(other.id, OTHER_ID);
+    assert_eq!(other.group, "ungrouped");
+    assert_eq!(other.groups, vec!["ungrouped".to_string()]);
+
+    let total: f64 = rows.iter().map(|r| r.cost_usd).sum();
+    assert!((total - 9.6).abs() < 1e-9);
+}
+
+#[test]
+fn test_max_series_keeps_service_named_other() {
+    let relabel = Relabeler::new(&[], Some(2)).unwrap();
+    let rows = relabel.apply(
+        vec![
+            service("other", &["ungrouped"], 5.0),
+            service("a", &["g"], 1.0),
+            service("b", &["g"], 0.5),
+        ],
+        "other",
+    );
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].id, "id-other");
+    assert_eq!(rows[0].cost_usd, 5.0);
+    assert_eq!(rows[1].id, OTHER_ID);
+    assert_eq!(rows[1].cost_usd, 1.5);
+}
+
+// =============================================================================
+// Gauge Relabeling Tests
+// =============================================================================
+
+#[test]
+fn test_gather_drops_and_renames_labels() {
+    let config = MetricsConfig {
+        relabel: vec![rule("icon", RelabelAction::Drop), rename("group", "team")],
+        ..Default::default()
+    };
+    let metrics = Metrics::with_config(&config);
+    metrics
+        .cpu_usage
+        .with_label_values(&["web", "proj", "📦", "frontend"])
+        .set(1.0);
+    metrics
+        .group_cost
+        .with_label_values(&["frontend", "proj"])
+        .set(2.0);
+
+    let output = metrics.encode();
+    assert!(output.contains(
+        "railway_cpu_usage_vcpu_minutes{project=\"proj\",service=\"web\",team=\"frontend\"} 1"
+    ));
+    assert!(output.contains("railway_group_cost_usd{project=\"proj\",team=\"frontend\"} 2"));
+    assert!(!output.contains("icon="));
+}
@@ -0,0 +1,238 @@
+//! Federation-style series selection for `/metrics`.
+//!
+//! `/metrics` accepts the same filter parameters as Prometheus' `/federate`,
+//! so secondary scrapers and ad-hoc `curl` calls can fetch just what they need:
+//!
+//! ```text
+//! /metrics?name[]=railway_service_cost_usd&match[]={group="database"}
+//! ```
+//!
+//! - `name[]` keeps only the listed metric families (exact names)
+//! - `match[]` keeps only series matching at least one selector
+//!
+//! Selectors use PromQL syntax: an optional metric name followed by label
+//! matchers with `=`, `!=`, `=~` or `!~` (regexes are fully anchored). A
+//! missing label compares as the empty string. Both parameters can be repeated
+//! and combined; families left without series are omitted.
+//!
+//! Filtering runs on the gathered families after relabeling, so selectors see
+//! the exported label names.
+
+use crate::metrics::is_valid_name;
+use prometheus::proto::{Metric, MetricFamily};
+use regex::Regex;
+
+/// Comparison done by a label matcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchOp {
+    /// `=`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `=~`
+    Regex,
+    /// `!~`
+    NotRegex,
+}
+
+/// A single `label<op>"value"` matcher.
+#[derive(Debug, Clone)]
+pub struct LabelMatcher {
+    name: String,
+    op: MatchOp,
+    value: String,
+    regex: Option<Regex>,
+}
+
+impl LabelMatcher {
+    /// Creates a matcher, compiling the value for regex operators.
+    pub fn new(name: &str, op: MatchOp, value: &str) -> Result<Self, String> {
+        let regex = match op {
+            MatchOp::Regex | MatchOp::NotRegex => Some(
+                Regex::new(&format!("^(?:{})$", value))
+                    .map_err(|e| format!("invalid regex '{}': {}", value, e))?,
+            ),
+            MatchOp::Equal | MatchOp::NotEqual => None,
+        };
+        Ok(Self {
+            name: name.to_string(),
+            op,
+            value: value.to_string(),
+            regex,
+        })
+    }
+
+    /// Returns true if a label value satisfies the matcher.
+    pub fn matches(&self, value: &str) -> bool {
+        match (self.op, &self.regex) {
+            (MatchOp::Equal, _) => value == self.value,
+            (MatchOp::NotEqual, _) => value != self.value,
+            (MatchOp::Regex, Some(re)) => re.is_match(value),
+            (MatchOp::NotRegex, Some(re)) => !re.is_match(value),
+            _ => false,
+        }
+    }
+}
+
+/// A parsed `match[]` selector (all matchers must hold).
+#[derive(Debug, Clone)]
+pub struct Selector {
+    matchers: Vec<LabelMatcher>,
+}
+
+impl Selector {
+    /// Parses a PromQL series selector such as `railway_service_cost_usd{group=~"db|cache"}`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let (name, body) = match input.find('{') {
+            Some(i) => (input[..i].trim_end(), Some(&input[i + 1..])),
+            None => (input, None),
+        };
+
+        let mut matchers = Vec::new();
+        if !name.is_empty() {
+            if !is_valid_name(name, true) {
+                return Err(format!("'{}' is not a valid metric name", name));
+            }
+            matchers.push(LabelMatcher::new("__name__", MatchOp::Equal, name)?);
+        }
+        if let Some(body) = body {
+            parse_matchers(body, &mut matchers)?;
+        }
+        if matchers.is_empty() {
+            return Err("selector needs a metric name or at least one label matcher".to_string());
+        }
+        Ok(Self { matchers })
+    }
+
+    /// Returns true if a series of the given family satisfies every matcher.
+    pub fn matches(&self, family: &str, metric: &Metric) -> bool {
+        self.matchers.iter().all(|m| {
+            let value = if m.name == "__name__" {
+                family
+            } else {
+                metric
+                    .get_label()
+                    .iter()
+                    .find(|l| l.get_name() == m.name)
+                    .map_or("", |l| l.get_value())
+            };
+            m.matches(value)
+        })
+    }
+}
+
+/// Parses `label<op>"value", ...}` (the part after `{`).
+fn parse_matchers(body: &str, matchers: &mut Vec<LabelMatcher>) -> Result<(), String> {
+    let mut rest = body;
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix('}') {
+            if !after.trim().is_empty() {
+                return Err(format!("unexpected '{}' after '}}'", after.trim()));
+            }
+            return Ok(());
+        }
+
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let label = &rest[..end];
+        if label.is_empty() || !is_valid_name(label, false) {
+            return Err(format!("expected label name at '{}'", rest));
+        }
+        rest = rest[end..].trim_start();
+
+        let (op, len) = if rest.starts_with("=~") {
+            (MatchOp::Regex, 2)
+        } else if rest.starts_with("!~") {
+            (MatchOp::NotRegex, 2)
+        } else if rest.starts_with("!=") {
+            (MatchOp::NotEqual, 2)
+        } else if rest.starts_with('=') {
+            (MatchOp::Equal, 1)
+        } else {
+            return Err(format!("expected =, !=, =~ or !~ after '{}'", label));
+        };
+        let (value, after) = parse_string(rest[len..].trim_start())?;
+        matchers.push(LabelMatcher::new(label, op, &value)?);
+
+        rest = after.trim_start();
+        if let Some(after) = rest.strip_prefix(',') {
+            rest = after;
+        } else if !rest.starts_with('}') {
+            return Err(format!("expected ',' or '}}' after '{}'", label));
+        }
+    }
+}
+
+/// Parses a single- or double-quoted string, returning it and the remainder.
+fn parse_string(input: &str) -> Result<(String, &str), String> {
+    let mut chars = input.char_indices();
+    let quote = match chars.next() {
+        Some((_, q @ ('"' | '\''))) => q,
+        _ => return Err("expected quoted label value".to_string()),
+    };
+
+    let mut value = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, c)) if c == '\\' || c == '"' || c == '\'' => value.push(c),
+                // Keep regex escapes such as \d as written
+                Some((_, c)) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => break,
+            },
+            c if c == quote => return Ok((value, &input[i + 1..])),
+            c => value.push(c),
+        }
+    }
+    Err("unterminated label value".to_string())
+}
+
+/// Family names and selectors from a `/metrics` query.
+#[derive(Debug, Clone, Default)]
+pub struct SeriesFilter {
+    names: Vec<String>,
+    selectors: Vec<Selector>,
+}
+
+impl SeriesFilter {
+    /// Builds a filter from `name[]` and `match[]` values.
+    pub fn new(names: Vec<String>, selectors: &[String]) -> Result<Self, String> {
+        let selectors = selectors
+            .iter()
+            .map(|s| Selector::parse(s).map_err(|e| format!("match[] '{}': {}", s, e)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { names, selectors })
+    }
+
+    /// Returns true if the filter keeps everything.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.selectors.is_empty()
+    }
+
+    /// Removes families and series not selected by the filter.
+    pub fn apply(&self, families: &mut Vec<MetricFamily>) {
+        families.retain_mut(|family| {
+            if !self.names.is_empty() && !self.names.iter().any(|n| n == family.get_name()) {
+                return false;
+            }
+            if !self.selectors.is_empty() {
+                let name = family.get_name().to_string();
+                let metrics: Vec<Metric> = family
+                    .take_metric()
+                    .into_iter()
+                    .filter(|m| self.selectors.iter().any(|s| s.matches(&name, m)))
+                    .collect();
+                family.set_metric(metrics.into());
+            }
+            !family.get_metric().is_empty()
+        });
+    }
+}
@@ -0,0 +1,174 @@
+//! Tests for federation-style series selection.
+
+use crate::metrics::Metrics;
+use crate::selector::{LabelMatcher, MatchOp, Selector, SeriesFilter};
+use prometheus::proto::MetricFamily;
+
+fn families() -> Vec<MetricFamily> {
+    let metrics = Metrics::new();
+    for (service, group, cost) in [("pg", "database", 3.0), ("web", "frontend", 1.0)] {
+        metrics
+            .service_cost
+            .with_label_values(&[service, "proj", "", group])
+            .set(cost);
+        metrics
+            .cpu_usage
+            .with_label_values(&[service, "proj", "", group])
+            .set(cost * 10.0);
+    }
+    metrics.api_up.with_label_values(&["proj"]).set(1.0);
+    metrics.gather()
+}
+
+/// Returns (family, service label) pairs left after filtering.
+fn series(filter: &SeriesFilter) -> Vec<(String, String)> {
+    let mut families = families();
+    filter.apply(&mut families);
+    families
+        .iter()
+        .filter(|f| !f.get_name().starts_with("railway_exporter_"))
+        .flat_map(|f| {
+            f.get_metric().iter().map(move |m| {
+                let service = m
+                    .get_label()
+                    .iter()
+                    .find(|l| l.get_name() == "service")
+                    .map_or("", |l| l.get_value());
+                (f.get_name().to_string(), service.to_string())
+            })
+        })
+        .collect()
+}
+
+fn filter(names: &[&str], selectors: &[&str]) -> SeriesFilter {
+    SeriesFilter::new(
+        names.iter().map(|s| s.to_string()).collect(),
+        &selectors.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
+
+// =============================================================================
+// Parsing Tests
+// =============================================================================
+
+#[test]
+fn test_parse_valid_selectors() {
+    let valid = [
+        "railway_api_up",
+        r#"{group="database"}"#,
+        r#"railway_service_cost_usd { group = "database" , service!~'we.*', }"#,
+        r#"{__name__=~"railway_.*_usd"}"#,
+        r#"{service="a \"quoted\" \\ name"}"#,
+        r#"{service=~"\d+"}"#,
+    ];
+    for s in valid {
+        assert!(Selector::parse(s).is_ok(), "{}", s);
+    }
+}
+
+#[test]
+fn test_parse_invalid_selectors() {
+    let invalid = [
+        "",
+        "{}",
+        "1metric",
+        r#"{group}"#,
+        r#"{group="x""#,
+        r#"{group=x}"#,
+        r#"{group="x" service="y"}"#,
+        r#"{group=~"("}"#,
+        r#"{group="x"} extra"#,
+        r#"{-bad="x"}"#,
+    ];
+    for s in invalid {
+        assert!(Selector::parse(s).is_err(), "{}", s);
+    }
+}
+
+#[test]
+fn test_label_matcher_ops() {
+    let eq = LabelMatcher::new("group", MatchOp::Equal, "db").unwrap();
+    assert!(eq.matches("db"));
+    assert!(!eq.matches("db2"));
+
+    let ne = LabelMatcher::new("group", MatchOp::NotEqual, "").unwrap();
+    assert!(ne.matches("db"));
+    assert!(!ne.matches(""));
+
+    let re = LabelMatcher::new("group", MatchOp::Regex, "db|cache").unwrap();
+    assert!(re.matches("cache"));
+    assert!(!re.matches("cache2"));
+
+    let nre = LabelMatcher::new("group", MatchOp::NotRegex, "d.*").unwrap();
+    assert!(nre.matches("web"));
+    assert!(!nre.matches("db"));
+}
+
+// =============================================================================
+// Filtering Tests
+// =============================================================================
+
+#[test]
+fn test_empty_filter_keeps_everything() {
+    let filter = SeriesFilter::default();
+    assert!(filter.is_empty());
+    assert_eq!(series(&filter).len(), 5);
+}
+
+#[test]
+fn test_filter_by_name() {
+    assert_eq!(
+        series(&filter(&["railway_service_cost_usd", "unknown"], &[])),
+        vec![
+            ("railway_service_cost_usd".to_string(), "pg".to_string()),
+            ("railway_service_cost_usd".to_string(), "web".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_filter_by_match() {
+    let result = series(&filter(&[], &[r#"{group="database"}"#]));
+    assert_eq!(
+        result,
+        vec![
+            (
+                "railway_cpu_usage_vcpu_minutes".to_string(),
+                "pg".to_string()
+            ),
+            ("railway_service_cost_usd".to_string(), "pg".to_string()),
+        ]
+    );
+
+    // A missing label compares as empty
+    let result = series(&filter(&[], &[r#"{service=""}"#]));
+    assert_eq!(result, vec![("railway_api_up".to_string(), String::new())]);
+}
+
+#[test]
+fn test_filter_match_union_and_name_intersection() {
+    let result = series(&filter(
+        &["railway_service_cost_usd"],
+        &[
+            r#"railway_service_cost_usd{service="web"}"#,
+            r#"{service="pg"}"#,
+        ],
+    ));
+    assert_eq!(result.len(), 2);
+    assert!(result
+        .iter()
+        .all(|(name, _)| name == "railway_service_cost_usd"));
+
+    let result = series(&filter(
+        &["railway_api_up"],
+        &[r#"railway_service_cost_usd"#],
+    ));
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_invalid_selector_reports_input() {
+    let err = SeriesFilter::new(vec![], &["{group=".to_string()]).unwrap_err();
+    assert!(err.contains("match[] '{group='"), "{}", err);
+}
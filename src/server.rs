@@ -1,29 +1,65 @@
 //! HTTP server and routing.
 
-use crate::handlers;
+use crate::access_log::AccessLogEntry;
+use crate::auth::WS_TOKEN_PROTOCOL;
+use crate::cors::CorsPolicy;
+use crate::handlers::{self, HandlerResponse};
+use crate::limits::{self, IdleTimeout};
+use crate::listen::{self, AcceptBackoff, Listener};
 use crate::state::AppState;
+use crate::tls::ReloadingAcceptor;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
 use futures_util::{SinkExt, StreamExt};
 use http_body_util::Full;
-use hyper::body::Bytes;
+use hyper::body::{Body, Bytes};
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
-use hyper::{Request, Response};
-use hyper_util::rt::TokioIo;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::{TokioIo, TokioTimer};
 use std::convert::Infallible;
-use std::net::SocketAddr;
+use std::net::IpAddr;
 use std::sync::Arc;
-use tokio::net::TcpListener;
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
 use tokio_tungstenite::tungstenite::Message;
-use tracing::{info, warn};
+use tokio_tungstenite::WebSocketStream;
+use tracing::{debug, error, info, warn};
 
 /// Starts the HTTP server.
 pub async fn start(state: Arc<AppState>) {
-    let addr = SocketAddr::from(([0, 0, 0, 0], state.config.port));
-    let listener = TcpListener::bind(addr).await.unwrap();
+    let listeners = match listen::bind(&state.config.listen) {
+        Ok(listeners) => listeners,
+        Err(e) => {
+            error!("Failed to listen on {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let tls = match state.config.tls.clone().map(ReloadingAcceptor::new) {
+        Some(Ok(acceptor)) => {
+            acceptor.spawn_reloader();
+            Some(acceptor)
+        }
+        Some(Err(e)) => {
+            error!("Failed to start TLS: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
 
-    info!("Listening on http://{}", addr);
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    for listener in &listeners {
+        match listener.local_addr() {
+            Ok(addr) => info!("Listening on {}://{}", scheme, addr),
+            Err(e) => warn!("Listening on unknown address: {}", e),
+        }
+    }
     info!(
-        "Endpoints: /metrics{}, /status, /health{}",
+        "Endpoints: /metrics, /metrics/influx{}, /status, /health{}",
         if state.config.websocket_enabled {
             ", /ws"
         } else {
@@ -36,74 +72,303 @@ pub async fn start(state: Arc<AppState>) {
         }
     );
 
+    state.spawn_self_metrics_refresher();
+
+    let tasks: Vec<_> = listeners
+        .into_iter()
+        .map(|listener| tokio::spawn(accept_loop(listener, tls.clone(), state.clone())))
+        .collect();
+    futures_util::future::join_all(tasks).await;
+}
+
+/// Accepts connections until the process exits.
+///
+/// Failed accepts never stop the server: per-connection errors are skipped,
+/// resource errors such as `EMFILE` are retried with a growing delay.
+pub(crate) async fn accept_loop(
+    listener: Listener,
+    tls: Option<Arc<ReloadingAcceptor>>,
+    state: Arc<AppState>,
+) {
+    let mut backoff = AcceptBackoff::new();
     loop {
-        let (stream, _peer_addr) = listener.accept().await.unwrap();
-        let state = state.clone();
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => {
+                backoff.reset();
+                accepted
+            }
+            Err(e) if listen::is_connection_error(&e) => {
+                debug!("Accept failed: {}", e);
+                continue;
+            }
+            Err(e) => {
+                let delay = backoff.next_delay();
+                error!("Accept failed, retrying in {:?}: {}", delay, e);
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+        };
+        match &tls {
+            Some(tls) => tokio::spawn(serve_tls_connection(
+                stream,
+                peer,
+                tls.acceptor(),
+                state.clone(),
+            )),
+            None => tokio::spawn(serve_connection(stream, peer, state.clone())),
+        };
+    }
+}
 
-        tokio::spawn(async move {
-            // Peek at first bytes to detect WebSocket upgrade to /ws
-            if state.config.websocket_enabled {
-                let mut peek_buf = [0u8; 256];
-                match stream.peek(&mut peek_buf).await {
-                    Ok(n) => {
-                        if n > 0 {
-                            let req_start = String::from_utf8_lossy(&peek_buf[..n]);
-                            // Check if this is a GET /ws request with WebSocket upgrade
-                            let is_ws_upgrade =
-                                req_start.contains("GET /ws") && req_start.contains("Upgrade");
-                            info!(
-                                "Peek: {} bytes, is_ws={}, first_line={:?}",
-                                n,
-                                is_ws_upgrade,
-                                req_start.lines().next().unwrap_or("")
-                            );
-                            if is_ws_upgrade {
-                                info!("Routing to WebSocket handler");
-                                handle_websocket(state, stream).await;
-                                return;
-                            }
-                        } else {
-                            warn!("Peek returned 0 bytes");
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Peek error: {}", e);
-                    }
-                }
+/// Completes the TLS handshake, then serves the connection like plain HTTP.
+///
+/// The handshake already holds a `max_connections` slot and must finish
+/// within `header_read_timeout`.
+pub(crate) async fn serve_tls_connection<I>(
+    stream: I,
+    peer: Option<IpAddr>,
+    acceptor: TlsAcceptor,
+    state: Arc<AppState>,
+) where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let stream = IdleTimeout::new(stream, state.config.limits.idle_timeout);
+    let slot = state
+        .connection_open()
+        .then(|| ConnectionSlot(state.clone()));
+
+    let handshake = acceptor.accept(stream);
+    let result = match state.config.limits.header_read_timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, handshake).await {
+            Ok(result) => result,
+            Err(_) => {
+                debug!("TLS handshake timed out");
+                return;
             }
+        },
+        None => handshake.await,
+    };
+    match result {
+        Ok(stream) => serve_http(stream, peer, state, slot).await,
+        Err(e) => debug!("TLS handshake failed: {}", e),
+    }
+}
 
-            // Regular HTTP request
-            let io = TokioIo::new(stream);
-            let state_clone = state.clone();
+/// Releases a `max_connections` slot when the connection ends.
+struct ConnectionSlot(Arc<AppState>);
 
-            let svc = service_fn(move |req: Request<hyper::body::Incoming>| {
-                let state = state_clone.clone();
-                async move { route_request(req, state).await }
-            });
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.connection_close();
+    }
+}
 
-            let _ = http1::Builder::new().serve_connection(io, svc).await;
-        });
+/// HTTP/1.1 settings shared by every connection.
+fn http1_builder(state: &AppState) -> http1::Builder {
+    let mut builder = http1::Builder::new();
+    builder
+        .timer(TokioTimer::new())
+        .header_read_timeout(state.config.limits.header_read_timeout);
+    builder
+}
+
+/// Serves HTTP/1.1 requests (with keep-alive and WebSocket upgrades) on one connection.
+///
+/// `peer` is the client address (None for connections without one).
+pub(crate) async fn serve_connection<I>(stream: I, peer: Option<IpAddr>, state: Arc<AppState>)
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let stream = IdleTimeout::new(stream, state.config.limits.idle_timeout);
+    let slot = state
+        .connection_open()
+        .then(|| ConnectionSlot(state.clone()));
+    serve_http(stream, peer, state, slot).await;
+}
+
+/// Serves a connection that already has its timeouts applied; `slot` is
+/// None if it is over `max_connections`.
+async fn serve_http<I>(
+    stream: I,
+    peer: Option<IpAddr>,
+    state: Arc<AppState>,
+    slot: Option<ConnectionSlot>,
+) where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let Some(_slot) = slot else {
+        return serve_overloaded(stream, state).await;
+    };
+
+    let builder = http1_builder(&state);
+    let svc = service_fn(move |req: Request<hyper::body::Incoming>| {
+        let state = state.clone();
+        async move { route_request(req, peer, state).await }
+    });
+
+    if let Err(e) = builder
+        .serve_connection(TokioIo::new(stream), svc)
+        .with_upgrades()
+        .await
+    {
+        debug!("Connection closed with error: {}", e);
+    }
+}
+
+/// Answers one request with `503` and closes a connection over `max_connections`.
+async fn serve_overloaded<I>(stream: I, state: Arc<AppState>)
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    debug!("Connection limit reached, rejecting connection");
+    let mut builder = http1_builder(&state);
+    builder.keep_alive(false);
+    let svc = service_fn(move |req: Request<hyper::body::Incoming>| {
+        let state = state.clone();
+        async move {
+            let start = Instant::now();
+            let response = handlers::finalize(
+                limits::unavailable("Too many connections"),
+                None,
+                None,
+                None,
+                &state.config.compression,
+            );
+            state.metrics.observe_request(
+                route_label(req.uri().path()),
+                response.status().as_u16(),
+                None,
+                start.elapsed().as_secs_f64(),
+            );
+            Ok::<_, Infallible>(response)
+        }
+    });
+    if let Err(e) = builder.serve_connection(TokioIo::new(stream), svc).await {
+        debug!("Connection closed with error: {}", e);
     }
 }
 
 /// Routes incoming HTTP requests to appropriate handlers.
 async fn route_request(
-    req: Request<hyper::body::Incoming>,
+    mut req: Request<hyper::body::Incoming>,
+    peer: Option<IpAddr>,
     state: Arc<AppState>,
 ) -> Result<Response<Full<Bytes>>, Infallible> {
-    let path = req.uri().path();
+    let start = Instant::now();
+    let route = route_label(req.uri().path());
 
-    // Check if client accepts gzip
-    let accepts_gzip = req
+    let accept_encoding = req
         .headers()
         .get("Accept-Encoding")
         .and_then(|v| v.to_str().ok())
-        .map(|v| v.contains("gzip"))
-        .unwrap_or(false);
+        .map(str::to_string);
+
+    let cors = state.config.cors_enabled.then_some(&state.config.cors);
+    let origin = req
+        .headers()
+        .get("Origin")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
 
-    // WebSocket is handled at TCP level before reaching here
-    // Route to handlers, get (Builder, Bytes) tuple
-    let response = match path {
+    // Client address behind trusted reverse proxies
+    let client = state.config.trusted_proxies.client(peer, req.headers());
+
+    // Rate limits apply before any work, including authentication
+    let limited = state
+        .config
+        .limits
+        .check_rate(req.uri().path(), client)
+        .err();
+
+    // Preflights carry no credentials, answer them before authentication
+    let preflight = cors.filter(|_| CorsPolicy::is_preflight(req.method(), req.headers()));
+    let (response, user) = match (limited, preflight) {
+        (Some(limited), _) => (limited.response(), None),
+        (None, Some(cors)) => (cors.preflight(req.headers()), None),
+        (None, None) => respond(&mut req, peer, &state).await,
+    };
+
+    // Finalize: add CORS headers if enabled, compress if configured, build response
+    let response = if preflight.is_some() {
+        handlers::finalize(response, None, None, None, &state.config.compression)
+    } else {
+        handlers::finalize(
+            response,
+            cors,
+            origin.as_deref(),
+            accept_encoding.as_deref(),
+            &state.config.compression,
+        )
+    };
+    state.metrics.observe_request(
+        route,
+        response.status().as_u16(),
+        response
+            .headers()
+            .get("Content-Encoding")
+            .and_then(|v| v.to_str().ok()),
+        start.elapsed().as_secs_f64(),
+    );
+
+    if let Some(access_log) = &state.config.access_log {
+        let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+        let protocol = format!("{:?}", req.version());
+        access_log.log(&AccessLogEntry {
+            time: Utc::now(),
+            client_ip: client,
+            user: user.as_deref(),
+            method: req.method().as_str(),
+            path: req.uri().path(),
+            protocol: &protocol,
+            status: response.status().as_u16(),
+            bytes: response.body().size_hint().exact().unwrap_or(0),
+            duration: start.elapsed(),
+            encoding: response
+                .headers()
+                .get("Content-Encoding")
+                .and_then(|v| v.to_str().ok()),
+            referer: header("Referer"),
+            user_agent: header("User-Agent"),
+        });
+    }
+    Ok(response)
+}
+
+/// Authenticates a request and calls its handler.
+///
+/// Returns the response and the authenticated user, if any.
+async fn respond(
+    req: &mut Request<hyper::body::Incoming>,
+    peer: Option<IpAddr>,
+    state: &Arc<AppState>,
+) -> (HandlerResponse, Option<String>) {
+    // Authentication runs first, so rejected clients never reach the WebSocket handshake
+    let user = match &state.config.auth {
+        Some(auth) => {
+            match auth
+                .authenticate(req.uri().path(), req.uri().query(), req.headers(), peer)
+                .await
+            {
+                Ok(user) => user,
+                Err(denied) => return (denied.response(), None),
+            }
+        }
+        None => None,
+    };
+
+    // WebSocket handshake: answer 101 here, take over the connection once hyper releases it
+    let response = if req.uri().path() == "/ws" && state.config.websocket_enabled {
+        websocket_upgrade(req, state)
+    } else {
+        dispatch(req, state).await
+    };
+    (response, user)
+}
+
+/// Calls the handler for a plain HTTP request.
+async fn dispatch(req: &Request<hyper::body::Incoming>, state: &AppState) -> HandlerResponse {
+    let path = req.uri().path();
+    match path {
         "/metrics" => {
             // Content negotiation: Accept header determines format
             let wants_json = req
@@ -114,12 +379,14 @@ async fn route_request(
                 .unwrap_or(false);
 
             if wants_json {
-                handlers::metrics_json(&state).await
+                handlers::metrics_json(state, req.headers()).await
             } else {
-                handlers::metrics_prometheus(&state)
+                handlers::metrics_prometheus(state, req.headers(), req.uri().query())
             }
         }
-        "/status" => handlers::status(&state).await,
+        "/metrics/influx" => handlers::metrics_influx(state).await,
+        "/status" => handlers::status(state).await,
+        "/chargeback" => handlers::chargeback(state, req.uri().query()).await,
         "/health" => handlers::health(),
         _ if path.starts_with("/icons/services/") => {
             // Extract service name from path: /icons/services/{service_name}
@@ -130,45 +397,154 @@ async fn route_request(
                 // URL decode the service name
                 let decoded =
                     urlencoding::decode(service_name).unwrap_or_else(|_| service_name.into());
-                handlers::icons(&state, &decoded).await
+                handlers::icons(state, &decoded).await
             }
         }
-        // Static files: serve from /static directory (for combined image)
+        // Dashboard: static_dir or the embedded export
         // Falls back to index.html for SPA routing
-        _ => handlers::static_file(path),
+        _ => handlers::static_file(&state.config.static_files, path, req.headers()).await,
+    }
+}
+
+/// Bounded `route` label for request metrics (static files share one value).
+pub(crate) fn route_label(path: &str) -> &'static str {
+    match path {
+        "/metrics" => "/metrics",
+        "/metrics/influx" => "/metrics/influx",
+        "/status" => "/status",
+        "/chargeback" => "/chargeback",
+        "/health" => "/health",
+        "/ws" => "/ws",
+        _ if path.starts_with("/icons/services/") => "/icons/services",
+        _ => "static",
+    }
+}
+
+/// Validates a WebSocket handshake (RFC 6455) and schedules the upgrade.
+///
+/// Returns `101 Switching Protocols` on success; the socket is handed to
+/// `handle_websocket` once hyper has sent the response.
+fn websocket_upgrade(
+    req: &mut Request<hyper::body::Incoming>,
+    state: &Arc<AppState>,
+) -> HandlerResponse {
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
     };
+    let has_token = |value: &str, token: &str| {
+        value
+            .split(',')
+            .any(|t| t.trim().eq_ignore_ascii_case(token))
+    };
+
+    if req.method() != Method::GET
+        || !has_token(header("Connection"), "upgrade")
+        || !has_token(header("Upgrade"), "websocket")
+    {
+        return (
+            Response::builder()
+                .status(StatusCode::UPGRADE_REQUIRED)
+                .header("Connection", "Upgrade")
+                .header("Upgrade", "websocket"),
+            Bytes::from("WebSocket upgrade required"),
+        );
+    }
+    if header("Sec-WebSocket-Version") != "13" {
+        return (
+            Response::builder()
+                .status(StatusCode::UPGRADE_REQUIRED)
+                .header("Sec-WebSocket-Version", "13"),
+            Bytes::from("Unsupported WebSocket version"),
+        );
+    }
+    let key = header("Sec-WebSocket-Key").trim();
+    let valid_key = STANDARD.decode(key).is_ok_and(|nonce| nonce.len() == 16);
+    if !valid_key {
+        return (
+            Response::builder().status(StatusCode::BAD_REQUEST),
+            Bytes::from("Invalid Sec-WebSocket-Key"),
+        );
+    }
+    let accept = derive_accept_key(key.as_bytes());
+    let protocol = select_protocol(header("Sec-WebSocket-Protocol"));
+
+    // Reserve the client slot now, so concurrent handshakes cannot exceed the limit
+    if state.ws_client_try_connect().is_none() {
+        return limits::unavailable("Too many WebSocket clients");
+    }
+
+    let upgrade = hyper::upgrade::on(req);
+    let state = state.clone();
+    tokio::spawn(async move {
+        match upgrade.await {
+            Ok(upgraded) => {
+                let ws_stream =
+                    WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, None)
+                        .await;
+                handle_websocket(state, ws_stream).await;
+            }
+            Err(e) => {
+                state.ws_client_disconnect();
+                debug!("WebSocket upgrade failed: {}", e);
+            }
+        }
+    });
 
-    // Finalize: add CORS headers if enabled, gzip if configured, build response
-    Ok(handlers::finalize(
-        response,
-        state.config.cors_enabled,
-        accepts_gzip,
-        &state.config.gzip,
-    ))
+    let mut builder = Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "websocket")
+        .header("Sec-WebSocket-Accept", accept);
+    if let Some(protocol) = protocol {
+        builder = builder.header("Sec-WebSocket-Protocol", protocol);
+    }
+    (builder, Bytes::new())
+}
+
+/// Picks the subprotocol echoed to the client.
+///
+/// Browsers fail the connection unless one of their offered protocols is
+/// echoed; prefer one that is not a `bearer.<token>` credential.
+fn select_protocol(offered: &str) -> Option<String> {
+    let protocols: Vec<&str> = offered
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    protocols
+        .iter()
+        .find(|p| !p.starts_with(WS_TOKEN_PROTOCOL))
+        .or(protocols.first())
+        .map(|p| p.to_string())
 }
 
-/// Handles WebSocket connections.
-pub async fn handle_websocket(state: Arc<AppState>, stream: tokio::net::TcpStream) {
+/// Handles an established WebSocket connection.
+///
+/// The client must already be counted (`AppState::ws_client_try_connect`);
+/// it is removed from the count when the connection ends.
+pub async fn handle_websocket<S>(state: Arc<AppState>, ws_stream: WebSocketStream<S>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     use crate::types::{ApiStatus, WsMessage, WsStatus};
     use crate::utils::ProcessInfoProvider;
 
-    info!("Starting WebSocket handshake...");
-    let ws_stream = match tokio_tungstenite::accept_async(stream).await {
-        Ok(ws) => {
-            info!("WebSocket handshake successful!");
-            ws
-        }
-        Err(e) => {
-            warn!("WebSocket handshake failed: {}", e);
-            return;
-        }
-    };
-
-    // Track client count
-    let client_count = state.ws_client_connect();
-    info!("WebSocket client connected (total: {})", client_count);
+    debug!(
+        "WebSocket client connected (total: {})",
+        state.ws_client_count()
+    );
 
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+    let ws_messages = |direction: &str| {
+        state
+            .metrics
+            .ws_messages
+            .with_label_values(&[direction])
+            .inc()
+    };
 
     // Process info provider for memory/cpu stats
     let process_info = ProcessInfoProvider::new();
@@ -200,27 +576,29 @@ pub async fn handle_websocket(state: Arc<AppState>, stream: tokio::net::TcpStrea
         // Send status first
         let status_msg = WsMessage::Status(build_status(&state, &api_status, &process_info));
         if let Ok(json) = serde_json::to_string(&status_msg) {
-            info!("Sending initial status ({} bytes)", json.len());
+            debug!("Sending initial status ({} bytes)", json.len());
             if let Err(e) = ws_sender.send(Message::Text(json.into())).await {
                 warn!("Failed to send initial status: {}", e);
                 state.ws_client_disconnect();
                 return;
             }
+            ws_messages("sent");
         }
 
         // Then send metrics if available
         if let Some(metrics) = state.metrics_json.read().await.as_ref() {
             let metrics_msg = WsMessage::Metrics(metrics.clone());
             if let Ok(json) = serde_json::to_string(&metrics_msg) {
-                info!("Sending initial metrics ({} bytes)", json.len());
+                debug!("Sending initial metrics ({} bytes)", json.len());
                 if let Err(e) = ws_sender.send(Message::Text(json.into())).await {
                     warn!("Failed to send initial metrics: {}", e);
                     state.ws_client_disconnect();
                     return;
                 }
+                ws_messages("sent");
             }
         } else {
-            info!("No metrics available yet");
+            debug!("No metrics available yet");
         }
     }
 
@@ -237,23 +615,30 @@ pub async fn handle_websocket(state: Arc<AppState>, stream: tokio::net::TcpStrea
                 let api_status = state.api_status.read().await;
                 let status_msg = WsMessage::Status(build_status(&state, &api_status, &process_info));
                 if let Ok(json) = serde_json::to_string(&status_msg) {
-                    info!("Sending periodic status update");
+                    debug!("Sending periodic status update");
                     if ws_sender.send(Message::Text(json.into())).await.is_err() {
-                        info!("Client disconnected during status send");
+                        debug!("Client disconnected during status send");
                         state.ws_client_disconnect();
                         break;
                     }
+                    ws_messages("sent");
                 }
             }
             // Handle incoming messages (for ping/pong)
             msg = ws_receiver.next() => {
+                if matches!(msg, Some(Ok(_))) {
+                    ws_messages("received");
+                }
                 match msg {
                     Some(Ok(Message::Ping(data))) => {
-                        let _ = ws_sender.send(Message::Pong(data)).await;
+                        let sent = ws_sender.send(Message::Pong(data)).await.is_ok();
+                        if sent {
+                            ws_messages("sent");
+                        }
                     }
                     Some(Ok(Message::Close(_))) | None => {
                         let remaining = state.ws_client_disconnect();
-                        info!("WebSocket client disconnected (remaining: {})", remaining);
+                        debug!("WebSocket client disconnected (remaining: {})", remaining);
                         break;
                     }
                     _ => {}
@@ -267,6 +652,7 @@ pub async fn handle_websocket(state: Arc<AppState>, stream: tokio::net::TcpStrea
                             state.ws_client_disconnect();
                             break;
                         }
+                        ws_messages("sent");
                     }
                     Err(_) => {
                         state.ws_client_disconnect();
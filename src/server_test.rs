@@ -4,6 +4,7 @@
 
 use crate::config::Plan;
 use crate::state::AppState;
+use crate::test_support::serve_state;
 use crate::Config;
 use std::sync::Arc;
 use std::time::Duration;
@@ -12,85 +13,11 @@ use std::time::Duration;
 // Server Integration Tests
 // =============================================================================
 
-/// Starts server on random port and returns the address.
+/// Starts the real connection handler on a random port.
 async fn start_test_server() -> (Arc<AppState>, String) {
     let config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
     let state = Arc::new(AppState::new(config));
-
-    // Bind to port 0 to get a random available port
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let addr = listener.local_addr().unwrap();
-    let url = format!("http://{}", addr);
-
-    let state_clone = state.clone();
-
-    // Start server in background
-    tokio::spawn(async move {
-        use hyper::server::conn::http1;
-        use hyper::service::service_fn;
-        use hyper_util::rt::TokioIo;
-
-        loop {
-            let (stream, _) = match listener.accept().await {
-                Ok(conn) => conn,
-                Err(_) => break,
-            };
-            let state = state_clone.clone();
-
-            tokio::spawn(async move {
-                let io = TokioIo::new(stream);
-                let state_svc = state.clone();
-
-                let svc = service_fn(move |req| {
-                    let state = state_svc.clone();
-                    async move {
-                        use crate::handlers;
-
-                        let path = req.uri().path();
-                        let accepts_gzip = req
-                            .headers()
-                            .get("Accept-Encoding")
-                            .and_then(|v| v.to_str().ok())
-                            .map(|v| v.contains("gzip"))
-                            .unwrap_or(false);
-
-                        let response = match path {
-                            "/metrics" => {
-                                let wants_json = req
-                                    .headers()
-                                    .get("Accept")
-                                    .and_then(|v| v.to_str().ok())
-                                    .map(|v| v.contains("application/json"))
-                                    .unwrap_or(false);
-
-                                if wants_json {
-                                    handlers::metrics_json(&state).await
-                                } else {
-                                    handlers::metrics_prometheus(&state)
-                                }
-                            }
-                            "/status" | "/" => handlers::status(&state).await,
-                            "/health" => handlers::health(),
-                            _ => handlers::not_found(),
-                        };
-
-                        Ok::<_, std::convert::Infallible>(handlers::finalize(
-                            response,
-                            state.config.cors_enabled,
-                            accepts_gzip,
-                            &state.config.gzip,
-                        ))
-                    }
-                });
-
-                let _ = http1::Builder::new().serve_connection(io, svc).await;
-            });
-        }
-    });
-
-    // Wait for server to be ready
-    tokio::time::sleep(Duration::from_millis(10)).await;
-
+    let url = format!("http://{}", serve_state(state.clone()).await);
     (state, url)
 }
 
@@ -124,16 +51,15 @@ async fn test_server_status_endpoint() {
 }
 
 #[tokio::test]
-async fn test_server_root_endpoint_redirects_to_status() {
+async fn test_server_root_endpoint_serves_dashboard() {
     let (_state, url) = start_test_server().await;
 
     let client = reqwest::Client::new();
     let resp = client.get(format!("{}/", url)).send().await.unwrap();
 
-    assert_eq!(resp.status(), 200);
-
-    let json: serde_json::Value = resp.json().await.unwrap();
-    assert!(json["version"].is_string());
+    // `/` belongs to the dashboard (none configured here), not to /status
+    assert_eq!(resp.status(), 404);
+    assert_eq!(resp.text().await.unwrap(), "Static files not available");
 }
 
 #[tokio::test]
@@ -157,6 +83,72 @@ async fn test_server_metrics_prometheus() {
     assert!(body.contains("railway_"));
 }
 
+#[tokio::test]
+async fn test_server_metrics_federation_filter() {
+    let (state, url) = start_test_server().await;
+    for (group, cost) in [("database", 3.0), ("frontend", 1.0)] {
+        state
+            .metrics
+            .service_cost
+            .with_label_values(&[group, "proj", "", group])
+            .set(cost);
+    }
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!(
+            "{}/metrics?name%5B%5D=railway_service_cost_usd&match[]={}",
+            url,
+            urlencoding::encode(r#"{group="database"}"#)
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let body = resp.text().await.unwrap();
+    let samples: Vec<&str> = body.lines().filter(|l| !l.starts_with('#')).collect();
+    assert_eq!(samples.len(), 1);
+    assert!(samples[0].contains("group=\"database\""));
+
+    let resp = client
+        .get(format!("{}/metrics?match[]=%7Bgroup%3D", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn test_server_metrics_openmetrics() {
+    let (state, url) = start_test_server().await;
+    state.refresh_metrics_cache().await;
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{}/metrics", url))
+        .header(
+            "Accept",
+            "application/openmetrics-text;version=1.0.0,text/plain;version=0.0.4;q=0.5,*/*;q=0.1",
+        )
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), 200);
+    let content_type = resp
+        .headers()
+        .get("content-type")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(content_type.starts_with("application/openmetrics-text"));
+
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("# TYPE railway_exporter_memory_bytes gauge"));
+    assert!(body.ends_with("# EOF\n"));
+}
+
 #[tokio::test]
 async fn test_server_metrics_json() {
     let (state, url) = start_test_server().await;
@@ -175,6 +167,8 @@ async fn test_server_metrics_json() {
                 days_remaining: 20,
             },
             services: vec![],
+            groups: vec![],
+            allocated: vec![],
             scrape_timestamp: 1700000000,
             scrape_duration_seconds: 0.1,
         });
@@ -271,83 +265,37 @@ async fn test_server_multiple_concurrent_requests() {
 // WebSocket Server Tests
 // =============================================================================
 
-/// Starts server with WebSocket support on random port.
+/// Starts the real connection handler with WebSocket support on random port.
 async fn start_ws_test_server() -> (Arc<AppState>, String) {
-    use crate::server::handle_websocket;
-    use hyper::server::conn::http1;
-    use hyper::service::service_fn;
-    use hyper_util::rt::TokioIo;
-
     let mut config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
     config.websocket_enabled = true;
     let state = Arc::new(AppState::new(config));
+    let url = format!("ws://{}", serve_state(state.clone()).await);
+    (state, url)
+}
 
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let addr = listener.local_addr().unwrap();
-    let url = format!("ws://{}", addr);
-
-    let state_clone = state.clone();
-
-    tokio::spawn(async move {
-        loop {
-            let (stream, _) = match listener.accept().await {
-                Ok(conn) => conn,
-                Err(_) => break,
-            };
-            let state = state_clone.clone();
-
-            tokio::spawn(async move {
-                // Peek to detect WebSocket upgrade
-                let mut peek_buf = [0u8; 256];
-                if let Ok(n) = stream.peek(&mut peek_buf).await {
-                    if n > 0 {
-                        let req_start = String::from_utf8_lossy(&peek_buf[..n]);
-                        if req_start.contains("GET /ws") && req_start.contains("Upgrade") {
-                            handle_websocket(state, stream).await;
-                            return;
-                        }
-                    }
-                }
-
-                // Regular HTTP
-                let io = TokioIo::new(stream);
-                let state_svc = state.clone();
-
-                let svc = service_fn(move |req| {
-                    let state = state_svc.clone();
-                    async move {
-                        use crate::handlers;
-
-                        let path = req.uri().path();
-                        let accepts_gzip = req
-                            .headers()
-                            .get("Accept-Encoding")
-                            .and_then(|v| v.to_str().ok())
-                            .map(|v| v.contains("gzip"))
-                            .unwrap_or(false);
-
-                        let response = match path {
-                            "/health" => handlers::health(),
-                            _ => handlers::not_found(),
-                        };
-
-                        Ok::<_, std::convert::Infallible>(handlers::finalize(
-                            response,
-                            state.config.cors_enabled,
-                            accepts_gzip,
-                            &state.config.gzip,
-                        ))
-                    }
-                });
-
-                let _ = http1::Builder::new().serve_connection(io, svc).await;
-            });
-        }
-    });
-
-    tokio::time::sleep(Duration::from_millis(10)).await;
+/// Reads one HTTP/1.1 response (headers + Content-Length body) from a raw stream.
+async fn read_response(stream: &mut tokio::net::TcpStream) -> (String, String) {
+    use tokio::io::AsyncReadExt;
 
-    (state, url)
+    let mut buf = Vec::new();
+    let mut byte = [0u8; 1];
+    while !buf.ends_with(b"\r\n\r\n") {
+        stream.read_exact(&mut byte).await.unwrap();
+        buf.push(byte[0]);
+    }
+    let head = String::from_utf8(buf).unwrap();
+    let length = head
+        .lines()
+        .find_map(|l| {
+            let (name, value) = l.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse::<usize>().unwrap())
+        })
+        .unwrap_or(0);
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body).await.unwrap();
+    (head, String::from_utf8(body).unwrap())
 }
 
 #[tokio::test]
@@ -371,6 +319,8 @@ async fn test_websocket_connection() {
                 days_remaining: 25,
             },
             services: vec![],
+            groups: vec![],
+            allocated: vec![],
             scrape_timestamp: 1700000000,
             scrape_duration_seconds: 0.05,
         });
@@ -397,6 +347,13 @@ async fn test_websocket_connection() {
 
     // Check client count was incremented
     assert_eq!(state.ws_client_count(), 1);
+
+    // Self-observability counters
+    assert_eq!(state.metrics.ws_connections.get(), 1);
+    assert_eq!(
+        state.metrics.ws_messages.with_label_values(&["sent"]).get(),
+        2
+    );
 }
 
 #[tokio::test]
@@ -452,10 +409,8 @@ async fn test_websocket_ping_pong() {
     // Note: The pong might come interleaved with status updates
     let timeout = tokio::time::timeout(Duration::from_secs(2), async {
         loop {
-            if let Some(Ok(msg)) = ws_stream.next().await {
-                if let Message::Pong(data) = msg {
-                    return data;
-                }
+            if let Some(Ok(Message::Pong(data))) = ws_stream.next().await {
+                return data;
             }
         }
     });
@@ -502,3 +457,114 @@ async fn test_websocket_broadcast() {
         Err(_) => panic!("Timeout waiting for broadcast"),
     }
 }
+
+#[tokio::test]
+async fn test_websocket_upgrade_after_keep_alive_request() {
+    use futures_util::StreamExt;
+    use tokio::io::AsyncWriteExt;
+
+    let (state, url) = start_ws_test_server().await;
+    let addr = url.trim_start_matches("ws://");
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+
+    // Two plain requests on the same connection
+    for _ in 0..2 {
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: test\r\n\r\n")
+            .await
+            .unwrap();
+        let (head, _body) = read_response(&mut stream).await;
+        assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+    }
+
+    // Then upgrade the same connection
+    let (mut ws_stream, response) = tokio_tungstenite::client_async(format!("{}/ws", url), stream)
+        .await
+        .expect("Failed to upgrade");
+    assert_eq!(response.status(), 101);
+    let msg = ws_stream.next().await.unwrap().unwrap();
+    assert!(msg.into_text().unwrap().contains("\"status\""));
+    assert_eq!(state.ws_client_count(), 1);
+}
+
+#[tokio::test]
+async fn test_websocket_large_headers() {
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+    let (_state, url) = start_ws_test_server().await;
+    let mut request = format!("{}/ws", url).into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert("X-Forwarded-Padding", "x".repeat(4096).parse().unwrap());
+
+    let (_ws, response) = tokio_tungstenite::connect_async(request)
+        .await
+        .expect("Failed to connect");
+    assert_eq!(response.status(), 101);
+}
+
+#[tokio::test]
+async fn test_websocket_invalid_handshake() {
+    let (state, url) = start_ws_test_server().await;
+    let http_url = format!("{}/ws", url.replace("ws://", "http://"));
+    let client = reqwest::Client::new();
+
+    // Plain GET without upgrade
+    let resp = client.get(&http_url).send().await.unwrap();
+    assert_eq!(resp.status(), 426);
+
+    let upgrade = |version: &'static str, key: &'static str| {
+        client
+            .get(&http_url)
+            .header("Connection", "keep-alive, Upgrade")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Version", version)
+            .header("Sec-WebSocket-Key", key)
+    };
+
+    let resp = upgrade("8", "dGhlIHNhbXBsZSBub25jZQ==")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 426);
+    assert_eq!(resp.headers()["sec-websocket-version"], "13");
+
+    let resp = upgrade("13", "c2hvcnQ=").send().await.unwrap();
+    assert_eq!(resp.status(), 400);
+
+    assert_eq!(state.ws_client_count(), 0);
+    assert_eq!(
+        state
+            .metrics
+            .http_requests
+            .with_label_values(&["/ws", "426", "identity"])
+            .get(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_websocket_disabled() {
+    use crate::server::serve_connection;
+
+    let mut config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
+    config.websocket_enabled = false;
+    let state = Arc::new(AppState::new(config));
+    let (client, server) = tokio::io::duplex(4096);
+    tokio::spawn(serve_connection(server, None, state));
+
+    let result = tokio_tungstenite::client_async("ws://test/ws", client).await;
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_route_label() {
+    use crate::server::route_label;
+
+    assert_eq!(route_label("/metrics"), "/metrics");
+    assert_eq!(route_label("/metrics/influx"), "/metrics/influx");
+    assert_eq!(route_label("/ws"), "/ws");
+    assert_eq!(route_label("/icons/services/web%20app"), "/icons/services");
+    assert_eq!(route_label("/_next/static/chunk.js"), "static");
+    assert_eq!(route_label("/"), "static");
+}
@@ -0,0 +1,115 @@
+//! InfluxDB v2 sink.
+//!
+//! Writes every collected snapshot as line protocol (see `crate::influx`) to
+//! `/api/v2/write` with second precision, so points carry the snapshot's
+//! `scrape_timestamp`. Queueing and retries are handled by `sinks::push`.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! sinks:
+//!   influxdb:
+//!     url: http://influxdb:8086  # server URL (without /api/v2/write)
+//!     org: my-org
+//!     bucket: railway
+//!     token: "secret"            # API token (optional for open servers)
+//!     max_retries: 3             # delivery settings, see `sinks::push`
+//! ```
+
+use super::push::{self, DeliveryConfig, HttpPusher, Payload, PushTarget};
+use crate::config::ConfigError;
+use crate::influx;
+use crate::types::MetricsJson;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// InfluxDB v2 settings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InfluxDbConfig {
+    /// InfluxDB server URL.
+    pub url: String,
+    /// Organization name or ID.
+    pub org: String,
+    /// Destination bucket.
+    pub bucket: String,
+    /// API token sent as `Authorization: Token ...`.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Timeouts, retries and queueing.
+    #[serde(flatten)]
+    pub delivery: DeliveryConfig,
+}
+
+impl InfluxDbConfig {
+    /// Creates a config with default settings for the given server and bucket.
+    pub fn new(url: &str, org: &str, bucket: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            org: org.to_string(),
+            bucket: bucket.to_string(),
+            token: None,
+            delivery: DeliveryConfig::default(),
+        }
+    }
+
+    /// Checks URL, org, bucket and limits.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        push::validate_url(&self.url, "sinks.influxdb.url")?;
+        if self.org.is_empty() || self.bucket.is_empty() {
+            return Err(ConfigError::InvalidValue(
+                "sinks.influxdb: org and bucket must not be empty".to_string(),
+            ));
+        }
+        self.delivery.validate("sinks.influxdb")
+    }
+
+    /// Returns the write endpoint URL.
+    pub fn write_url(&self) -> String {
+        format!(
+            "{}/api/v2/write?org={}&bucket={}&precision=s",
+            self.url.trim_end_matches('/'),
+            urlencoding::encode(&self.org),
+            urlencoding::encode(&self.bucket)
+        )
+    }
+}
+
+/// Handle to the background InfluxDB worker.
+#[derive(Debug)]
+pub struct InfluxDbWriter {
+    pusher: HttpPusher,
+}
+
+impl InfluxDbWriter {
+    /// Starts the background worker. Must be called inside a Tokio runtime.
+    pub fn spawn(config: InfluxDbConfig) -> Self {
+        let mut headers = Vec::new();
+        if let Some(token) = &config.token {
+            headers.push(("Authorization".to_string(), format!("Token {}", token)));
+        }
+        let pusher = HttpPusher::spawn(PushTarget {
+            name: "influxdb",
+            method: Method::POST,
+            url: config.write_url(),
+            headers,
+            delivery: config.delivery,
+        });
+        Self { pusher }
+    }
+
+    /// Queues a snapshot for writing.
+    ///
+    /// Returns false if the queue is full and the snapshot was dropped.
+    pub fn push(&self, json: &MetricsJson) -> bool {
+        self.pusher.enqueue(Payload {
+            url: None,
+            body: influx::encode(json).into_bytes(),
+            headers: vec![("Content-Type", influx::CONTENT_TYPE)],
+        })
+    }
+
+    /// Waits until queued snapshots are delivered.
+    pub async fn close(self) {
+        self.pusher.close().await;
+    }
+}
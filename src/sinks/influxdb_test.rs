@@ -0,0 +1,67 @@
+//! Tests for the InfluxDB sink.
+
+use super::test_support::{next_request, start_capture_server};
+use crate::sinks::influxdb::{InfluxDbConfig, InfluxDbWriter};
+use crate::types::{MetricsJson, ProjectSummary};
+
+#[test]
+fn test_write_url() {
+    let config = InfluxDbConfig::new("http://influxdb:8086/", "my org", "railway");
+    assert_eq!(
+        config.write_url(),
+        "http://influxdb:8086/api/v2/write?org=my%20org&bucket=railway&precision=s"
+    );
+}
+
+#[test]
+fn test_validate() {
+    assert!(InfluxDbConfig::new("http://influxdb:8086", "org", "bucket")
+        .validate()
+        .is_ok());
+    assert!(InfluxDbConfig::new("influxdb:8086", "org", "bucket")
+        .validate()
+        .is_err());
+    assert!(InfluxDbConfig::new("http://influxdb:8086", "", "bucket")
+        .validate()
+        .is_err());
+    assert!(InfluxDbConfig::new("http://influxdb:8086", "org", "")
+        .validate()
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_push_writes_line_protocol() {
+    let (url, mut rx) = start_capture_server(0).await;
+    let mut config = InfluxDbConfig::new(&url, "org", "railway");
+    config.token = Some("secret".to_string());
+    let writer = InfluxDbWriter::spawn(config);
+
+    let json = MetricsJson {
+        project: ProjectSummary {
+            name: "proj".to_string(),
+            current_usage_usd: 10.0,
+            estimated_monthly_usd: 30.0,
+            daily_average_usd: 1.0,
+            days_elapsed: 10,
+            days_remaining: 20,
+        },
+        services: vec![],
+        groups: vec![],
+        allocated: vec![],
+        scrape_timestamp: 1700000000,
+        scrape_duration_seconds: 0.1,
+    };
+    assert!(writer.push(&json));
+
+    let received = next_request(&mut rx).await;
+    assert_eq!(
+        received.uri,
+        "/api/v2/write?org=org&bucket=railway&precision=s"
+    );
+    assert_eq!(received.header("authorization"), "Token secret");
+    let body = received.text();
+    assert!(body.starts_with("railway_project,project=proj current_usage_usd=10,"));
+    assert!(body.contains(" 1700000000\n"));
+
+    writer.close().await;
+}
@@ -0,0 +1,231 @@
+//! Push sinks.
+//!
+//! Besides being scraped on `/metrics`, the exporter can push every collected
+//! snapshot to external systems. All sinks are configured under `sinks:` and
+//! fed by the same post-collection hook (`Sinks::push`), which runs after
+//! every collection attempt — failed ones included, so `railway_api_up`
+//! reaches the receiver.
+//!
+//! | Sink | Module | Transport |
+//! |------|--------|-----------|
+//! | `remote_write` | `remote_write` | Prometheus remote_write (protobuf + snappy) |
+//! | `otlp` | `otlp` | OTLP/HTTP (protobuf or JSON) |
+//! | `pushgateway` | `pushgateway` | Prometheus Pushgateway (text format) |
+//! | `statsd` | `statsd` | StatsD / DogStatsD over UDP |
+//! | `influxdb` | `influxdb` | InfluxDB v2 `/api/v2/write` (line protocol) |
+//!
+//! HTTP sinks share queueing and retries (`push`).
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! sinks:
+//!   remote_write:
+//!     url: https://prometheus.example.com/api/v1/write
+//!   otlp:
+//!     endpoint: http://otel-collector:4318/v1/metrics
+//!   pushgateway:
+//!     url: http://pushgateway:9091
+//!   statsd:
+//!     address: 127.0.0.1:8125
+//!   influxdb:
+//!     url: http://influxdb:8086
+//!     org: my-org
+//!     bucket: railway
+//! ```
+
+pub mod influxdb;
+pub mod otlp;
+pub mod push;
+pub mod pushgateway;
+pub mod remote_write;
+pub mod statsd;
+
+use crate::config::ConfigError;
+use crate::state::AppState;
+use influxdb::{InfluxDbConfig, InfluxDbWriter};
+use otlp::{OtlpConfig, OtlpExporter};
+use pushgateway::{PushgatewayConfig, PushgatewaySink};
+use remote_write::{RemoteWriteConfig, RemoteWriter};
+use serde::{Deserialize, Serialize};
+use statsd::{StatsdConfig, StatsdSink};
+use tracing::{error, info};
+
+/// `sinks:` section.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SinksConfig {
+    #[serde(default)]
+    pub remote_write: Option<RemoteWriteConfig>,
+    #[serde(default)]
+    pub otlp: Option<OtlpConfig>,
+    #[serde(default)]
+    pub pushgateway: Option<PushgatewayConfig>,
+    #[serde(default)]
+    pub statsd: Option<StatsdConfig>,
+    #[serde(default)]
+    pub influxdb: Option<InfluxDbConfig>,
+}
+
+impl SinksConfig {
+    /// Validates every configured sink.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(ref c) = self.remote_write {
+            c.validate()?;
+        }
+        if let Some(ref c) = self.otlp {
+            c.validate()?;
+        }
+        if let Some(ref c) = self.pushgateway {
+            c.validate()?;
+        }
+        if let Some(ref c) = self.statsd {
+            c.validate()?;
+        }
+        if let Some(ref c) = self.influxdb {
+            c.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if no sink is configured.
+    pub fn is_empty(&self) -> bool {
+        self.remote_write.is_none()
+            && self.otlp.is_none()
+            && self.pushgateway.is_none()
+            && self.statsd.is_none()
+            && self.influxdb.is_none()
+    }
+}
+
+/// Running sinks.
+#[derive(Debug, Default)]
+pub struct Sinks {
+    remote_write: Option<RemoteWriter>,
+    otlp: Option<OtlpExporter>,
+    pushgateway: Option<PushgatewaySink>,
+    statsd: Option<StatsdSink>,
+    influxdb: Option<InfluxDbWriter>,
+}
+
+impl Sinks {
+    /// Starts every configured sink. Must be called inside a Tokio runtime.
+    ///
+    /// A StatsD address that doesn't resolve is logged and that sink skipped.
+    pub fn spawn(config: &SinksConfig) -> Self {
+        Self {
+            remote_write: config.remote_write.clone().map(|c| {
+                info!("Sink remote_write: {}", c.url);
+                RemoteWriter::spawn(c)
+            }),
+            otlp: config.otlp.clone().map(|c| {
+                info!("Sink otlp: {} ({:?})", c.endpoint, c.protocol);
+                OtlpExporter::spawn(c)
+            }),
+            pushgateway: config.pushgateway.clone().map(|c| {
+                info!("Sink pushgateway: {}", c.group_url("<project>"));
+                PushgatewaySink::spawn(c)
+            }),
+            statsd: config.statsd.clone().and_then(|c| {
+                let address = c.address.clone();
+                match StatsdSink::new(c) {
+                    Ok(sink) => {
+                        info!("Sink statsd: {}", address);
+                        Some(sink)
+                    }
+                    Err(e) => {
+                        error!("Sink statsd disabled ({}): {}", address, e);
+                        None
+                    }
+                }
+            }),
+            influxdb: config.influxdb.clone().map(|c| {
+                info!("Sink influxdb: {} (bucket {})", c.url, c.bucket);
+                InfluxDbWriter::spawn(c)
+            }),
+        }
+    }
+
+    /// Returns true if no sink is running.
+    pub fn is_empty(&self) -> bool {
+        self.remote_write.is_none()
+            && self.otlp.is_none()
+            && self.pushgateway.is_none()
+            && self.statsd.is_none()
+            && self.influxdb.is_none()
+    }
+
+    /// Post-collection hook: pushes the current snapshot to every sink.
+    ///
+    /// Registry-based sinks get the gauges; InfluxDB gets the JSON snapshot
+    /// (skipped until the first successful collection).
+    pub async fn push(&self, state: &AppState) {
+        if self.is_empty() {
+            return;
+        }
+        let metrics = &state.metrics;
+        metrics.update_process_metrics();
+        let now = chrono::Utc::now().timestamp_millis();
+        if let Some(sink) = &self.remote_write {
+            sink.push(metrics, now);
+        }
+        if let Some(sink) = &self.otlp {
+            sink.push(metrics, now);
+        }
+        if let Some(sink) = &self.pushgateway {
+            // Group by the collected name, which the series carry as `project`
+            let project = match state.metrics_json.read().await.as_ref() {
+                Some(json) => json.project.name.clone(),
+                None => state.config.project_name.clone(),
+            };
+            sink.push(metrics, &project);
+        }
+        if let Some(sink) = &self.statsd {
+            sink.push(metrics);
+        }
+        if let Some(sink) = &self.influxdb {
+            if let Some(json) = state.metrics_json.read().await.as_ref() {
+                sink.push(json);
+            }
+        }
+    }
+
+    /// Waits until HTTP sinks have delivered their queued snapshots.
+    pub async fn close(self) {
+        if let Some(sink) = self.remote_write {
+            sink.close().await;
+        }
+        if let Some(sink) = self.otlp {
+            sink.close().await;
+        }
+        if let Some(sink) = self.pushgateway {
+            sink.close().await;
+        }
+        if let Some(sink) = self.influxdb {
+            sink.close().await;
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "test_support.rs"]
+mod test_support;
+
+#[cfg(test)]
+#[path = "remote_write_test.rs"]
+mod remote_write_test;
+
+#[cfg(test)]
+#[path = "otlp_test.rs"]
+mod otlp_test;
+
+#[cfg(test)]
+#[path = "pushgateway_test.rs"]
+mod pushgateway_test;
+
+#[cfg(test)]
+#[path = "statsd_test.rs"]
+mod statsd_test;
+
+#[cfg(test)]
+#[path = "influxdb_test.rs"]
+mod influxdb_test;
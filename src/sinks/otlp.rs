@@ -0,0 +1,588 @@
+//! OTLP metrics export.
+//!
+//! Pushes the metrics registry to an OpenTelemetry collector via OTLP/HTTP
+//! after every collection, in protobuf or JSON encoding. Runs alongside the
+//! Prometheus endpoint; queueing and retries are handled by `sinks::push`.
+//!
+//! ## Mapping
+//!
+//! | Prometheus | OTLP |
+//! |------------|------|
+//! | gauge | Gauge (`asDouble`) |
+//! | counter | monotonic cumulative Sum |
+//! | histogram | cumulative explicit-bucket Histogram |
+//! | summary | Summary |
+//! | labels (`project`, `service`, `group`, ...) | data-point attributes |
+//!
+//! `project` stays on the data points because its value differs between
+//! series (`railway_api_up` carries the project ID, the rest the collected
+//! project name). The resource carries `service.name` / `service.version` of
+//! the exporter plus any configured `resource_attributes`. Units are derived from
+//! metric name suffixes (`_usd` → `{USD}`, `_seconds` → `s`, ...).
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! sinks:
+//!   otlp:
+//!     endpoint: http://otel-collector:4318/v1/metrics   # default: http://localhost:4318/v1/metrics
+//!     protocol: http/protobuf     # or http/json
+//!     headers:
+//!       Authorization: "Api-Token secret"
+//!     resource_attributes:
+//!       deployment.environment: production
+//!     max_retries: 3              # delivery settings, see `sinks::push`
+//! ```
+
+use super::push::{self, DeliveryConfig, HttpPusher, Payload, PushTarget};
+use crate::config::ConfigError;
+use crate::exposition::unit_for;
+use crate::metrics::Metrics;
+use crate::utils::protobuf::{write_bytes, write_double, write_fixed64, write_string, write_uint};
+use prometheus::proto::{Metric, MetricFamily, MetricType};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// `AGGREGATION_TEMPORALITY_CUMULATIVE`.
+const CUMULATIVE: u64 = 2;
+
+/// OTLP/HTTP payload encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum OtlpProtocol {
+    #[default]
+    #[serde(rename = "http/protobuf")]
+    HttpProtobuf,
+    #[serde(rename = "http/json")]
+    HttpJson,
+}
+
+/// OTLP exporter settings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OtlpConfig {
+    /// Collector metrics endpoint (including `/v1/metrics`).
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+    /// Payload encoding.
+    #[serde(default)]
+    pub protocol: OtlpProtocol,
+    /// Extra request headers (auth, tenant IDs, ...).
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Extra resource attributes.
+    #[serde(default)]
+    pub resource_attributes: BTreeMap<String, String>,
+    /// Timeouts, retries and queueing.
+    #[serde(flatten)]
+    pub delivery: DeliveryConfig,
+}
+
+fn default_endpoint() -> String {
+    "http://localhost:4318/v1/metrics".to_string()
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: default_endpoint(),
+            protocol: OtlpProtocol::default(),
+            headers: BTreeMap::new(),
+            resource_attributes: BTreeMap::new(),
+            delivery: DeliveryConfig::default(),
+        }
+    }
+}
+
+impl OtlpConfig {
+    /// Checks endpoint and limits.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        push::validate_url(&self.endpoint, "sinks.otlp.endpoint")?;
+        self.delivery.validate("sinks.otlp")
+    }
+}
+
+/// Handle to the background OTLP worker.
+#[derive(Debug)]
+pub struct OtlpExporter {
+    pusher: HttpPusher,
+    protocol: OtlpProtocol,
+    resource: Vec<(String, String)>,
+}
+
+impl OtlpExporter {
+    /// Starts the background worker. Must be called inside a Tokio runtime.
+    pub fn spawn(config: OtlpConfig) -> Self {
+        let mut resource = vec![
+            ("service.name".to_string(), "railway-exporter".to_string()),
+            (
+                "service.version".to_string(),
+                env!("CARGO_PKG_VERSION").to_string(),
+            ),
+        ];
+        resource.extend(config.resource_attributes);
+
+        let pusher = HttpPusher::spawn(PushTarget {
+            name: "otlp",
+            method: Method::POST,
+            url: config.endpoint,
+            headers: config.headers.into_iter().collect(),
+            delivery: config.delivery,
+        });
+        Self {
+            pusher,
+            protocol: config.protocol,
+            resource,
+        }
+    }
+
+    /// Encodes the current registry contents and queues them for sending.
+    ///
+    /// Returns false if the queue is full and the snapshot was dropped.
+    pub fn push(&self, metrics: &Metrics, timestamp_ms: i64) -> bool {
+        let request = ExportRequest::new(
+            &metrics.gather(),
+            self.resource.clone(),
+            (metrics.created * 1e9) as u64,
+            timestamp_ms as u64 * 1_000_000,
+        );
+        let payload = match self.protocol {
+            OtlpProtocol::HttpProtobuf => Payload {
+                url: None,
+                body: request.to_protobuf(),
+                headers: vec![("Content-Type", "application/x-protobuf")],
+            },
+            OtlpProtocol::HttpJson => Payload {
+                url: None,
+                body: request.to_json().to_string().into_bytes(),
+                headers: vec![("Content-Type", "application/json")],
+            },
+        };
+        self.pusher.enqueue(payload)
+    }
+
+    /// Waits until queued snapshots are delivered.
+    pub async fn close(self) {
+        self.pusher.close().await;
+    }
+}
+
+/// Maps a metric name suffix to a UCUM unit.
+pub fn otlp_unit(name: &str) -> &'static str {
+    match unit_for(name) {
+        Some("vcpu_minutes") => "{vCPU}.min",
+        Some("gb_minutes") => "GBy.min",
+        Some("gb") => "GBy",
+        Some("bytes") => "By",
+        Some("seconds") => "s",
+        Some("percent") => "%",
+        Some("usd") => "{USD}",
+        _ => "",
+    }
+}
+
+// =============================================================================
+// Export request model
+// =============================================================================
+
+/// An `ExportMetricsServiceRequest` with a single resource and scope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportRequest {
+    resource: Vec<(String, String)>,
+    metrics: Vec<OtlpMetric>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct OtlpMetric {
+    name: String,
+    description: String,
+    unit: &'static str,
+    data: Data,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Data {
+    Gauge(Vec<NumberPoint>),
+    Sum(Vec<NumberPoint>),
+    Histogram(Vec<HistogramPoint>),
+    Summary(Vec<SummaryPoint>),
+}
+
+/// Attributes plus start/end time shared by all data point kinds.
+#[derive(Debug, Clone, PartialEq)]
+struct PointMeta {
+    attributes: Vec<(String, String)>,
+    start_ns: u64,
+    time_ns: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct NumberPoint {
+    meta: PointMeta,
+    value: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct HistogramPoint {
+    meta: PointMeta,
+    count: u64,
+    sum: f64,
+    /// Per-bucket (non-cumulative) counts, one more than `bounds`.
+    bucket_counts: Vec<u64>,
+    bounds: Vec<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SummaryPoint {
+    meta: PointMeta,
+    count: u64,
+    sum: f64,
+    quantiles: Vec<(f64, f64)>,
+}
+
+impl ExportRequest {
+    /// Converts metric families; `start_ns` is used as start time of cumulative points.
+    pub fn new(
+        families: &[MetricFamily],
+        resource: Vec<(String, String)>,
+        start_ns: u64,
+        time_ns: u64,
+    ) -> Self {
+        let metrics = families
+            .iter()
+            .map(|family| {
+                let meta = |metric: &Metric, start_ns: u64| PointMeta {
+                    attributes: metric
+                        .get_label()
+                        .iter()
+                        .map(|l| (l.get_name().to_string(), l.get_value().to_string()))
+                        .collect(),
+                    start_ns,
+                    time_ns,
+                };
+                let points = family.get_metric().iter();
+                let data = match family.get_field_type() {
+                    MetricType::GAUGE => Data::Gauge(
+                        points
+                            .map(|m| NumberPoint {
+                                meta: meta(m, 0),
+                                value: m.get_gauge().get_value(),
+                            })
+                            .collect(),
+                    ),
+                    MetricType::UNTYPED => Data::Gauge(
+                        points
+                            .map(|m| NumberPoint {
+                                meta: meta(m, 0),
+                                value: m.get_untyped().get_value(),
+                            })
+                            .collect(),
+                    ),
+                    MetricType::COUNTER => Data::Sum(
+                        points
+                            .map(|m| NumberPoint {
+                                meta: meta(m, start_ns),
+                                value: m.get_counter().get_value(),
+                            })
+                            .collect(),
+                    ),
+                    MetricType::HISTOGRAM => Data::Histogram(
+                        points
+                            .map(|m| {
+                                let h = m.get_histogram();
+                                let bounds: Vec<f64> = h
+                                    .get_bucket()
+                                    .iter()
+                                    .map(|b| b.get_upper_bound())
+                                    .filter(|b| b.is_finite())
+                                    .collect();
+                                let mut bucket_counts = Vec::with_capacity(bounds.len() + 1);
+                                let mut previous = 0;
+                                for b in h.get_bucket().iter().take(bounds.len()) {
+                                    bucket_counts.push(b.get_cumulative_count() - previous);
+                                    previous = b.get_cumulative_count();
+                                }
+                                bucket_counts.push(h.get_sample_count() - previous);
+                                HistogramPoint {
+                                    meta: meta(m, start_ns),
+                                    count: h.get_sample_count(),
+                                    sum: h.get_sample_sum(),
+                                    bucket_counts,
+                                    bounds,
+                                }
+                            })
+                            .collect(),
+                    ),
+                    MetricType::SUMMARY => Data::Summary(
+                        points
+                            .map(|m| {
+                                let s = m.get_summary();
+                                SummaryPoint {
+                                    meta: meta(m, start_ns),
+                                    count: s.get_sample_count(),
+                                    sum: s.get_sample_sum(),
+                                    quantiles: s
+                                        .get_quantile()
+                                        .iter()
+                                        .map(|q| (q.get_quantile(), q.get_value()))
+                                        .collect(),
+                                }
+                            })
+                            .collect(),
+                    ),
+                };
+                OtlpMetric {
+                    name: family.get_name().to_string(),
+                    description: family.get_help().to_string(),
+                    unit: otlp_unit(family.get_name()),
+                    data,
+                }
+            })
+            .collect();
+
+        Self { resource, metrics }
+    }
+
+    /// Encodes as OTLP protobuf (`ExportMetricsServiceRequest`).
+    pub fn to_protobuf(&self) -> Vec<u8> {
+        // Resource { attributes = 1 }
+        let mut resource = Vec::new();
+        for (key, value) in &self.resource {
+            write_bytes(&mut resource, 1, &key_value(key, value));
+        }
+
+        // ScopeMetrics { scope = 1; metrics = 2 }
+        let mut scope = Vec::new();
+        write_string(&mut scope, 1, "railway-exporter");
+        write_string(&mut scope, 2, env!("CARGO_PKG_VERSION"));
+        let mut scope_metrics = Vec::new();
+        write_bytes(&mut scope_metrics, 1, &scope);
+        for metric in &self.metrics {
+            write_bytes(&mut scope_metrics, 2, &metric.to_protobuf());
+        }
+
+        // ResourceMetrics { resource = 1; scope_metrics = 2 }
+        let mut resource_metrics = Vec::new();
+        write_bytes(&mut resource_metrics, 1, &resource);
+        write_bytes(&mut resource_metrics, 2, &scope_metrics);
+
+        // ExportMetricsServiceRequest { resource_metrics = 1 }
+        let mut out = Vec::new();
+        write_bytes(&mut out, 1, &resource_metrics);
+        out
+    }
+
+    /// Encodes as OTLP/JSON (camelCase fields, 64-bit integers as strings).
+    pub fn to_json(&self) -> Value {
+        json!({
+            "resourceMetrics": [{
+                "resource": { "attributes": json_attributes(&self.resource) },
+                "scopeMetrics": [{
+                    "scope": { "name": "railway-exporter", "version": env!("CARGO_PKG_VERSION") },
+                    "metrics": self.metrics.iter().map(OtlpMetric::to_json).collect::<Vec<_>>(),
+                }],
+            }],
+        })
+    }
+}
+
+impl OtlpMetric {
+    fn to_protobuf(&self) -> Vec<u8> {
+        // Metric { name = 1; description = 2; unit = 3; gauge = 5 | sum = 7 | histogram = 9 | summary = 11 }
+        let mut out = Vec::new();
+        write_string(&mut out, 1, &self.name);
+        write_string(&mut out, 2, &self.description);
+        write_string(&mut out, 3, self.unit);
+
+        let mut data = Vec::new();
+        let field = match &self.data {
+            Data::Gauge(points) => {
+                for p in points {
+                    write_bytes(&mut data, 1, &p.to_protobuf());
+                }
+                5
+            }
+            Data::Sum(points) => {
+                for p in points {
+                    write_bytes(&mut data, 1, &p.to_protobuf());
+                }
+                write_uint(&mut data, 2, CUMULATIVE);
+                write_uint(&mut data, 3, 1); // is_monotonic
+                7
+            }
+            Data::Histogram(points) => {
+                for p in points {
+                    write_bytes(&mut data, 1, &p.to_protobuf());
+                }
+                write_uint(&mut data, 2, CUMULATIVE);
+                9
+            }
+            Data::Summary(points) => {
+                for p in points {
+                    write_bytes(&mut data, 1, &p.to_protobuf());
+                }
+                11
+            }
+        };
+        write_bytes(&mut out, field, &data);
+        out
+    }
+
+    fn to_json(&self) -> Value {
+        let mut metric = json!({
+            "name": self.name,
+            "description": self.description,
+            "unit": self.unit,
+        });
+        let (key, data) = match &self.data {
+            Data::Gauge(points) => (
+                "gauge",
+                json!({ "dataPoints": points.iter().map(NumberPoint::to_json).collect::<Vec<_>>() }),
+            ),
+            Data::Sum(points) => (
+                "sum",
+                json!({
+                    "dataPoints": points.iter().map(NumberPoint::to_json).collect::<Vec<_>>(),
+                    "aggregationTemporality": CUMULATIVE,
+                    "isMonotonic": true,
+                }),
+            ),
+            Data::Histogram(points) => (
+                "histogram",
+                json!({
+                    "dataPoints": points.iter().map(HistogramPoint::to_json).collect::<Vec<_>>(),
+                    "aggregationTemporality": CUMULATIVE,
+                }),
+            ),
+            Data::Summary(points) => (
+                "summary",
+                json!({ "dataPoints": points.iter().map(SummaryPoint::to_json).collect::<Vec<_>>() }),
+            ),
+        };
+        metric[key] = data;
+        metric
+    }
+}
+
+impl PointMeta {
+    /// Writes attributes and timestamps using the given field numbers.
+    fn write_protobuf(&self, out: &mut Vec<u8>, attributes_field: u64) {
+        for (key, value) in &self.attributes {
+            write_bytes(out, attributes_field, &key_value(key, value));
+        }
+        if self.start_ns != 0 {
+            write_fixed64(out, 2, self.start_ns);
+        }
+        write_fixed64(out, 3, self.time_ns);
+    }
+
+    fn to_json(&self) -> Value {
+        let mut point = json!({
+            "attributes": json_attributes(&self.attributes),
+            "timeUnixNano": self.time_ns.to_string(),
+        });
+        if self.start_ns != 0 {
+            point["startTimeUnixNano"] = json!(self.start_ns.to_string());
+        }
+        point
+    }
+}
+
+impl NumberPoint {
+    fn to_protobuf(&self) -> Vec<u8> {
+        // NumberDataPoint { start = 2; time = 3; as_double = 4; attributes = 7 }
+        let mut out = Vec::new();
+        self.meta.write_protobuf(&mut out, 7);
+        write_double(&mut out, 4, self.value);
+        out
+    }
+
+    fn to_json(&self) -> Value {
+        let mut point = self.meta.to_json();
+        point["asDouble"] = json!(self.value);
+        point
+    }
+}
+
+impl HistogramPoint {
+    fn to_protobuf(&self) -> Vec<u8> {
+        // HistogramDataPoint { start = 2; time = 3; count = 4; sum = 5;
+        //   bucket_counts = 6 (packed fixed64); explicit_bounds = 7 (packed double); attributes = 9 }
+        let mut out = Vec::new();
+        self.meta.write_protobuf(&mut out, 9);
+        write_fixed64(&mut out, 4, self.count);
+        write_double(&mut out, 5, self.sum);
+        let counts: Vec<u8> = self
+            .bucket_counts
+            .iter()
+            .flat_map(|c| c.to_le_bytes())
+            .collect();
+        write_bytes(&mut out, 6, &counts);
+        if !self.bounds.is_empty() {
+            let bounds: Vec<u8> = self.bounds.iter().flat_map(|b| b.to_le_bytes()).collect();
+            write_bytes(&mut out, 7, &bounds);
+        }
+        out
+    }
+
+    fn to_json(&self) -> Value {
+        let mut point = self.meta.to_json();
+        point["count"] = json!(self.count.to_string());
+        point["sum"] = json!(self.sum);
+        point["bucketCounts"] = json!(self
+            .bucket_counts
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>());
+        point["explicitBounds"] = json!(self.bounds);
+        point
+    }
+}
+
+impl SummaryPoint {
+    fn to_protobuf(&self) -> Vec<u8> {
+        // SummaryDataPoint { start = 2; time = 3; count = 4; sum = 5; quantile_values = 6; attributes = 7 }
+        let mut out = Vec::new();
+        self.meta.write_protobuf(&mut out, 7);
+        write_fixed64(&mut out, 4, self.count);
+        write_double(&mut out, 5, self.sum);
+        for (quantile, value) in &self.quantiles {
+            let mut q = Vec::new();
+            write_double(&mut q, 1, *quantile);
+            write_double(&mut q, 2, *value);
+            write_bytes(&mut out, 6, &q);
+        }
+        out
+    }
+
+    fn to_json(&self) -> Value {
+        let mut point = self.meta.to_json();
+        point["count"] = json!(self.count.to_string());
+        point["sum"] = json!(self.sum);
+        point["quantileValues"] = json!(self
+            .quantiles
+            .iter()
+            .map(|(q, v)| json!({ "quantile": q, "value": v }))
+            .collect::<Vec<_>>());
+        point
+    }
+}
+
+/// Encodes `KeyValue { key = 1; value = 2 (AnyValue { string_value = 1 }) }`.
+fn key_value(key: &str, value: &str) -> Vec<u8> {
+    let mut any = Vec::new();
+    write_bytes(&mut any, 1, value.as_bytes());
+    let mut kv = Vec::new();
+    write_string(&mut kv, 1, key);
+    write_bytes(&mut kv, 2, &any);
+    kv
+}
+
+fn json_attributes(attributes: &[(String, String)]) -> Value {
+    Value::Array(
+        attributes
+            .iter()
+            .map(|(k, v)| json!({ "key": k, "value": { "stringValue": v } }))
+            .collect(),
+    )
+}
@@ -0,0 +1,172 @@
+//! Tests for OTLP metrics export.
+
+use super::test_support::{gather, next_request, start_capture_server, test_metrics};
+use crate::sinks::otlp::{otlp_unit, ExportRequest, OtlpConfig, OtlpExporter, OtlpProtocol};
+use prometheus::{CounterVec, Histogram, HistogramOpts, Opts, Registry};
+use serde_json::Value;
+
+fn resource() -> Vec<(String, String)> {
+    vec![(
+        "deployment.environment".to_string(),
+        "production".to_string(),
+    )]
+}
+
+// =============================================================================
+// Config Tests
+// =============================================================================
+
+#[test]
+fn test_config_from_yaml() {
+    let config: OtlpConfig = serde_yaml::from_str("protocol: http/json\nmax_retries: 1").unwrap();
+    assert_eq!(config.endpoint, "http://localhost:4318/v1/metrics");
+    assert_eq!(config.protocol, OtlpProtocol::HttpJson);
+    assert_eq!(config.delivery.max_retries, 1);
+    assert!(config.validate().is_ok());
+
+    let config: OtlpConfig = serde_yaml::from_str("endpoint: otel:4318").unwrap();
+    assert_eq!(config.protocol, OtlpProtocol::HttpProtobuf);
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_otlp_unit() {
+    assert_eq!(otlp_unit("railway_service_cost_usd"), "{USD}");
+    assert_eq!(otlp_unit("railway_exporter_scrape_duration_seconds"), "s");
+    assert_eq!(otlp_unit("railway_exporter_memory_bytes"), "By");
+    assert_eq!(otlp_unit("railway_cpu_usage_vcpu_minutes"), "{vCPU}.min");
+    assert_eq!(otlp_unit("railway_api_up"), "");
+}
+
+// =============================================================================
+// Mapping Tests
+// =============================================================================
+
+#[test]
+fn test_gauge_to_json() {
+    let request = ExportRequest::new(&gather(&test_metrics("web")), resource(), 5, 10);
+    let json = request.to_json();
+
+    let rm = &json["resourceMetrics"][0];
+    assert_eq!(
+        rm["resource"]["attributes"][0],
+        serde_json::json!({"key": "deployment.environment", "value": {"stringValue": "production"}})
+    );
+
+    let metric = &rm["scopeMetrics"][0]["metrics"][0];
+    assert_eq!(metric["name"], "railway_service_cost_usd");
+    assert_eq!(metric["unit"], "{USD}");
+    let point = &metric["gauge"]["dataPoints"][0];
+    assert_eq!(point["asDouble"], 2.5);
+    assert_eq!(point["timeUnixNano"], "10");
+    assert!(point.get("startTimeUnixNano").is_none());
+
+    // Every label stays on the point, project included
+    let attributes = point["attributes"].as_array().unwrap();
+    let keys: Vec<&str> = attributes
+        .iter()
+        .map(|a| a["key"].as_str().unwrap())
+        .collect();
+    assert!(keys.contains(&"service"));
+    assert!(keys.contains(&"group"));
+    assert!(attributes
+        .iter()
+        .any(|a| a["key"] == "project" && a["value"]["stringValue"] == "proj"));
+}
+
+#[test]
+fn test_counter_and_histogram_to_json() {
+    let registry = Registry::new();
+    let counter =
+        CounterVec::new(Opts::new("test_requests_total", "Requests"), &["route"]).unwrap();
+    let histogram = Histogram::with_opts(
+        HistogramOpts::new("test_duration_seconds", "Duration").buckets(vec![0.1, 1.0]),
+    )
+    .unwrap();
+    registry.register(Box::new(counter.clone())).unwrap();
+    registry.register(Box::new(histogram.clone())).unwrap();
+    counter.with_label_values(&["/metrics"]).inc_by(3.0);
+    histogram.observe(0.05);
+    histogram.observe(0.5);
+    histogram.observe(5.0);
+
+    let json = ExportRequest::new(&registry.gather(), resource(), 5, 10).to_json();
+    let metrics = &json["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+    let find = |name: &str| -> Value {
+        metrics
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|m| m["name"] == name)
+            .cloned()
+            .unwrap()
+    };
+
+    let sum = find("test_requests_total")["sum"].clone();
+    assert_eq!(sum["isMonotonic"], true);
+    assert_eq!(sum["aggregationTemporality"], 2);
+    assert_eq!(sum["dataPoints"][0]["asDouble"], 3.0);
+    assert_eq!(sum["dataPoints"][0]["startTimeUnixNano"], "5");
+
+    let point = find("test_duration_seconds")["histogram"]["dataPoints"][0].clone();
+    assert_eq!(point["count"], "3");
+    assert_eq!(point["explicitBounds"], serde_json::json!([0.1, 1.0]));
+    assert_eq!(point["bucketCounts"], serde_json::json!(["1", "1", "1"]));
+}
+
+#[test]
+fn test_to_protobuf_structure() {
+    let request = ExportRequest::new(&gather(&test_metrics("web")), resource(), 0, 10);
+    let body = request.to_protobuf();
+
+    // ExportMetricsServiceRequest.resource_metrics (field 1, length-delimited)
+    assert_eq!(body[0], 0x0a);
+    let contains = |needle: &[u8]| body.windows(needle.len()).any(|w| w == needle);
+    assert!(contains(b"deployment.environment"));
+    assert!(contains(b"railway_service_cost_usd"));
+    assert!(contains(b"{USD}"));
+    assert!(contains(&2.5f64.to_le_bytes()));
+}
+
+// =============================================================================
+// Export Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_push_json() {
+    let (url, mut rx) = start_capture_server(0).await;
+    let config = OtlpConfig {
+        endpoint: format!("{}/v1/metrics", url),
+        protocol: OtlpProtocol::HttpJson,
+        ..OtlpConfig::default()
+    };
+
+    let exporter = OtlpExporter::spawn(config);
+    assert!(exporter.push(&test_metrics("web"), 1_700_000_000_000));
+
+    let received = next_request(&mut rx).await;
+    assert_eq!(received.header("content-type"), "application/json");
+    let json: Value = serde_json::from_slice(&received.body).unwrap();
+    let attributes = json["resourceMetrics"][0]["resource"]["attributes"]
+        .as_array()
+        .unwrap()
+        .clone();
+    assert!(attributes.iter().any(|a| a["key"] == "service.name"));
+    assert!(!attributes.iter().any(|a| a["key"] == "railway.project"));
+}
+
+#[tokio::test]
+async fn test_push_protobuf() {
+    let (url, mut rx) = start_capture_server(0).await;
+    let config = OtlpConfig {
+        endpoint: format!("{}/v1/metrics", url),
+        ..OtlpConfig::default()
+    };
+
+    let exporter = OtlpExporter::spawn(config);
+    assert!(exporter.push(&test_metrics("web"), 0));
+
+    let received = next_request(&mut rx).await;
+    assert_eq!(received.header("content-type"), "application/x-protobuf");
+    assert_eq!(received.body[0], 0x0a);
+}
@@ -0,0 +1,277 @@
+//! Shared delivery for push sinks.
+//!
+//! Sinks that send snapshots over HTTP (remote_write, OTLP, ...) only encode
+//! payloads; queueing and retries live here. Each sink gets a bounded queue
+//! and a single background worker. Sends are retried with exponential backoff
+//! on network errors, `429` and `5xx`; other responses drop the payload. When
+//! the queue is full the new payload is dropped (the next snapshot supersedes
+//! it anyway).
+//!
+//! ## YAML Format
+//!
+//! Every HTTP sink section accepts these delivery settings:
+//!
+//! ```yaml
+//! timeout_seconds: 30      # per request (default: 30)
+//! max_retries: 3           # retries after the first attempt (default: 3, max: 10)
+//! retry_backoff_ms: 500    # initial backoff, doubled per retry up to 60s (default: 500)
+//! queue_capacity: 10       # queued snapshots (default: 10)
+//! ```
+
+use crate::config::ConfigError;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Upper bound for `max_retries`.
+pub const MAX_RETRIES: u32 = 10;
+
+/// Upper bound for a single retry backoff; doubling stops here.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Basic auth credentials.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: String,
+}
+
+/// Returns the `Authorization` header value for bearer or basic auth.
+pub fn authorization(bearer_token: Option<&str>, basic_auth: Option<&BasicAuth>) -> Option<String> {
+    if let Some(token) = bearer_token {
+        Some(format!("Bearer {}", token))
+    } else {
+        basic_auth.map(|auth| {
+            let credentials = format!("{}:{}", auth.username, auth.password);
+            format!("Basic {}", STANDARD.encode(credentials))
+        })
+    }
+}
+
+/// Timeouts, retries and queueing for an HTTP sink.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeliveryConfig {
+    /// Request timeout in seconds.
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// Retries after the first attempt.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Initial retry backoff in milliseconds (doubled per retry, capped at
+    /// [`MAX_BACKOFF`]).
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// Number of payloads that may wait for the worker.
+    #[serde(default = "default_queue_capacity")]
+    pub queue_capacity: usize,
+}
+
+fn default_timeout_seconds() -> u64 {
+    30
+}
+fn default_max_retries() -> u32 {
+    3
+}
+fn default_retry_backoff_ms() -> u64 {
+    500
+}
+fn default_queue_capacity() -> usize {
+    10
+}
+
+impl Default for DeliveryConfig {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: default_timeout_seconds(),
+            max_retries: default_max_retries(),
+            retry_backoff_ms: default_retry_backoff_ms(),
+            queue_capacity: default_queue_capacity(),
+        }
+    }
+}
+
+impl DeliveryConfig {
+    /// Checks limits; `section` is used in error messages.
+    pub fn validate(&self, section: &str) -> Result<(), ConfigError> {
+        if self.timeout_seconds == 0 || self.queue_capacity == 0 {
+            return Err(ConfigError::InvalidValue(format!(
+                "{}.timeout_seconds and queue_capacity must be positive",
+                section
+            )));
+        }
+        if self.max_retries > MAX_RETRIES {
+            return Err(ConfigError::InvalidValue(format!(
+                "{}.max_retries must be at most {}",
+                section, MAX_RETRIES
+            )));
+        }
+        if self.retry_backoff_ms > MAX_BACKOFF.as_millis() as u64 {
+            return Err(ConfigError::InvalidValue(format!(
+                "{}.retry_backoff_ms must be at most {}",
+                section,
+                MAX_BACKOFF.as_millis()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Checks that a sink URL is http(s).
+pub fn validate_url(url: &str, key: &str) -> Result<(), ConfigError> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidValue(format!(
+            "{} must be an http(s) URL",
+            key
+        )))
+    }
+}
+
+/// Where and how a sink sends its payloads.
+#[derive(Debug, Clone)]
+pub struct PushTarget {
+    /// Sink name used in log messages.
+    pub name: &'static str,
+    pub method: Method,
+    pub url: String,
+    /// Headers sent with every request (auth, tenant IDs, ...).
+    pub headers: Vec<(String, String)>,
+    pub delivery: DeliveryConfig,
+}
+
+/// A single encoded snapshot.
+#[derive(Debug, Clone)]
+pub struct Payload {
+    /// Request URL if it differs from the target's (e.g. per metric group).
+    pub url: Option<String>,
+    pub body: Vec<u8>,
+    /// Format headers (`Content-Type`, `Content-Encoding`, ...).
+    pub headers: Vec<(&'static str, &'static str)>,
+}
+
+/// Handle to a sink's background worker.
+#[derive(Debug)]
+pub struct HttpPusher {
+    name: &'static str,
+    tx: mpsc::Sender<Payload>,
+    worker: JoinHandle<()>,
+}
+
+impl HttpPusher {
+    /// Starts the background worker. Must be called inside a Tokio runtime.
+    pub fn spawn(target: PushTarget) -> Self {
+        let (tx, rx) = mpsc::channel(target.delivery.queue_capacity);
+        let name = target.name;
+        let worker = tokio::spawn(run_worker(target, rx));
+        Self { name, tx, worker }
+    }
+
+    /// Stops accepting payloads and waits until queued ones are delivered
+    /// (or retries are exhausted).
+    pub async fn close(self) {
+        drop(self.tx);
+        let _ = self.worker.await;
+    }
+
+    /// Queues a payload. Returns false if it was dropped.
+    pub fn enqueue(&self, payload: Payload) -> bool {
+        match self.tx.try_send(payload) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                warn!("{}: queue full, dropping snapshot", self.name);
+                false
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                warn!("{}: worker stopped, dropping snapshot", self.name);
+                false
+            }
+        }
+    }
+}
+
+/// Sends queued payloads one by one, retrying transient failures.
+async fn run_worker(target: PushTarget, mut rx: mpsc::Receiver<Payload>) {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(target.delivery.timeout_seconds))
+        .user_agent(concat!("railway-exporter/", env!("CARGO_PKG_VERSION")))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("{}: failed to build HTTP client: {}", target.name, e);
+            return;
+        }
+    };
+
+    let max_retries = target.delivery.max_retries;
+    while let Some(payload) = rx.recv().await {
+        let mut backoff = Duration::from_millis(target.delivery.retry_backoff_ms);
+        for attempt in 0..=max_retries {
+            match send(&client, &target, &payload).await {
+                SendOutcome::Sent => {
+                    debug!("{}: sent {} bytes", target.name, payload.body.len());
+                    break;
+                }
+                SendOutcome::Fatal(reason) => {
+                    warn!("{}: dropping snapshot: {}", target.name, reason);
+                    break;
+                }
+                SendOutcome::Retry(reason) if attempt < max_retries => {
+                    warn!(
+                        "{}: attempt {} failed ({}), retrying in {:?}",
+                        target.name,
+                        attempt + 1,
+                        reason,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+                SendOutcome::Retry(reason) => {
+                    warn!(
+                        "{}: giving up after {} attempts: {}",
+                        target.name,
+                        attempt + 1,
+                        reason
+                    );
+                }
+            }
+        }
+    }
+}
+
+enum SendOutcome {
+    Sent,
+    Retry(String),
+    Fatal(String),
+}
+
+async fn send(client: &reqwest::Client, target: &PushTarget, payload: &Payload) -> SendOutcome {
+    let url = payload.url.as_deref().unwrap_or(&target.url);
+    let mut request = client.request(target.method.clone(), url);
+    for (name, value) in &payload.headers {
+        request = request.header(*name, *value);
+    }
+    for (name, value) in &target.headers {
+        request = request.header(name, value);
+    }
+
+    match request.body(payload.body.clone()).send().await {
+        Ok(resp) => {
+            let status = resp.status();
+            if status.is_success() {
+                SendOutcome::Sent
+            } else if status.is_server_error() || status.as_u16() == 429 {
+                SendOutcome::Retry(format!("HTTP {}", status))
+            } else {
+                SendOutcome::Fatal(format!("HTTP {}", status))
+            }
+        }
+        Err(e) => SendOutcome::Retry(e.to_string()),
+    }
+}
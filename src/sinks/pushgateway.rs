@@ -0,0 +1,172 @@
+//! Prometheus Pushgateway sink.
+//!
+//! Meant for cron-style runs (`railway-exporter --once`) where the exporter
+//! isn't alive long enough to be scraped. Each snapshot replaces the whole
+//! metric group (HTTP `PUT`) identified by `job`, `project=<project name>`
+//! and any extra `grouping` labels. The project is the name collected from
+//! Railway, the same value the series carry in their own `project` label, so
+//! exporters for several projects can share one Pushgateway and `job`.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! sinks:
+//!   pushgateway:
+//!     url: http://pushgateway:9091
+//!     job: railway_exporter      # default: railway_exporter
+//!     grouping:                  # extra grouping labels (optional)
+//!       env: production
+//!       project_id: abc123
+//!     basic_auth:                # or bearer_token (optional)
+//!       username: user
+//!       password: pass
+//!     max_retries: 3             # delivery settings, see `sinks::push`
+//! ```
+
+use super::push::{self, BasicAuth, DeliveryConfig, HttpPusher, Payload, PushTarget};
+use crate::config::ConfigError;
+use crate::exposition::TEXT_CONTENT_TYPE;
+use crate::metrics::Metrics;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Pushgateway settings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PushgatewayConfig {
+    /// Pushgateway base URL (without `/metrics/job/...`).
+    pub url: String,
+    /// Job name of the metric group.
+    #[serde(default = "default_job")]
+    pub job: String,
+    /// Extra grouping labels (`job` is reserved; `project` replaces the
+    /// collected project name).
+    #[serde(default)]
+    pub grouping: BTreeMap<String, String>,
+    /// Bearer token sent as `Authorization: Bearer ...`.
+    #[serde(default)]
+    pub bearer_token: Option<String>,
+    /// Basic auth credentials.
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuth>,
+    /// Timeouts, retries and queueing.
+    #[serde(flatten)]
+    pub delivery: DeliveryConfig,
+}
+
+fn default_job() -> String {
+    "railway_exporter".to_string()
+}
+
+impl PushgatewayConfig {
+    /// Creates a config with default settings for the given Pushgateway.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            job: default_job(),
+            grouping: BTreeMap::new(),
+            bearer_token: None,
+            basic_auth: None,
+            delivery: DeliveryConfig::default(),
+        }
+    }
+
+    /// Checks URL, job, auth and limits.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        push::validate_url(&self.url, "sinks.pushgateway.url")?;
+        if self.job.is_empty() {
+            return Err(ConfigError::InvalidValue(
+                "sinks.pushgateway.job must not be empty".to_string(),
+            ));
+        }
+        if self.grouping.contains_key("job") {
+            return Err(ConfigError::InvalidValue(
+                "sinks.pushgateway.grouping must not override job".to_string(),
+            ));
+        }
+        if self.bearer_token.is_some() && self.basic_auth.is_some() {
+            return Err(ConfigError::InvalidValue(
+                "sinks.pushgateway: set either bearer_token or basic_auth, not both".to_string(),
+            ));
+        }
+        self.delivery.validate("sinks.pushgateway")
+    }
+
+    /// Returns the metric group URL for a project (unless `grouping` sets one).
+    pub fn group_url(&self, project: &str) -> String {
+        let mut url = format!(
+            "{}/metrics/job/{}",
+            self.url.trim_end_matches('/'),
+            path_value(&self.job)
+        );
+        let project = self.grouping.get("project").map_or(project, |p| p.as_str());
+        let labels = std::iter::once(("project", project)).chain(
+            self.grouping
+                .iter()
+                .filter(|(k, _)| *k != "project")
+                .map(|(k, v)| (k.as_str(), v.as_str())),
+        );
+        for (name, value) in labels {
+            if value.is_empty() || value.contains('/') {
+                // Pushgateway's base64 form is the only way to encode these
+                url.push_str(&format!(
+                    "/{}@base64/{}",
+                    name,
+                    URL_SAFE_NO_PAD.encode(value)
+                ));
+            } else {
+                url.push_str(&format!("/{}/{}", name, path_value(value)));
+            }
+        }
+        url
+    }
+}
+
+fn path_value(value: &str) -> String {
+    urlencoding::encode(value).into_owned()
+}
+
+/// Handle to the background Pushgateway worker.
+#[derive(Debug)]
+pub struct PushgatewaySink {
+    config: PushgatewayConfig,
+    pusher: HttpPusher,
+}
+
+impl PushgatewaySink {
+    /// Starts the background worker. Must be called inside a Tokio runtime.
+    pub fn spawn(config: PushgatewayConfig) -> Self {
+        let mut headers = Vec::new();
+        if let Some(auth) =
+            push::authorization(config.bearer_token.as_deref(), config.basic_auth.as_ref())
+        {
+            headers.push(("Authorization".to_string(), auth));
+        }
+        let pusher = HttpPusher::spawn(PushTarget {
+            name: "pushgateway",
+            method: Method::PUT,
+            url: config.url.clone(),
+            headers,
+            delivery: config.delivery.clone(),
+        });
+        Self { config, pusher }
+    }
+
+    /// Queues the current registry contents (text format) for sending to the
+    /// group of `project`.
+    ///
+    /// Returns false if the queue is full and the snapshot was dropped.
+    pub fn push(&self, metrics: &Metrics, project: &str) -> bool {
+        self.pusher.enqueue(Payload {
+            url: Some(self.config.group_url(project)),
+            body: metrics.encode().into_bytes(),
+            headers: vec![("Content-Type", TEXT_CONTENT_TYPE)],
+        })
+    }
+
+    /// Waits until queued snapshots are delivered.
+    pub async fn close(self) {
+        self.pusher.close().await;
+    }
+}
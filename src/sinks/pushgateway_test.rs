@@ -0,0 +1,92 @@
+//! Tests for the Pushgateway sink.
+
+use super::test_support::{next_request, start_capture_server};
+use crate::metrics::Metrics;
+use crate::sinks::pushgateway::{PushgatewayConfig, PushgatewaySink};
+
+#[test]
+fn test_group_url() {
+    let mut config = PushgatewayConfig::new("http://pushgateway:9091/");
+    assert_eq!(
+        config.group_url("my project"),
+        "http://pushgateway:9091/metrics/job/railway_exporter/project/my%20project"
+    );
+    assert_eq!(
+        config.group_url("a/b"),
+        "http://pushgateway:9091/metrics/job/railway_exporter/project@base64/YS9i"
+    );
+
+    config
+        .grouping
+        .insert("env".to_string(), "my prod".to_string());
+    config
+        .grouping
+        .insert("team".to_string(), "a/b".to_string());
+    assert_eq!(
+        config.group_url("p"),
+        "http://pushgateway:9091/metrics/job/railway_exporter/project/p/env/my%20prod/team@base64/YS9i"
+    );
+
+    // A configured project replaces the collected name
+    config
+        .grouping
+        .insert("project".to_string(), "fixed".to_string());
+    assert!(config
+        .group_url("p")
+        .ends_with("/job/railway_exporter/project/fixed/env/my%20prod/team@base64/YS9i"));
+}
+
+#[test]
+fn test_validate() {
+    assert!(PushgatewayConfig::new("http://pg:9091").validate().is_ok());
+    assert!(PushgatewayConfig::new("pg:9091").validate().is_err());
+
+    let mut config = PushgatewayConfig::new("http://pg:9091");
+    config
+        .grouping
+        .insert("project".to_string(), "x".to_string());
+    assert!(config.validate().is_ok());
+    config.grouping.insert("job".to_string(), "x".to_string());
+    assert!(config.validate().is_err());
+
+    let mut config = PushgatewayConfig::new("http://pg:9091");
+    config.job = String::new();
+    assert!(config.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_push_replaces_group() {
+    let (url, mut rx) = start_capture_server(0).await;
+    let mut config = PushgatewayConfig::new(&url);
+    config
+        .grouping
+        .insert("project_id".to_string(), "abc123".to_string());
+    let sink = PushgatewaySink::spawn(config);
+
+    // Grouped by the collected project name, matching the series' label
+    let metrics = Metrics::new();
+    metrics.api_up.with_label_values(&["My Project"]).set(1.0);
+    assert!(sink.push(&metrics, "My Project"));
+    let other = Metrics::new();
+    other.api_up.with_label_values(&["Other"]).set(1.0);
+    assert!(sink.push(&other, "Other"));
+    sink.close().await;
+
+    let received = next_request(&mut rx).await;
+    assert_eq!(received.method, "PUT");
+    assert_eq!(
+        received.path(),
+        "/metrics/job/railway_exporter/project/My%20Project/project_id/abc123"
+    );
+    assert!(received.header("content-type").starts_with("text/plain"));
+    assert!(received
+        .text()
+        .contains("railway_api_up{project=\"My Project\"} 1"));
+
+    // Another project gets its own group instead of replacing the first
+    let received = next_request(&mut rx).await;
+    assert_eq!(
+        received.path(),
+        "/metrics/job/railway_exporter/project/Other/project_id/abc123"
+    );
+}
@@ -0,0 +1,232 @@
+//! Prometheus remote_write push mode.
+//!
+//! For deployments that can't be scraped (e.g. behind Railway's private
+//! network), every post-collection snapshot of the metrics registry is pushed
+//! to a remote_write endpoint (Prometheus, Mimir, Thanos receive, VictoriaMetrics,
+//! Grafana Cloud, ...).
+//!
+//! Payloads are `prometheus.WriteRequest` protobuf messages (remote_write 1.0),
+//! snappy block-compressed. Queueing and retries are handled by `sinks::push`.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! sinks:
+//!   remote_write:
+//!     url: https://prometheus.example.com/api/v1/write
+//!     bearer_token: "secret"        # or basic_auth (not both)
+//!     # basic_auth:
+//!     #   username: user
+//!     #   password: pass
+//!     headers:                      # extra request headers (optional)
+//!       X-Scope-OrgID: tenant-1
+//!     max_retries: 3                # delivery settings, see `sinks::push`
+//! ```
+
+use super::push::{self, BasicAuth, DeliveryConfig, HttpPusher, Payload, PushTarget};
+use crate::config::ConfigError;
+use crate::metrics::Metrics;
+use crate::utils::protobuf::{write_bytes, write_double, write_uint};
+use prometheus::proto::{Metric, MetricFamily, MetricType};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// Remote write settings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RemoteWriteConfig {
+    /// Remote write endpoint URL.
+    pub url: String,
+    /// Bearer token sent as `Authorization: Bearer ...`.
+    #[serde(default)]
+    pub bearer_token: Option<String>,
+    /// Basic auth credentials.
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuth>,
+    /// Extra request headers.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Timeouts, retries and queueing.
+    #[serde(flatten)]
+    pub delivery: DeliveryConfig,
+}
+
+impl RemoteWriteConfig {
+    /// Creates a config with default settings for the given endpoint.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            bearer_token: None,
+            basic_auth: None,
+            headers: BTreeMap::new(),
+            delivery: DeliveryConfig::default(),
+        }
+    }
+
+    /// Checks URL, auth and limits.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        push::validate_url(&self.url, "sinks.remote_write.url")?;
+        if self.bearer_token.is_some() && self.basic_auth.is_some() {
+            return Err(ConfigError::InvalidValue(
+                "sinks.remote_write: set either bearer_token or basic_auth, not both".to_string(),
+            ));
+        }
+        self.delivery.validate("sinks.remote_write")
+    }
+}
+
+/// Handle to the background remote_write worker.
+#[derive(Debug)]
+pub struct RemoteWriter {
+    pusher: HttpPusher,
+}
+
+impl RemoteWriter {
+    /// Starts the background worker. Must be called inside a Tokio runtime.
+    pub fn spawn(config: RemoteWriteConfig) -> Self {
+        let mut headers: Vec<(String, String)> = config.headers.into_iter().collect();
+        if let Some(auth) =
+            push::authorization(config.bearer_token.as_deref(), config.basic_auth.as_ref())
+        {
+            headers.push(("Authorization".to_string(), auth));
+        }
+        let pusher = HttpPusher::spawn(PushTarget {
+            name: "remote_write",
+            method: Method::POST,
+            url: config.url,
+            headers,
+            delivery: config.delivery,
+        });
+        Self { pusher }
+    }
+
+    /// Encodes the current registry contents and queues them for sending.
+    ///
+    /// Returns false if the queue is full and the snapshot was dropped.
+    pub fn push(&self, metrics: &Metrics, timestamp_ms: i64) -> bool {
+        let body = encode_write_request(&metrics.gather(), timestamp_ms);
+        let body = match snap::raw::Encoder::new().compress_vec(&body) {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("remote_write: snappy compression failed: {}", e);
+                return false;
+            }
+        };
+        self.pusher.enqueue(Payload {
+            url: None,
+            body,
+            headers: vec![
+                ("Content-Type", "application/x-protobuf"),
+                ("Content-Encoding", "snappy"),
+                ("X-Prometheus-Remote-Write-Version", "0.1.0"),
+            ],
+        })
+    }
+
+    /// Waits until queued snapshots are delivered.
+    pub async fn close(self) {
+        self.pusher.close().await;
+    }
+}
+
+// =============================================================================
+// WriteRequest encoding
+// =============================================================================
+
+/// Converts metric families to an uncompressed `prometheus.WriteRequest`.
+///
+/// Histograms and summaries are flattened into their `_bucket` / `_sum` /
+/// `_count` (and quantile) series, as Prometheus does on scrape.
+pub fn encode_write_request(families: &[MetricFamily], timestamp_ms: i64) -> Vec<u8> {
+    let mut out = Vec::new();
+    for family in families {
+        let name = family.get_name();
+        for metric in family.get_metric() {
+            let ts = if metric.get_timestamp_ms() != 0 {
+                metric.get_timestamp_ms()
+            } else {
+                timestamp_ms
+            };
+            let mut series = |suffix: &str, extra: Option<(&str, String)>, value: f64| {
+                let labels = series_labels(&format!("{}{}", name, suffix), metric, extra);
+                write_bytes(&mut out, 1, &encode_time_series(&labels, value, ts));
+            };
+            match family.get_field_type() {
+                MetricType::GAUGE => series("", None, metric.get_gauge().get_value()),
+                MetricType::COUNTER => series("", None, metric.get_counter().get_value()),
+                MetricType::UNTYPED => series("", None, metric.get_untyped().get_value()),
+                MetricType::HISTOGRAM => {
+                    let h = metric.get_histogram();
+                    for b in h.get_bucket() {
+                        let le = format_bound(b.get_upper_bound());
+                        series("_bucket", Some(("le", le)), b.get_cumulative_count() as f64);
+                    }
+                    if !h
+                        .get_bucket()
+                        .iter()
+                        .any(|b| b.get_upper_bound().is_infinite())
+                    {
+                        let count = h.get_sample_count() as f64;
+                        series("_bucket", Some(("le", "+Inf".to_string())), count);
+                    }
+                    series("_sum", None, h.get_sample_sum());
+                    series("_count", None, h.get_sample_count() as f64);
+                }
+                MetricType::SUMMARY => {
+                    let s = metric.get_summary();
+                    for q in s.get_quantile() {
+                        let quantile = format_bound(q.get_quantile());
+                        series("", Some(("quantile", quantile)), q.get_value());
+                    }
+                    series("_sum", None, s.get_sample_sum());
+                    series("_count", None, s.get_sample_count() as f64);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Returns `__name__` plus metric labels, sorted by name as remote_write requires.
+fn series_labels(
+    name: &str,
+    metric: &Metric,
+    extra: Option<(&str, String)>,
+) -> Vec<(String, String)> {
+    let mut labels: Vec<(String, String)> = metric
+        .get_label()
+        .iter()
+        .map(|l| (l.get_name().to_string(), l.get_value().to_string()))
+        .chain(extra.map(|(k, v)| (k.to_string(), v)))
+        .collect();
+    labels.push(("__name__".to_string(), name.to_string()));
+    labels.sort();
+    labels
+}
+
+fn format_bound(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Encodes `TimeSeries { labels = 1; samples = 2 }` with a single sample.
+fn encode_time_series(labels: &[(String, String)], value: f64, timestamp_ms: i64) -> Vec<u8> {
+    let mut ts = Vec::new();
+    for (name, value) in labels {
+        // Label { name = 1; value = 2 }
+        let mut label = Vec::new();
+        write_bytes(&mut label, 1, name.as_bytes());
+        write_bytes(&mut label, 2, value.as_bytes());
+        write_bytes(&mut ts, 1, &label);
+    }
+    // Sample { double value = 1; int64 timestamp = 2 }
+    let mut sample = Vec::new();
+    write_double(&mut sample, 1, value);
+    write_uint(&mut sample, 2, timestamp_ms as u64);
+    write_bytes(&mut ts, 2, &sample);
+    ts
+}
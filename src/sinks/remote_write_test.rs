@@ -0,0 +1,174 @@
+//! Tests for remote_write push mode.
+//!
+//! Sending is verified against a local receiver that records requests.
+
+use super::test_support::{gather, next_request, start_capture_server, test_metrics};
+use crate::metrics::Metrics;
+use crate::sinks::push::BasicAuth;
+use crate::sinks::remote_write::{encode_write_request, RemoteWriteConfig, RemoteWriter};
+
+/// Service cost and `railway_api_up` series.
+fn write_metrics() -> Metrics {
+    let metrics = test_metrics("web");
+    metrics.api_up.with_label_values(&["proj"]).set(1.0);
+    metrics
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+// =============================================================================
+// Config Tests
+// =============================================================================
+
+#[test]
+fn test_config_defaults_from_yaml() {
+    let config: RemoteWriteConfig =
+        serde_yaml::from_str("url: http://localhost:9090/api/v1/write").unwrap();
+    assert_eq!(config.delivery.timeout_seconds, 30);
+    assert_eq!(config.delivery.max_retries, 3);
+    assert_eq!(config.delivery.retry_backoff_ms, 500);
+    assert_eq!(config.delivery.queue_capacity, 10);
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_config_validate() {
+    assert!(RemoteWriteConfig::new("localhost:9090").validate().is_err());
+
+    let mut both = RemoteWriteConfig::new("https://example.com/write");
+    both.bearer_token = Some("t".to_string());
+    both.basic_auth = Some(BasicAuth {
+        username: "u".to_string(),
+        password: "p".to_string(),
+    });
+    assert!(both.validate().is_err());
+
+    let mut zero = RemoteWriteConfig::new("https://example.com/write");
+    zero.delivery.queue_capacity = 0;
+    assert!(zero.validate().is_err());
+
+    let mut retries = RemoteWriteConfig::new("https://example.com/write");
+    retries.delivery.max_retries = 11;
+    assert!(retries.validate().is_err());
+    retries.delivery.max_retries = 10;
+    retries.delivery.retry_backoff_ms = 60_000;
+    assert!(retries.validate().is_ok());
+    retries.delivery.retry_backoff_ms = 60_001;
+    assert!(retries.validate().is_err());
+}
+
+// =============================================================================
+// Encoding Tests
+// =============================================================================
+
+#[test]
+fn test_encode_single_gauge() {
+    let metrics = Metrics::new();
+    metrics.api_up.with_label_values(&["p"]).set(1.0);
+
+    let body = encode_write_request(&gather(&metrics), 1000);
+
+    let mut expected = vec![0x0a]; // WriteRequest.timeseries (field 1, len)
+    let mut series = Vec::new();
+    for (name, value) in [("__name__", "railway_api_up"), ("project", "p")] {
+        let mut label = vec![0x0a, name.len() as u8];
+        label.extend_from_slice(name.as_bytes());
+        label.push(0x12);
+        label.push(value.len() as u8);
+        label.extend_from_slice(value.as_bytes());
+        series.push(0x0a);
+        series.push(label.len() as u8);
+        series.extend(label);
+    }
+    let mut sample = vec![0x09];
+    sample.extend_from_slice(&1.0f64.to_le_bytes());
+    sample.extend_from_slice(&[0x10, 0xe8, 0x07]); // timestamp 1000 as varint
+    series.push(0x12);
+    series.push(sample.len() as u8);
+    series.extend(sample);
+    expected.push(series.len() as u8);
+    expected.extend(series);
+
+    assert_eq!(body, expected);
+}
+
+#[test]
+fn test_encode_labels_sorted() {
+    let body = encode_write_request(&gather(&write_metrics()), 0);
+    // Label.name fields (tag 0x0a + length) of the service cost series
+    let position = |name: &[u8]| {
+        let mut field = vec![0x0a, name.len() as u8];
+        field.extend_from_slice(name);
+        body.windows(field.len())
+            .rposition(|w| w == field.as_slice())
+    };
+    let name = position(b"__name__").unwrap();
+    let group = position(b"group").unwrap();
+    let service = position(b"service").unwrap();
+    assert!(name < group && group < service);
+}
+
+// =============================================================================
+// Sending Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_push_sends_snappy_protobuf() {
+    let (url, mut rx) = start_capture_server(0).await;
+    let mut config = RemoteWriteConfig::new(&format!("{}/api/v1/write", url));
+    config.bearer_token = Some("secret".to_string());
+    config
+        .headers
+        .insert("X-Scope-OrgID".to_string(), "tenant-1".to_string());
+
+    let writer = RemoteWriter::spawn(config);
+    assert!(writer.push(&write_metrics(), 1_700_000_000_000));
+
+    let received = next_request(&mut rx).await;
+    assert_eq!(received.headers["content-encoding"], "snappy");
+    assert_eq!(received.headers["content-type"], "application/x-protobuf");
+    assert_eq!(
+        received.headers["x-prometheus-remote-write-version"],
+        "0.1.0"
+    );
+    assert_eq!(received.headers["authorization"], "Bearer secret");
+    assert_eq!(received.headers["x-scope-orgid"], "tenant-1");
+
+    let body = snap::raw::Decoder::new()
+        .decompress_vec(&received.body)
+        .unwrap();
+    assert!(contains(&body, b"railway_service_cost_usd"));
+    assert!(contains(&body, b"railway_api_up"));
+}
+
+#[tokio::test]
+async fn test_push_retries_server_errors() {
+    let (url, mut rx) = start_capture_server(2).await;
+    let mut config = RemoteWriteConfig::new(&format!("{}/api/v1/write", url));
+    config.delivery.retry_backoff_ms = 10;
+    config.basic_auth = Some(BasicAuth {
+        username: "user".to_string(),
+        password: "pass".to_string(),
+    });
+
+    let writer = RemoteWriter::spawn(config);
+    assert!(writer.push(&write_metrics(), 0));
+
+    let received = next_request(&mut rx).await;
+    assert_eq!(received.headers["authorization"], "Basic dXNlcjpwYXNz");
+}
+
+#[tokio::test]
+async fn test_push_queue_full_drops_snapshot() {
+    // Nothing listens here; the worker is stuck retrying the first payload
+    let mut config = RemoteWriteConfig::new("http://127.0.0.1:9/api/v1/write");
+    config.delivery.queue_capacity = 1;
+    config.delivery.retry_backoff_ms = 60_000;
+
+    let writer = RemoteWriter::spawn(config);
+    let metrics = write_metrics();
+    let results: Vec<bool> = (0..3).map(|_| writer.push(&metrics, 0)).collect();
+    assert!(results.contains(&false));
+}
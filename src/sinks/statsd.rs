@@ -0,0 +1,262 @@
+//! StatsD / DogStatsD sink.
+//!
+//! Emits every registry value as a gauge over UDP after each collection.
+//! Counters are sent as gauges of their cumulative value; histograms and
+//! summaries as `_sum` / `_count` gauges.
+//!
+//! Labels become tags with DogStatsD (`|#group:backend,service:web`). Plain
+//! StatsD has no tags, so label values are appended to the metric name
+//! instead (`railway_service_cost_usd.backend.proj.web`). Labels listed in
+//! `drop_labels` (default: `icon`) are left out in both flavors.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! sinks:
+//!   statsd:
+//!     address: 127.0.0.1:8125    # default
+//!     flavor: dogstatsd          # or statsd (default: dogstatsd)
+//!     tags:                      # constant tags (DogStatsD only)
+//!       env: production
+//!     drop_labels: [icon]        # default: [icon]
+//!     max_packet_size: 1432      # bytes per UDP datagram (default: 1432)
+//! ```
+
+use crate::config::ConfigError;
+use crate::metrics::Metrics;
+use prometheus::proto::{Metric, MetricFamily, MetricType};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+use tracing::warn;
+
+/// Line format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsdFlavor {
+    /// Classic StatsD (labels folded into the name).
+    Statsd,
+    /// DogStatsD (labels as tags).
+    #[default]
+    Dogstatsd,
+}
+
+/// StatsD settings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StatsdConfig {
+    /// `host:port` of the StatsD agent.
+    #[serde(default = "default_address")]
+    pub address: String,
+    #[serde(default)]
+    pub flavor: StatsdFlavor,
+    /// Constant tags added to every line (DogStatsD only).
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    /// Labels left out of names/tags.
+    #[serde(default = "default_drop_labels")]
+    pub drop_labels: Vec<String>,
+    /// Maximum datagram size; lines are batched up to this size.
+    #[serde(default = "default_max_packet_size")]
+    pub max_packet_size: usize,
+}
+
+fn default_address() -> String {
+    "127.0.0.1:8125".to_string()
+}
+fn default_drop_labels() -> Vec<String> {
+    vec!["icon".to_string()]
+}
+fn default_max_packet_size() -> usize {
+    1432
+}
+
+impl Default for StatsdConfig {
+    fn default() -> Self {
+        Self {
+            address: default_address(),
+            flavor: StatsdFlavor::default(),
+            tags: BTreeMap::new(),
+            drop_labels: default_drop_labels(),
+            max_packet_size: default_max_packet_size(),
+        }
+    }
+}
+
+impl StatsdConfig {
+    /// Checks address and packet size.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !self.address.contains(':') {
+            return Err(ConfigError::InvalidValue(
+                "sinks.statsd.address must be host:port".to_string(),
+            ));
+        }
+        if self.max_packet_size < 64 {
+            return Err(ConfigError::InvalidValue(
+                "sinks.statsd.max_packet_size must be at least 64".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// UDP emitter.
+#[derive(Debug)]
+pub struct StatsdSink {
+    socket: UdpSocket,
+    config: StatsdConfig,
+}
+
+impl StatsdSink {
+    /// Resolves the agent address and opens a non-blocking UDP socket.
+    pub fn new(config: StatsdConfig) -> io::Result<Self> {
+        let target =
+            config.address.to_socket_addrs()?.next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "address did not resolve")
+            })?;
+        let bind = if target.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.connect(target)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, config })
+    }
+
+    /// Sends the current registry contents. Returns the number of datagrams sent.
+    pub fn push(&self, metrics: &Metrics) -> usize {
+        let lines = format_lines(&metrics.gather(), &self.config);
+        let mut sent = 0;
+        for packet in pack(&lines, self.config.max_packet_size) {
+            match self.socket.send(packet.as_bytes()) {
+                Ok(_) => sent += 1,
+                Err(e) => {
+                    warn!("statsd: send to {} failed: {}", self.config.address, e);
+                    break;
+                }
+            }
+        }
+        sent
+    }
+}
+
+/// Renders metric families as StatsD gauge lines.
+pub fn format_lines(families: &[MetricFamily], config: &StatsdConfig) -> Vec<String> {
+    let mut lines = Vec::new();
+    for family in families {
+        let name = family.get_name();
+        for metric in family.get_metric() {
+            let mut line = |suffix: &str, value: f64| {
+                lines.extend(format_line(
+                    &format!("{}{}", name, suffix),
+                    metric,
+                    value,
+                    config,
+                ));
+            };
+            match family.get_field_type() {
+                MetricType::GAUGE => line("", metric.get_gauge().get_value()),
+                MetricType::COUNTER => line("", metric.get_counter().get_value()),
+                MetricType::UNTYPED => line("", metric.get_untyped().get_value()),
+                MetricType::HISTOGRAM => {
+                    let h = metric.get_histogram();
+                    line("_sum", h.get_sample_sum());
+                    line("_count", h.get_sample_count() as f64);
+                }
+                MetricType::SUMMARY => {
+                    let s = metric.get_summary();
+                    line("_sum", s.get_sample_sum());
+                    line("_count", s.get_sample_count() as f64);
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Formats one gauge; plain StatsD needs two lines for negative values.
+fn format_line(name: &str, metric: &Metric, value: f64, config: &StatsdConfig) -> Vec<String> {
+    if !value.is_finite() {
+        return Vec::new();
+    }
+    let labels = metric
+        .get_label()
+        .iter()
+        .filter(|l| !config.drop_labels.iter().any(|d| d == l.get_name()));
+
+    match config.flavor {
+        StatsdFlavor::Dogstatsd => {
+            let tags: Vec<String> = config
+                .tags
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .chain(labels.map(|l| (l.get_name(), l.get_value())))
+                .map(|(k, v)| format!("{}:{}", sanitize_tag(k), sanitize_tag(v)))
+                .collect();
+            let mut line = format!("{}:{}|g", name, value);
+            if !tags.is_empty() {
+                line.push_str("|#");
+                line.push_str(&tags.join(","));
+            }
+            vec![line]
+        }
+        StatsdFlavor::Statsd => {
+            let mut full_name = name.to_string();
+            for label in labels {
+                full_name.push('.');
+                full_name.push_str(&sanitize_name(label.get_value()));
+            }
+            if value < 0.0 {
+                // A leading sign means "adjust" in StatsD; reset to 0 first
+                vec![
+                    format!("{}:0|g", full_name),
+                    format!("{}:{}|g", full_name, value),
+                ]
+            } else {
+                vec![format!("{}:{}|g", full_name, value)]
+            }
+        }
+    }
+}
+
+/// Replaces characters with meaning in DogStatsD tag lists.
+fn sanitize_tag(value: &str) -> String {
+    value.replace([',', '|', '#', '\n'], "_")
+}
+
+/// Keeps metric-name-safe characters only.
+fn sanitize_name(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Joins lines into newline-separated datagrams of at most `max_size` bytes.
+///
+/// A single line longer than `max_size` is sent on its own.
+pub fn pack(lines: &[String], max_size: usize) -> Vec<String> {
+    let mut packets = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty() && current.len() + 1 + line.len() > max_size {
+            packets.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        packets.push(current);
+    }
+    packets
+}
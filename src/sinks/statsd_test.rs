@@ -0,0 +1,91 @@
+//! Tests for the StatsD / DogStatsD sink.
+
+use super::test_support::{gather, test_metrics};
+use crate::metrics::Metrics;
+use crate::sinks::statsd::{format_lines, pack, StatsdConfig, StatsdFlavor, StatsdSink};
+use std::net::UdpSocket;
+use std::time::Duration;
+
+#[test]
+fn test_format_dogstatsd() {
+    let mut config = StatsdConfig::default();
+    config.tags.insert("env".to_string(), "prod".to_string());
+
+    let lines = format_lines(&gather(&test_metrics("web app")), &config);
+    assert_eq!(
+        lines,
+        vec![
+            "railway_service_cost_usd:2.5|g|#env:prod,group:frontend,project:proj,service:web app"
+        ]
+    );
+}
+
+#[test]
+fn test_format_statsd_folds_labels() {
+    let config = StatsdConfig {
+        flavor: StatsdFlavor::Statsd,
+        ..StatsdConfig::default()
+    };
+
+    let lines = format_lines(&gather(&test_metrics("web app")), &config);
+    assert_eq!(
+        lines,
+        vec!["railway_service_cost_usd.frontend.proj.web_app:2.5|g"]
+    );
+}
+
+#[test]
+fn test_format_statsd_negative_value() {
+    let metrics = Metrics::new();
+    metrics.api_up.with_label_values(&["p"]).set(-1.0);
+    let config = StatsdConfig {
+        flavor: StatsdFlavor::Statsd,
+        ..StatsdConfig::default()
+    };
+
+    let lines = format_lines(&gather(&metrics), &config);
+    assert_eq!(lines, vec!["railway_api_up.p:0|g", "railway_api_up.p:-1|g"]);
+}
+
+#[test]
+fn test_pack() {
+    let lines: Vec<String> = ["aaaa", "bbbb", "cccc"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(pack(&lines, 9), vec!["aaaa\nbbbb", "cccc"]);
+    assert_eq!(pack(&lines, 2), vec!["aaaa", "bbbb", "cccc"]);
+    assert!(pack(&[], 100).is_empty());
+}
+
+#[test]
+fn test_validate() {
+    assert!(StatsdConfig::default().validate().is_ok());
+    let config = StatsdConfig {
+        address: "localhost".to_string(),
+        ..StatsdConfig::default()
+    };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_push_over_udp() {
+    let agent = UdpSocket::bind("127.0.0.1:0").unwrap();
+    agent
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let config = StatsdConfig {
+        address: agent.local_addr().unwrap().to_string(),
+        ..StatsdConfig::default()
+    };
+
+    let sink = StatsdSink::new(config).unwrap();
+    assert_eq!(sink.push(&test_metrics("web app")), 1);
+
+    let mut buf = [0u8; 2048];
+    let n = agent.recv(&mut buf).unwrap();
+    let packet = std::str::from_utf8(&buf[..n]).unwrap();
+    assert!(packet
+        .lines()
+        .any(|l| l.starts_with("railway_service_cost_usd:2.5|g|#")));
+}
@@ -0,0 +1,123 @@
+//! Shared helpers for sink tests.
+//!
+//! HTTP sinks are tested against a local capture server that records every
+//! request it accepts.
+
+use crate::metrics::Metrics;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::header::HeaderMap;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use prometheus::proto::MetricFamily;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// A request seen by the capture server.
+pub struct Received {
+    pub method: String,
+    /// Path and query.
+    pub uri: String,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl Received {
+    /// Request path without the query.
+    pub fn path(&self) -> &str {
+        self.uri.split('?').next().unwrap_or_default()
+    }
+
+    /// Header value, or "" if absent.
+    pub fn header(&self, name: &str) -> &str {
+        self.headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+    }
+
+    /// Body as (lossy) UTF-8.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Starts a server on a free port that fails the first `failures` requests
+/// with 503 and records the rest (answered with 204).
+///
+/// Returns the base URL (`http://127.0.0.1:<port>`).
+pub async fn start_capture_server(failures: usize) -> (String, mpsc::UnboundedReceiver<Received>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::unbounded_channel();
+    let seen = Arc::new(AtomicUsize::new(0));
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let tx = tx.clone();
+            let seen = seen.clone();
+            tokio::spawn(async move {
+                let svc = service_fn(move |req: hyper::Request<hyper::body::Incoming>| {
+                    let tx = tx.clone();
+                    let seen = seen.clone();
+                    async move {
+                        let method = req.method().to_string();
+                        let uri = req.uri().to_string();
+                        let headers = req.headers().clone();
+                        let body = req.into_body().collect().await.unwrap().to_bytes();
+                        let status = if seen.fetch_add(1, Ordering::SeqCst) < failures {
+                            StatusCode::SERVICE_UNAVAILABLE
+                        } else {
+                            let _ = tx.send(Received {
+                                method,
+                                uri,
+                                headers,
+                                body: body.to_vec(),
+                            });
+                            StatusCode::NO_CONTENT
+                        };
+                        let resp = Response::builder()
+                            .status(status)
+                            .body(Full::new(Bytes::new()))
+                            .unwrap();
+                        Ok::<_, std::convert::Infallible>(resp)
+                    }
+                });
+                let _ = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), svc)
+                    .await;
+            });
+        }
+    });
+
+    (url, rx)
+}
+
+/// Waits up to five seconds for the next recorded request.
+pub async fn next_request(rx: &mut mpsc::UnboundedReceiver<Received>) -> Received {
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Metrics with a single `railway_service_cost_usd` series for `service`.
+pub fn test_metrics(service: &str) -> Metrics {
+    let metrics = Metrics::new();
+    metrics
+        .service_cost
+        .with_label_values(&[service, "proj", "🌐", "frontend"])
+        .set(2.5);
+    metrics
+}
+
+/// Gathers the families under test, leaving out the exporter's own metrics.
+pub fn gather(metrics: &Metrics) -> Vec<MetricFamily> {
+    let mut families = metrics.gather();
+    families.retain(|f| !f.get_name().starts_with("railway_exporter_"));
+    families
+}
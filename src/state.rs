@@ -1,10 +1,13 @@
 //! Application state management.
 
+use crate::chargeback::ChargebackLedger;
+use crate::handlers::{MetricsCache, SELF_METRICS_INTERVAL};
 use crate::metrics::Metrics;
 use crate::types::MetricsJson;
 use crate::utils::{create_icon_cache, ProcessInfoProvider, SharedIconCache};
 use crate::Config;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::{broadcast, RwLock};
 
@@ -29,6 +32,8 @@ pub struct AppState {
     pub metrics: Metrics,
     /// Cached JSON metrics for /metrics.json endpoint.
     pub metrics_json: RwLock<Option<MetricsJson>>,
+    /// Rendered `/metrics` bodies from the last collection.
+    pub metrics_cache: std::sync::RwLock<Option<Arc<MetricsCache>>>,
     /// Server start time.
     pub start_time: Instant,
     /// API status tracking.
@@ -39,8 +44,12 @@ pub struct AppState {
     pub process_info: ProcessInfoProvider,
     /// Number of active WebSocket clients.
     pub ws_clients: AtomicU32,
+    /// Number of open HTTP connections.
+    pub connections: AtomicUsize,
     /// Icon cache - stores Base64 data URLs for service icons.
     pub icon_cache: SharedIconCache,
+    /// Monthly cost center totals (open period + frozen history).
+    pub chargeback: RwLock<ChargebackLedger>,
 }
 
 impl AppState {
@@ -50,25 +59,44 @@ impl AppState {
         let icon_cache_capacity = config.icon_cache.max_count;
 
         Self {
-            metrics: Metrics::new(),
+            metrics: Metrics::with_config(&config.metrics),
             metrics_json: RwLock::new(None),
+            metrics_cache: std::sync::RwLock::new(None),
             start_time: Instant::now(),
             api_status: RwLock::new(ApiStatusData::default()),
             ws_broadcast: ws_tx,
             process_info: ProcessInfoProvider::new(),
             ws_clients: AtomicU32::new(0),
+            connections: AtomicUsize::new(0),
             icon_cache: create_icon_cache(icon_cache_capacity),
+            chargeback: RwLock::new(ChargebackLedger::load(
+                config.chargeback.state_file.as_deref(),
+            )),
             config,
         }
     }
 
     /// Increment WebSocket client count.
     pub fn ws_client_connect(&self) -> u32 {
+        self.metrics.ws_connections.inc();
         self.ws_clients.fetch_add(1, Ordering::SeqCst) + 1
     }
 
+    /// Increment WebSocket client count unless `limits.max_ws_clients` is reached.
+    pub fn ws_client_try_connect(&self) -> Option<u32> {
+        let max = self.config.limits.max_ws_clients.unwrap_or(u32::MAX);
+        self.ws_clients
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .ok()?;
+        self.metrics.ws_connections.inc();
+        Some(self.ws_client_count())
+    }
+
     /// Decrement WebSocket client count.
     pub fn ws_client_disconnect(&self) -> u32 {
+        self.metrics.ws_disconnections.inc();
         self.ws_clients.fetch_sub(1, Ordering::SeqCst) - 1
     }
 
@@ -76,4 +104,62 @@ impl AppState {
     pub fn ws_client_count(&self) -> u32 {
         self.ws_clients.load(Ordering::SeqCst)
     }
+
+    /// Counts a new connection unless `limits.max_connections` is reached.
+    pub fn connection_open(&self) -> bool {
+        let max = self.config.limits.max_connections.unwrap_or(usize::MAX);
+        self.connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .is_ok()
+    }
+
+    /// Counts a closed connection.
+    pub fn connection_close(&self) {
+        self.connections.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Re-renders the `/metrics` bodies (after every collection).
+    pub async fn refresh_metrics_cache(&self) {
+        self.metrics.update_process_metrics();
+        let cache = {
+            let json = self.metrics_json.read().await;
+            MetricsCache::render(&self.metrics, json.as_ref(), &self.config.compression)
+        };
+        *self.metrics_cache.write().unwrap() = Some(Arc::new(cache));
+    }
+
+    /// Re-renders the self-metrics part of the `/metrics` bodies.
+    pub fn refresh_self_metrics(&self) {
+        self.metrics.update_process_metrics();
+        let Some(current) = self.metrics_cache() else {
+            return;
+        };
+        let refreshed =
+            Arc::new(current.with_self_metrics(&self.metrics, &self.config.compression));
+
+        // Keep a cache rendered by a collection that finished meanwhile
+        let mut cache = self.metrics_cache.write().unwrap();
+        if cache.as_ref().is_some_and(|c| Arc::ptr_eq(c, &current)) {
+            *cache = Some(refreshed);
+        }
+    }
+
+    /// Refreshes the self-metrics every `SELF_METRICS_INTERVAL` in the background.
+    pub fn spawn_self_metrics_refresher(self: &Arc<Self>) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(SELF_METRICS_INTERVAL);
+            loop {
+                ticker.tick().await;
+                state.refresh_self_metrics();
+            }
+        });
+    }
+
+    /// Returns the rendered `/metrics` bodies, if a collection has run.
+    pub fn metrics_cache(&self) -> Option<Arc<MetricsCache>> {
+        self.metrics_cache.read().unwrap().clone()
+    }
 }
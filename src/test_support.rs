@@ -0,0 +1,48 @@
+//! Shared test helpers: server startup and fixtures.
+
+use crate::server::serve_connection;
+use crate::state::AppState;
+use crate::types::ServiceData;
+use crate::Config;
+use std::sync::Arc;
+
+/// Runs the real connection handler for `config` on a random port and
+/// returns its address (`127.0.0.1:<port>`).
+pub async fn start_test_server(config: Config) -> String {
+    serve_state(Arc::new(AppState::new(config))).await
+}
+
+/// Like [`start_test_server`], for tests that inspect the state afterwards.
+pub async fn serve_state(state: Arc<AppState>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, peer)) = listener.accept().await {
+            tokio::spawn(serve_connection(stream, Some(peer.ip()), state.clone()));
+        }
+    });
+    addr.to_string()
+}
+
+/// A service row in `groups` (the first is its primary group) costing `cost`.
+///
+/// Usage and estimates are derived from `cost`: CPU `cost * 10`, memory
+/// `cost * 20`, network 1, estimated monthly `cost * 3`. Override fields with
+/// struct update syntax where a test needs other values.
+pub fn service(name: &str, groups: &[&str], cost: f64) -> ServiceData {
+    ServiceData {
+        id: format!("id-{}", name),
+        name: name.to_string(),
+        icon: "📦".to_string(),
+        group: groups[0].to_string(),
+        groups: groups.iter().map(|g| g.to_string()).collect(),
+        group_matches: Vec::new(),
+        cpu_usage: cost * 10.0,
+        memory_usage: cost * 20.0,
+        disk_usage: 0.0,
+        network_tx: 1.0,
+        cost_usd: cost,
+        estimated_monthly_usd: cost * 3.0,
+        is_deleted: false,
+    }
+}
@@ -0,0 +1,226 @@
+//! Native TLS termination (rustls).
+//!
+//! With a `tls` section the server speaks HTTPS (and `wss://` for `/ws`) on
+//! the configured port instead of plain HTTP. Certificates are PEM files;
+//! the certificate file may contain the full chain.
+//!
+//! Setting `client_ca_file` enables mutual TLS: clients must present a
+//! certificate signed by one of the CAs in that file (or may, with
+//! `client_auth: optional`).
+//!
+//! The files are checked every `reload_interval` seconds and the server
+//! configuration is rebuilt when any of them changes, so renewed certificates
+//! (certbot, cert-manager) are picked up without a restart. Established
+//! connections keep the certificate they were opened with. A reload that
+//! fails (e.g. key and certificate replaced one at a time) keeps the previous
+//! configuration and is retried on the next check.
+//!
+//! ## YAML Format
+//!
+//! ```yaml
+//! tls:
+//!   cert_file: /etc/exporter/tls.crt
+//!   key_file: /etc/exporter/tls.key
+//!   client_ca_file: /etc/exporter/clients-ca.crt  # optional, enables mTLS
+//!   client_auth: required                          # required (default) or optional
+//!   reload_interval: 60                            # seconds, 0 disables reload
+//! ```
+
+use crate::config::ConfigError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tracing::{info, warn};
+
+/// Whether clients must present a certificate (with `client_ca_file`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuth {
+    /// Reject clients without a valid certificate.
+    #[default]
+    Required,
+    /// Verify certificates if presented, accept anonymous clients.
+    Optional,
+}
+
+/// TLS settings as written in YAML.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TlsConfig {
+    /// PEM certificate (chain) file.
+    pub cert_file: String,
+    /// PEM private key file (PKCS#8, PKCS#1 or SEC1).
+    pub key_file: String,
+    /// PEM file with CAs trusted for client certificates (enables mTLS).
+    #[serde(default)]
+    pub client_ca_file: Option<String>,
+    #[serde(default)]
+    pub client_auth: ClientAuth,
+    /// Seconds between file change checks (0 = never reload).
+    #[serde(default = "default_reload_interval")]
+    pub reload_interval: u64,
+}
+
+fn default_reload_interval() -> u64 {
+    60
+}
+
+impl TlsConfig {
+    /// Creates a config for a certificate and key with default settings.
+    pub fn new(cert_file: &str, key_file: &str) -> Self {
+        Self {
+            cert_file: cert_file.to_string(),
+            key_file: key_file.to_string(),
+            client_ca_file: None,
+            client_auth: ClientAuth::default(),
+            reload_interval: default_reload_interval(),
+        }
+    }
+
+    /// Checks that the files load into a usable server configuration.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.server_config().map(|_| ())
+    }
+
+    /// Loads the files and builds a rustls server configuration.
+    pub fn server_config(&self) -> Result<ServerConfig, ConfigError> {
+        let err = |msg: String| ConfigError::InvalidValue(format!("tls: {}", msg));
+        let provider = Arc::new(ring::default_provider());
+
+        let certs = load_certs(&self.cert_file).map_err(err)?;
+        let key = PrivateKeyDer::from_pem_file(&self.key_file)
+            .map_err(|e| err(format!("cannot read key_file '{}': {}", self.key_file, e)))?;
+
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| err(e.to_string()))?;
+        let builder = match &self.client_ca_file {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(path).map_err(err)? {
+                    roots
+                        .add(cert)
+                        .map_err(|e| err(format!("invalid CA in '{}': {}", path, e)))?;
+                }
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone());
+                let verifier = match self.client_auth {
+                    ClientAuth::Required => verifier,
+                    ClientAuth::Optional => verifier.allow_unauthenticated(),
+                };
+                builder.with_client_cert_verifier(
+                    verifier
+                        .build()
+                        .map_err(|e| err(format!("client_ca_file: {}", e)))?,
+                )
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder.with_single_cert(certs, key).map_err(|e| {
+            err(format!(
+                "certificate '{}' does not match key '{}': {}",
+                self.cert_file, self.key_file, e
+            ))
+        })?;
+        // Only HTTP/1.1 is served (WebSocket upgrades need it too)
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(config)
+    }
+
+    /// Paths watched for changes.
+    fn files(&self) -> Vec<&str> {
+        let mut files = vec![self.cert_file.as_str(), self.key_file.as_str()];
+        files.extend(self.client_ca_file.as_deref());
+        files
+    }
+}
+
+/// Reads all certificates from a PEM file.
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("cannot read '{}': {}", path, e))?;
+    if certs.is_empty() {
+        return Err(format!("no certificates found in '{}'", path));
+    }
+    Ok(certs)
+}
+
+/// Modification time of a file (None if missing).
+fn modified(path: &str) -> Option<SystemTime> {
+    Path::new(path).metadata().and_then(|m| m.modified()).ok()
+}
+
+/// TLS acceptor whose certificate can be swapped while the server runs.
+#[derive(Debug)]
+pub struct ReloadingAcceptor {
+    config: TlsConfig,
+    current: RwLock<Arc<ServerConfig>>,
+    /// File modification times of the loaded configuration.
+    stamps: Mutex<Vec<Option<SystemTime>>>,
+}
+
+impl ReloadingAcceptor {
+    /// Loads the initial configuration.
+    pub fn new(config: TlsConfig) -> Result<Arc<Self>, ConfigError> {
+        let stamps = config.files().into_iter().map(modified).collect();
+        let server_config = config.server_config()?;
+        Ok(Arc::new(Self {
+            config,
+            current: RwLock::new(Arc::new(server_config)),
+            stamps: Mutex::new(stamps),
+        }))
+    }
+
+    /// Returns an acceptor using the current certificate.
+    pub fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(self.current.read().unwrap().clone())
+    }
+
+    /// Reloads the configuration if any file changed since the last load.
+    ///
+    /// Returns true if a new configuration was installed.
+    pub fn reload_if_changed(&self) -> bool {
+        let stamps: Vec<_> = self.config.files().into_iter().map(modified).collect();
+        let mut loaded = self.stamps.lock().unwrap();
+        if *loaded == stamps {
+            return false;
+        }
+
+        match self.config.server_config() {
+            Ok(server_config) => {
+                *self.current.write().unwrap() = Arc::new(server_config);
+                *loaded = stamps;
+                info!("TLS certificate reloaded from {}", self.config.cert_file);
+                true
+            }
+            Err(e) => {
+                warn!("TLS reload failed, keeping previous certificate: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Starts a background task checking the files every `reload_interval` seconds.
+    pub fn spawn_reloader(self: &Arc<Self>) {
+        if self.config.reload_interval == 0 {
+            return;
+        }
+        let acceptor = self.clone();
+        tokio::spawn(async move {
+            let period = Duration::from_secs(acceptor.config.reload_interval);
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                ticker.tick().await;
+                acceptor.reload_if_changed();
+            }
+        });
+    }
+}
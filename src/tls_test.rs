@@ -0,0 +1,305 @@
+//! Tests for native TLS termination.
+
+use crate::config::Plan;
+use crate::server::serve_tls_connection;
+use crate::state::AppState;
+use crate::tls::{ClientAuth, ReloadingAcceptor, TlsConfig};
+use crate::Config;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+fn testdata(name: &str) -> String {
+    format!("{}/src/testdata/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn server_tls() -> TlsConfig {
+    TlsConfig::new(&testdata("server.pem"), &testdata("server.key"))
+}
+
+fn mtls(client_auth: ClientAuth) -> TlsConfig {
+    TlsConfig {
+        client_ca_file: Some(testdata("ca.pem")),
+        client_auth,
+        ..server_tls()
+    }
+}
+
+/// Starts a TLS server with the given acceptor, returns its port.
+async fn start_tls_server(acceptor: Arc<ReloadingAcceptor>) -> u16 {
+    let mut config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
+    config.websocket_enabled = true;
+    start_tls_server_with(config, acceptor).await.1
+}
+
+/// Starts a TLS server with the given config, returns its state and port.
+async fn start_tls_server_with(
+    config: Config,
+    acceptor: Arc<ReloadingAcceptor>,
+) -> (Arc<AppState>, u16) {
+    let state = Arc::new(AppState::new(config));
+    let server_state = state.clone();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((stream, peer)) = listener.accept().await {
+            tokio::spawn(serve_tls_connection(
+                stream,
+                Some(peer.ip()),
+                acceptor.acceptor(),
+                server_state.clone(),
+            ));
+        }
+    });
+    (state, port)
+}
+
+/// Opens a TLS connection trusting the test CA, optionally with a client certificate.
+async fn connect(port: u16, client_cert: bool) -> std::io::Result<TlsStream<TcpStream>> {
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(testdata("ca.pem")).unwrap() {
+        roots.add(cert.unwrap()).unwrap();
+    }
+    let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots);
+    let config = if client_cert {
+        let certs = CertificateDer::pem_file_iter(testdata("client.pem"))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let key = PrivateKeyDer::from_pem_file(testdata("client.key")).unwrap();
+        builder.with_client_auth_cert(certs, key).unwrap()
+    } else {
+        builder.with_no_client_auth()
+    };
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).await?;
+    TlsConnector::from(Arc::new(config))
+        .connect(ServerName::try_from("localhost").unwrap(), stream)
+        .await
+}
+
+/// Sends `GET /health` and returns the raw response (empty on failure).
+async fn get_health(port: u16, client_cert: bool) -> String {
+    let Ok(mut stream) = connect(port, client_cert).await else {
+        return String::new();
+    };
+    let request = b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+    if stream.write_all(request).await.is_err() {
+        return String::new();
+    }
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response).await;
+    String::from_utf8_lossy(&response).into_owned()
+}
+
+/// Copies the server certificate and key into a fresh directory.
+fn temp_cert_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tls-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(testdata("server.pem"), dir.join("tls.crt")).unwrap();
+    std::fs::copy(testdata("server.key"), dir.join("tls.key")).unwrap();
+    dir
+}
+
+/// Overwrites a file and moves its modification time forward.
+fn replace_file(path: &PathBuf, contents: &[u8]) {
+    std::fs::write(path, contents).unwrap();
+    let file = std::fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+}
+
+// =============================================================================
+// Config Tests
+// =============================================================================
+
+#[test]
+fn test_validate() {
+    assert!(server_tls().validate().is_ok());
+    assert!(mtls(ClientAuth::Required).validate().is_ok());
+
+    let invalid = vec![
+        TlsConfig::new(&testdata("missing.pem"), &testdata("server.key")),
+        TlsConfig::new(&testdata("server.pem"), &testdata("missing.key")),
+        // Key of another certificate
+        TlsConfig::new(&testdata("server.pem"), &testdata("server2.key")),
+        // Not a certificate
+        TlsConfig::new(&testdata("server.key"), &testdata("server.key")),
+        TlsConfig {
+            client_ca_file: Some(testdata("missing.pem")),
+            ..server_tls()
+        },
+    ];
+    for config in invalid {
+        assert!(config.validate().is_err(), "{:?}", config);
+    }
+}
+
+#[test]
+fn test_yaml_defaults() {
+    let config: TlsConfig = serde_yaml::from_str("cert_file: a.crt\nkey_file: a.key").unwrap();
+    assert_eq!(config.client_ca_file, None);
+    assert_eq!(config.client_auth, ClientAuth::Required);
+    assert_eq!(config.reload_interval, 60);
+
+    let config: TlsConfig =
+        serde_yaml::from_str("cert_file: a\nkey_file: b\nclient_auth: optional").unwrap();
+    assert_eq!(config.client_auth, ClientAuth::Optional);
+}
+
+// =============================================================================
+// Server Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_https_request() {
+    let port = start_tls_server(ReloadingAcceptor::new(server_tls()).unwrap()).await;
+    let response = get_health(port, false).await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+}
+
+#[tokio::test]
+async fn test_wss_upgrade() {
+    use futures_util::StreamExt;
+
+    let port = start_tls_server(ReloadingAcceptor::new(server_tls()).unwrap()).await;
+    let stream = connect(port, false).await.unwrap();
+    let (mut ws, response) = tokio_tungstenite::client_async("wss://localhost/ws", stream)
+        .await
+        .expect("WebSocket over TLS");
+    assert_eq!(response.status(), 101);
+
+    let msg = ws.next().await.unwrap().unwrap();
+    assert!(msg.into_text().unwrap().contains("\"status\""));
+}
+
+#[tokio::test]
+async fn test_mtls_required() {
+    let port = start_tls_server(ReloadingAcceptor::new(mtls(ClientAuth::Required)).unwrap()).await;
+
+    let response = get_health(port, true).await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+
+    // No client certificate: handshake rejected
+    assert_eq!(get_health(port, false).await, "");
+}
+
+#[tokio::test]
+async fn test_mtls_optional() {
+    let port = start_tls_server(ReloadingAcceptor::new(mtls(ClientAuth::Optional)).unwrap()).await;
+
+    assert!(get_health(port, false).await.starts_with("HTTP/1.1 200"));
+    assert!(get_health(port, true).await.starts_with("HTTP/1.1 200"));
+}
+
+// =============================================================================
+// Reload Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_handshake_holds_slot_and_times_out() {
+    let mut config = Config::new("test-token", "test-project", Plan::Pro, 300, 0);
+    config.limits.max_connections = Some(1);
+    config.limits.header_read_timeout = Some(Duration::from_millis(500));
+    let (state, port) =
+        start_tls_server_with(config, ReloadingAcceptor::new(server_tls()).unwrap()).await;
+
+    // A client that never starts the handshake takes the only slot
+    let mut stalled = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(state.connections.load(Ordering::SeqCst), 1);
+    assert!(get_health(port, false).await.starts_with("HTTP/1.1 503"));
+
+    // The handshake times out and releases the slot
+    let mut buf = [0u8; 1];
+    let read = tokio::time::timeout(Duration::from_secs(5), stalled.read(&mut buf))
+        .await
+        .unwrap();
+    assert!(matches!(read, Ok(0) | Err(_)));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(state.connections.load(Ordering::SeqCst), 0);
+    assert!(get_health(port, false).await.starts_with("HTTP/1.1 200"));
+}
+
+#[tokio::test]
+async fn test_reload_on_change() {
+    let dir = temp_cert_dir("reload");
+    let config = TlsConfig::new(
+        dir.join("tls.crt").to_str().unwrap(),
+        dir.join("tls.key").to_str().unwrap(),
+    );
+    let acceptor = ReloadingAcceptor::new(config).unwrap();
+    let port = start_tls_server(acceptor.clone()).await;
+    assert!(!acceptor.reload_if_changed());
+
+    let peer_cert = |stream: &TlsStream<TcpStream>| {
+        stream.get_ref().1.peer_certificates().unwrap()[0]
+            .as_ref()
+            .to_vec()
+    };
+    let first = peer_cert(&connect(port, false).await.unwrap());
+    let server2 = std::fs::read(testdata("server2.pem")).unwrap();
+    assert_eq!(
+        first,
+        CertificateDer::from_pem_file(testdata("server.pem"))
+            .unwrap()
+            .as_ref()
+    );
+
+    replace_file(&dir.join("tls.crt"), &server2);
+    replace_file(
+        &dir.join("tls.key"),
+        &std::fs::read(testdata("server2.key")).unwrap(),
+    );
+    assert!(acceptor.reload_if_changed());
+    assert!(!acceptor.reload_if_changed());
+
+    let second = peer_cert(&connect(port, false).await.unwrap());
+    assert_eq!(
+        second,
+        CertificateDer::from_pem_slice(&server2).unwrap().as_ref()
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_failed_reload_keeps_certificate() {
+    let dir = temp_cert_dir("broken");
+    let config = TlsConfig::new(
+        dir.join("tls.crt").to_str().unwrap(),
+        dir.join("tls.key").to_str().unwrap(),
+    );
+    let acceptor = ReloadingAcceptor::new(config).unwrap();
+    let port = start_tls_server(acceptor.clone()).await;
+
+    // Certificate replaced, key not yet: mismatch
+    replace_file(
+        &dir.join("tls.crt"),
+        &std::fs::read(testdata("server2.pem")).unwrap(),
+    );
+    assert!(!acceptor.reload_if_changed());
+    assert!(get_health(port, false).await.starts_with("HTTP/1.1 200"));
+
+    // Retried once the key arrives
+    replace_file(
+        &dir.join("tls.key"),
+        &std::fs::read(testdata("server2.key")).unwrap(),
+    );
+    assert!(acceptor.reload_if_changed());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
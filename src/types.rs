@@ -1,7 +1,13 @@
 //! Shared types for Railway Exporter.
 
 use crate::config::PriceValues;
+use crate::grouping::{GroupMatch, UNGROUPED};
 use serde::{Deserialize, Serialize};
+
+/// Reserved service ID of the bucket that collects filtered or collapsed
+/// spend. Railway IDs are UUIDs, so it never clashes with a real service.
+pub const OTHER_ID: &str = "__other__";
+
 // ============================================================================
 // JSON Response Types
 // ============================================================================
@@ -12,7 +18,13 @@ pub struct ServiceData {
     pub id: String,
     pub name: String,
     pub icon: String,
+    /// Primary group (first matching rule); used as the metric `group` label.
     pub group: String,
+    /// All groups the service belongs to (more than one with `service_groups_multi`).
+    pub groups: Vec<String>,
+    /// Rules that assigned `groups`, recorded at collection time for `/status`.
+    #[serde(skip)]
+    pub group_matches: Vec<GroupMatch>,
     pub cpu_usage: f64,
     pub memory_usage: f64,
     pub disk_usage: f64,
@@ -23,6 +35,30 @@ pub struct ServiceData {
     pub is_deleted: bool,
 }
 
+impl ServiceData {
+    /// Empty bucket row for spend folded out of the per-service series.
+    ///
+    /// Identified by [`OTHER_ID`]; `name` is only the display name. The bucket
+    /// is rolled up under the `ungrouped` group.
+    pub fn other(name: &str) -> Self {
+        Self {
+            id: OTHER_ID.to_string(),
+            name: name.to_string(),
+            icon: String::new(),
+            group: UNGROUPED.to_string(),
+            groups: vec![UNGROUPED.to_string()],
+            group_matches: Vec::new(),
+            cpu_usage: 0.0,
+            memory_usage: 0.0,
+            disk_usage: 0.0,
+            network_tx: 0.0,
+            cost_usd: 0.0,
+            estimated_monthly_usd: 0.0,
+            is_deleted: false,
+        }
+    }
+}
+
 /// Project summary for JSON output.
 #[derive(Clone, Serialize, Debug)]
 pub struct ProjectSummary {
@@ -34,11 +70,50 @@ pub struct ProjectSummary {
     pub days_remaining: u32,
 }
 
+/// Per-group rollup for JSON output.
+///
+/// With multi-group membership a service is counted in each of its groups,
+/// so shares across groups may add up to more than 1.0.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct GroupSummary {
+    pub name: String,
+    pub service_count: usize,
+    pub cpu_usage: f64,
+    pub memory_usage: f64,
+    pub disk_usage: f64,
+    pub network_tx: f64,
+    pub cost_usd: f64,
+    pub estimated_monthly_usd: f64,
+    /// Fraction of the project's current cost (0.0 - 1.0).
+    pub cost_share: f64,
+}
+
+/// Group cost after shared-cost redistribution (`cost_allocation`).
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct AllocatedGroup {
+    pub name: String,
+    /// Raw cost of the group's own services.
+    pub direct_cost_usd: f64,
+    /// Cost moved into this group from shared groups.
+    pub received_usd: f64,
+    /// Cost moved out of this group to its consumers.
+    pub distributed_usd: f64,
+    /// Cost after redistribution.
+    pub allocated_cost_usd: f64,
+    /// Estimated monthly cost after redistribution.
+    pub allocated_estimated_monthly_usd: f64,
+}
+
 /// Full metrics JSON response.
 #[derive(Clone, Serialize, Debug)]
 pub struct MetricsJson {
     pub project: ProjectSummary,
     pub services: Vec<ServiceData>,
+    /// Group rollups, sorted by cost (highest first).
+    pub groups: Vec<GroupSummary>,
+    /// Group costs after redistribution (only present when `cost_allocation` is configured).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allocated: Vec<AllocatedGroup>,
     pub scrape_timestamp: i64,
     pub scrape_duration_seconds: f64,
 }
@@ -81,11 +156,25 @@ pub struct ServerStatus {
     pub config: ConfigStatus,
     pub process: ProcessStatus,
     pub api: ApiStatus,
+    /// Grouping rule that matched each service from the last scrape.
+    pub service_groups: Vec<ServiceGroupAssignment>,
     /// Icon cache statistics (only present in base64 mode).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub icon_cache: Option<crate::utils::IconCacheStats>,
 }
 
+/// Group assignment of one service, with the rules that produced it.
+#[derive(Serialize, Debug, Clone)]
+pub struct ServiceGroupAssignment {
+    pub service: String,
+    /// Primary group, as exported in the `group` label.
+    pub group: String,
+    /// Every group the service belongs to.
+    pub groups: Vec<String>,
+    /// Matching rules in order; empty means the service is ungrouped.
+    pub matches: Vec<GroupMatch>,
+}
+
 /// Endpoint availability status (from config).
 #[derive(Serialize, Debug, Clone)]
 pub struct EndpointStatus {
@@ -93,6 +182,8 @@ pub struct EndpointStatus {
     pub json: bool,
     pub websocket: bool,
     pub health: bool,
+    /// Chargeback report (only when cost centers are configured).
+    pub chargeback: bool,
 }
 
 /// Config status exposed to frontend.
@@ -106,6 +197,10 @@ pub struct ConfigStatus {
     pub prices: PriceValues,
     /// Gzip compression configuration from YAML.
     pub gzip: crate::config::GzipConfig,
+    /// Brotli compression configuration from YAML.
+    pub brotli: crate::config::BrotliConfig,
+    /// Zstandard compression configuration from YAML.
+    pub zstd: crate::config::ZstdConfig,
     /// Icon cache configuration (fields depend on mode).
     pub icon_cache: IconCacheStatusConfig,
 }
@@ -3,8 +3,9 @@
 use crate::config::{NetworkPricing, PriceValues};
 use crate::types::{
     ApiStatus, ConfigStatus, EndpointStatus, EstimatedData, EstimatedItem, GraphQLRequest,
-    GraphQLResponse, IconCacheStatusConfig, MetricsJson, ProcessStatus, Project, ProjectData,
-    ProjectSummary, ServerStatus, ServiceData, UsageData, UsageItem, WsMessage, WsStatus,
+    GraphQLResponse, GroupSummary, IconCacheStatusConfig, MetricsJson, ProcessStatus, Project,
+    ProjectData, ProjectSummary, ServerStatus, ServiceData, UsageData, UsageItem, WsMessage,
+    WsStatus,
 };
 
 // =============================================================================
@@ -18,6 +19,8 @@ fn test_service_data_serialize() {
         name: "web".to_string(),
         icon: "🌐".to_string(),
         group: "frontend".to_string(),
+        groups: vec!["frontend".to_string()],
+        group_matches: Vec::new(),
         cpu_usage: 123.45,
         memory_usage: 256.0,
         disk_usage: 1024.0,
@@ -50,6 +53,8 @@ fn test_service_data_deleted_flag_rename() {
         name: "deleted-service".to_string(),
         icon: "".to_string(),
         group: "default".to_string(),
+        groups: vec!["default".to_string()],
+        group_matches: Vec::new(),
         cpu_usage: 0.0,
         memory_usage: 0.0,
         disk_usage: 0.0,
@@ -112,6 +117,8 @@ fn test_metrics_json_serialize() {
             name: "api".to_string(),
             icon: "🚀".to_string(),
             group: "backend".to_string(),
+            groups: vec!["backend".to_string()],
+            group_matches: Vec::new(),
             cpu_usage: 50.0,
             memory_usage: 128.0,
             disk_usage: 512.0,
@@ -120,6 +127,18 @@ fn test_metrics_json_serialize() {
             estimated_monthly_usd: 15.0,
             is_deleted: false,
         }],
+        groups: vec![GroupSummary {
+            name: "backend".to_string(),
+            service_count: 1,
+            cpu_usage: 50.0,
+            memory_usage: 128.0,
+            disk_usage: 512.0,
+            network_tx: 10.0,
+            cost_usd: 0.5,
+            estimated_monthly_usd: 15.0,
+            cost_share: 0.05,
+        }],
+        allocated: vec![],
         scrape_timestamp: 1700000000,
         scrape_duration_seconds: 0.123,
     };
@@ -130,6 +149,9 @@ fn test_metrics_json_serialize() {
     assert_eq!(parsed["project"]["name"], "test");
     assert_eq!(parsed["services"].as_array().unwrap().len(), 1);
     assert_eq!(parsed["services"][0]["name"], "api");
+    assert_eq!(parsed["groups"][0]["name"], "backend");
+    assert_eq!(parsed["groups"][0]["service_count"], 1);
+    assert_eq!(parsed["groups"][0]["cost_share"], 0.05);
     assert_eq!(parsed["scrape_timestamp"], 1700000000);
     assert_eq!(parsed["scrape_duration_seconds"], 0.123);
 }
@@ -150,6 +172,8 @@ fn test_ws_message_metrics_serialize() {
             days_remaining: 20,
         },
         services: vec![],
+        groups: vec![],
+        allocated: vec![],
         scrape_timestamp: 1700000000,
         scrape_duration_seconds: 0.1,
     };
@@ -204,6 +228,7 @@ fn test_server_status_serialize() {
             json: true,
             websocket: true,
             health: true,
+            chargeback: false,
         },
         config: ConfigStatus {
             plan: "pro".to_string(),
@@ -217,6 +242,8 @@ fn test_server_status_serialize() {
                 network: Some(NetworkPricing { tx: Some(0.10) }),
             },
             gzip: crate::config::GzipConfig::default(),
+            brotli: crate::config::BrotliConfig::default(),
+            zstd: crate::config::ZstdConfig::default(),
             icon_cache: IconCacheStatusConfig {
                 enabled: true,
                 mode: crate::config::IconMode::Base64,
@@ -236,6 +263,7 @@ fn test_server_status_serialize() {
             total_scrapes: 50,
             failed_scrapes: 1,
         },
+        service_groups: vec![],
         icon_cache: Some(crate::utils::IconCacheStats::default()),
     };
 
@@ -256,6 +284,8 @@ fn test_server_status_serialize() {
     assert_eq!(parsed["config"]["gzip"]["enabled"], true);
     assert_eq!(parsed["config"]["gzip"]["min_size"], 256);
     assert_eq!(parsed["config"]["gzip"]["level"], 1);
+    assert_eq!(parsed["config"]["brotli"]["level"], 4);
+    assert_eq!(parsed["config"]["zstd"]["level"], 3);
     assert_eq!(parsed["config"]["icon_cache"]["enabled"], true);
     assert_eq!(parsed["config"]["icon_cache"]["max_count"], 200);
     assert_eq!(parsed["process"]["pid"], 12345);
@@ -429,6 +459,7 @@ fn test_endpoint_status_all_enabled() {
         json: true,
         websocket: true,
         health: true,
+        chargeback: false,
     };
 
     let json = serde_json::to_string(&status).unwrap();
@@ -447,6 +478,7 @@ fn test_endpoint_status_websocket_disabled() {
         json: true,
         websocket: false,
         health: true,
+        chargeback: true,
     };
 
     let json = serde_json::to_string(&status).unwrap();
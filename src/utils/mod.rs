@@ -1,7 +1,9 @@
 //! Utility modules.
 
 mod icons;
+pub mod negotiate;
 mod process_info;
+pub mod protobuf;
 
 pub use icons::{create_icon_cache, CachedIcon, IconCache, IconCacheStats, SharedIconCache};
 pub use process_info::ProcessInfoProvider;
@@ -13,3 +15,7 @@ mod icons_test;
 #[cfg(test)]
 #[path = "process_info_test.rs"]
 mod process_info_test;
+
+#[cfg(test)]
+#[path = "negotiate_test.rs"]
+mod negotiate_test;
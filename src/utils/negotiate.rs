@@ -0,0 +1,58 @@
+//! HTTP content negotiation helpers.
+//!
+//! Parses `Accept`-style header values (`Accept`, `Accept-Encoding`) into
+//! weighted entries so callers can pick the best representation they support.
+
+/// A single entry of an `Accept`-style header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityItem {
+    /// Media type or coding, lowercased (e.g. `application/openmetrics-text`, `gzip`).
+    pub value: String,
+    /// Parameters other than `q`, with lowercased names (e.g. `version=1.0.0`).
+    pub params: Vec<(String, String)>,
+    /// Quality weight (0.0 - 1.0, default 1.0).
+    pub q: f32,
+}
+
+impl QualityItem {
+    /// Returns the value of a parameter, if present.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Parses a header value into entries, highest quality first.
+///
+/// Entries with equal quality keep their original order. Entries with
+/// `q=0` are kept (they explicitly reject a value) and sort last.
+pub fn quality_list(header: &str) -> Vec<QualityItem> {
+    let mut items: Vec<QualityItem> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';').map(str::trim);
+            let value = parts.next().filter(|v| !v.is_empty())?.to_ascii_lowercase();
+            let mut q = 1.0;
+            let mut params = Vec::new();
+            for param in parts {
+                let Some((name, val)) = param.split_once('=') else {
+                    continue;
+                };
+                let name = name.trim().to_ascii_lowercase();
+                let val = val.trim().trim_matches('"');
+                if name == "q" {
+                    q = val.parse::<f32>().unwrap_or(0.0).clamp(0.0, 1.0);
+                } else {
+                    params.push((name, val.to_string()));
+                }
+            }
+            Some(QualityItem { value, params, q })
+        })
+        .collect();
+
+    // Stable sort keeps header order for equal weights
+    items.sort_by(|a, b| b.q.total_cmp(&a.q));
+    items
+}
@@ -0,0 +1,39 @@
+//! Tests for content negotiation helpers.
+
+use super::negotiate::quality_list;
+
+#[test]
+fn test_quality_list_orders_by_q() {
+    let items = quality_list(
+        "text/plain;version=0.0.4;q=0.5, application/openmetrics-text;version=1.0.0, */*;q=0.1",
+    );
+    let values: Vec<&str> = items.iter().map(|i| i.value.as_str()).collect();
+    assert_eq!(
+        values,
+        vec!["application/openmetrics-text", "text/plain", "*/*"]
+    );
+    assert_eq!(items[0].param("version"), Some("1.0.0"));
+    assert_eq!(items[1].q, 0.5);
+}
+
+#[test]
+fn test_quality_list_keeps_order_for_equal_q() {
+    let items = quality_list("gzip, br, zstd;q=1.0");
+    let values: Vec<&str> = items.iter().map(|i| i.value.as_str()).collect();
+    assert_eq!(values, vec!["gzip", "br", "zstd"]);
+}
+
+#[test]
+fn test_quality_list_lowercases_and_skips_empty() {
+    let items = quality_list("GZIP;Q=0, , identity");
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].value, "identity");
+    assert_eq!(items[1].value, "gzip");
+    assert_eq!(items[1].q, 0.0);
+}
+
+#[test]
+fn test_quality_list_invalid_q() {
+    let items = quality_list("text/plain;q=abc");
+    assert_eq!(items[0].q, 0.0);
+}
@@ -0,0 +1,57 @@
+//! Minimal protobuf wire-format writer.
+//!
+//! Push sinks only need to emit a handful of fixed message types
+//! (remote_write `WriteRequest`, OTLP `ExportMetricsServiceRequest`), so they
+//! are encoded by hand instead of pulling in a code generator.
+
+/// Wire type for varint fields.
+const VARINT: u64 = 0;
+/// Wire type for 64-bit fields (`double`, `fixed64`).
+const FIXED64: u64 = 1;
+/// Wire type for length-delimited fields (strings, bytes, messages).
+const LEN: u64 = 2;
+
+/// Writes a base-128 varint.
+pub fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_key(out: &mut Vec<u8>, field: u64, wire_type: u64) {
+    write_varint(out, (field << 3) | wire_type);
+}
+
+/// Writes a length-delimited field (string, bytes or embedded message).
+pub fn write_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_key(out, field, LEN);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Writes a string field, skipping empty values (proto3 default).
+pub fn write_string(out: &mut Vec<u8>, field: u64, value: &str) {
+    if !value.is_empty() {
+        write_bytes(out, field, value.as_bytes());
+    }
+}
+
+/// Writes an `int64` / `uint64` / enum / bool field as varint.
+pub fn write_uint(out: &mut Vec<u8>, field: u64, value: u64) {
+    write_key(out, field, VARINT);
+    write_varint(out, value);
+}
+
+/// Writes a `double` field.
+pub fn write_double(out: &mut Vec<u8>, field: u64, value: f64) {
+    write_key(out, field, FIXED64);
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Writes a `fixed64` field.
+pub fn write_fixed64(out: &mut Vec<u8>, field: u64, value: u64) {
+    write_key(out, field, FIXED64);
+    out.extend_from_slice(&value.to_le_bytes());
+}